@@ -123,7 +123,10 @@ pub fn create_benchmark_extrinsic(
 		)),
 		frame_system::CheckNonce::<runtime::Runtime>::from(nonce),
 		frame_system::CheckWeight::<runtime::Runtime>::new(),
-		pallet_transaction_payment::ChargeTransactionPayment::<runtime::Runtime>::from(0),
+		sugarfunge_sponsor::ChargeSponsored::<runtime::Runtime, _>::from(
+			None,
+			sugarfunge_fee::ChargeAssetTxPayment::<runtime::Runtime>::from(0, None),
+		),
 	);
 
 	let raw_payload = runtime::SignedPayload::from_raw(
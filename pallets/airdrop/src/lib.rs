@@ -0,0 +1,408 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{dispatch::DispatchResult, ensure, traits::Get, PalletId};
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{AccountIdConversion, Hash},
+    DispatchError, RuntimeDebug,
+};
+use sugarfunge_primitives::Balance;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub type DistributionId = u32;
+
+/// Prefix hashed with leaves so they cannot be mistaken for inner nodes
+const LEAF_PREFIX: u8 = 0;
+/// Prefix hashed with the two children of an inner node
+const NODE_PREFIX: u8 = 1;
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum DistributionSource {
+    /// Claims mint new assets of the class
+    Mint,
+    /// Claims transfer assets funded to the distribution pool
+    Pool,
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Distribution<AccountId, ClassId, Hash, BlockNumber> {
+    pub creator: AccountId,
+    pub class_id: ClassId,
+    /// Root of the tree of `(account, class_id, asset_id, amount)` leaves
+    pub merkle_root: Hash,
+    /// Last block claims are accepted in
+    pub expiry: BlockNumber,
+    pub source: DistributionSource,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+    use frame_system::pallet_prelude::*;
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config + sugarfunge_asset::Config {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+
+        /// Max number of hashes in a claim proof
+        #[pallet::constant]
+        type MaxProofLength: Get<u32>;
+    }
+
+    pub type DistributionOf<T> = Distribution<
+        <T as frame_system::Config>::AccountId,
+        <T as sugarfunge_asset::Config>::ClassId,
+        <T as frame_system::Config>::Hash,
+        BlockNumberFor<T>,
+    >;
+
+    pub type ProofOf<T> =
+        BoundedVec<<T as frame_system::Config>::Hash, <T as Config>::MaxProofLength>;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::storage]
+    #[pallet::getter(fn next_distribution_id)]
+    pub(super) type NextDistributionId<T: Config> = StorageValue<_, DistributionId, ValueQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn distributions)]
+    pub(super) type Distributions<T: Config> =
+        StorageMap<_, Blake2_128Concat, DistributionId, DistributionOf<T>>;
+
+    /// Bitmap of claimed leaves, 128 leaf indices per word
+    #[pallet::storage]
+    pub(super) type Claimed<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, DistributionId, Twox64Concat, u32, u128, ValueQuery>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        Created {
+            distribution_id: DistributionId,
+            creator: T::AccountId,
+            class_id: T::ClassId,
+            merkle_root: T::Hash,
+            expiry: BlockNumberFor<T>,
+            source: DistributionSource,
+        },
+        Funded {
+            distribution_id: DistributionId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        },
+        Claimed {
+            distribution_id: DistributionId,
+            who: T::AccountId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        },
+        Reclaimed {
+            distribution_id: DistributionId,
+            creator: T::AccountId,
+        },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        NoPermission,
+        InvalidDistribution,
+        InvalidExpiry,
+        InvalidProof,
+        AlreadyClaimed,
+        DistributionExpired,
+        DistributionNotExpired,
+        NotPoolDistribution,
+        NumOverflow,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Publish the root of a tree of claimable amounts of the class
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn create(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            merkle_root: T::Hash,
+            expiry: BlockNumberFor<T>,
+            source: DistributionSource,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_create(&who, class_id, merkle_root, expiry, source)?;
+
+            Ok(().into())
+        }
+
+        /// Move assets of the creator to the pool of the distribution
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn fund(
+            origin: OriginFor<T>,
+            distribution_id: DistributionId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_fund(&who, distribution_id, asset_id, amount)?;
+
+            Ok(().into())
+        }
+
+        /// Claim the leaf at `index` for the caller
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn claim(
+            origin: OriginFor<T>,
+            distribution_id: DistributionId,
+            index: u32,
+            asset_id: T::AssetId,
+            amount: Balance,
+            proof: ProofOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_claim(&who, distribution_id, index, asset_id, amount, &proof)?;
+
+            Ok(().into())
+        }
+
+        /// Close an expired distribution and return the unclaimed pool to the creator
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn reclaim(
+            origin: OriginFor<T>,
+            distribution_id: DistributionId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_reclaim(&who, distribution_id)?;
+
+            Ok(().into())
+        }
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Account holding the assets of a pool distribution
+    pub fn pool_account(distribution_id: DistributionId) -> T::AccountId {
+        <T as Config>::PalletId::get().into_sub_account_truncating(distribution_id)
+    }
+
+    pub fn leaf_hash(
+        account: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+        amount: Balance,
+    ) -> T::Hash {
+        T::Hashing::hash_of(&(LEAF_PREFIX, account, class_id, asset_id, amount))
+    }
+
+    pub fn node_hash(left: &T::Hash, right: &T::Hash) -> T::Hash {
+        T::Hashing::hash_of(&(NODE_PREFIX, left, right))
+    }
+
+    /// Whether `proof` links the leaf at `index` to `root`, siblings ordered from the leaf up
+    pub fn verify_proof(root: &T::Hash, leaf: T::Hash, index: u32, proof: &[T::Hash]) -> bool {
+        let mut hash = leaf;
+        let mut position = index;
+        for sibling in proof {
+            hash = if position % 2 == 0 {
+                Self::node_hash(&hash, sibling)
+            } else {
+                Self::node_hash(sibling, &hash)
+            };
+            position /= 2;
+        }
+        // Index bits beyond the depth of the proof would alias other leaves
+        position == 0 && hash == *root
+    }
+
+    pub fn is_claimed(distribution_id: DistributionId, index: u32) -> bool {
+        Claimed::<T>::get(distribution_id, index / 128) & (1u128 << (index % 128)) != 0
+    }
+
+    pub fn do_create(
+        who: &T::AccountId,
+        class_id: T::ClassId,
+        merkle_root: T::Hash,
+        expiry: BlockNumberFor<T>,
+        source: DistributionSource,
+    ) -> DispatchResult {
+        ensure!(
+            sugarfunge_asset::Pallet::<T>::account_is_owner(who, class_id),
+            Error::<T>::NoPermission
+        );
+        ensure!(
+            expiry >= frame_system::Pallet::<T>::block_number(),
+            Error::<T>::InvalidExpiry
+        );
+
+        let distribution_id =
+            NextDistributionId::<T>::try_mutate(|id| -> Result<DistributionId, DispatchError> {
+                let current_id = *id;
+                *id = id.checked_add(1).ok_or(Error::<T>::NumOverflow)?;
+                Ok(current_id)
+            })?;
+
+        Distributions::<T>::insert(
+            distribution_id,
+            Distribution {
+                creator: who.clone(),
+                class_id,
+                merkle_root,
+                expiry,
+                source,
+            },
+        );
+
+        Self::deposit_event(Event::Created {
+            distribution_id,
+            creator: who.clone(),
+            class_id,
+            merkle_root,
+            expiry,
+            source,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_fund(
+        who: &T::AccountId,
+        distribution_id: DistributionId,
+        asset_id: T::AssetId,
+        amount: Balance,
+    ) -> DispatchResult {
+        let distribution =
+            Distributions::<T>::get(distribution_id).ok_or(Error::<T>::InvalidDistribution)?;
+        ensure!(distribution.creator == *who, Error::<T>::NoPermission);
+        ensure!(
+            distribution.source == DistributionSource::Pool,
+            Error::<T>::NotPoolDistribution
+        );
+
+        sugarfunge_asset::Pallet::<T>::do_transfer_from(
+            who,
+            who,
+            &Self::pool_account(distribution_id),
+            distribution.class_id,
+            asset_id,
+            amount,
+        )?;
+
+        Self::deposit_event(Event::Funded {
+            distribution_id,
+            asset_id,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_claim(
+        who: &T::AccountId,
+        distribution_id: DistributionId,
+        index: u32,
+        asset_id: T::AssetId,
+        amount: Balance,
+        proof: &[T::Hash],
+    ) -> DispatchResult {
+        let distribution =
+            Distributions::<T>::get(distribution_id).ok_or(Error::<T>::InvalidDistribution)?;
+        ensure!(
+            frame_system::Pallet::<T>::block_number() <= distribution.expiry,
+            Error::<T>::DistributionExpired
+        );
+        ensure!(
+            !Self::is_claimed(distribution_id, index),
+            Error::<T>::AlreadyClaimed
+        );
+
+        let leaf = Self::leaf_hash(who, distribution.class_id, asset_id, amount);
+        ensure!(
+            Self::verify_proof(&distribution.merkle_root, leaf, index, proof),
+            Error::<T>::InvalidProof
+        );
+
+        Claimed::<T>::mutate(distribution_id, index / 128, |word| {
+            *word |= 1u128 << (index % 128)
+        });
+
+        match distribution.source {
+            DistributionSource::Mint => sugarfunge_asset::Pallet::<T>::do_mint(
+                &distribution.creator,
+                who,
+                distribution.class_id,
+                asset_id,
+                amount,
+            )?,
+            DistributionSource::Pool => {
+                let pool = Self::pool_account(distribution_id);
+                sugarfunge_asset::Pallet::<T>::do_custody_transfer_from(
+                    &pool,
+                    &pool,
+                    who,
+                    distribution.class_id,
+                    asset_id,
+                    amount,
+                )?
+            }
+        }
+
+        Self::deposit_event(Event::Claimed {
+            distribution_id,
+            who: who.clone(),
+            asset_id,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_reclaim(who: &T::AccountId, distribution_id: DistributionId) -> DispatchResult {
+        let distribution =
+            Distributions::<T>::get(distribution_id).ok_or(Error::<T>::InvalidDistribution)?;
+        ensure!(distribution.creator == *who, Error::<T>::NoPermission);
+        ensure!(
+            frame_system::Pallet::<T>::block_number() > distribution.expiry,
+            Error::<T>::DistributionNotExpired
+        );
+
+        if distribution.source == DistributionSource::Pool {
+            let pool = Self::pool_account(distribution_id);
+            for (class_id, asset_id, balance) in
+                sugarfunge_asset::Pallet::<T>::balances_of_owner(&pool)?
+            {
+                sugarfunge_asset::Pallet::<T>::do_custody_transfer_from(
+                    &pool, &pool, who, class_id, asset_id, balance,
+                )?;
+            }
+        }
+
+        Distributions::<T>::remove(distribution_id);
+        let _ = Claimed::<T>::clear_prefix(distribution_id, u32::MAX, None);
+
+        Self::deposit_event(Event::Reclaimed {
+            distribution_id,
+            creator: who.clone(),
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use super::*;
+use crate as sugarfunge_airdrop;
+use frame_support::{
+    parameter_types,
+    traits::{ConstU128, ConstU16, ConstU32, ConstU64, Everything},
+    PalletId,
+};
+use sp_core::H256;
+use sp_runtime::{
+    testing::{TestSignature, UintAuthorityId},
+    traits::{BlakeTwo256, IdentityLookup},
+    BuildStorage,
+};
+use sugarfunge_primitives::Balance;
+
+pub const MILLICENTS: Balance = 10_000_000_000_000;
+pub const CENTS: Balance = 1_000 * MILLICENTS;
+pub const DOLLARS: Balance = 100 * CENTS;
+
+parameter_types! {
+    pub const CreateAssetClassDeposit: Balance = 500 * MILLICENTS;
+    pub const AirdropModuleId: PalletId = PalletId(*b"sug/drop");
+}
+
+impl frame_system::Config for Test {
+    type BaseCallFilter = Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Nonce = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Block = frame_system::mocking::MockBlock<Test>;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ConstU16<42>;
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+    type RuntimeTask = ();
+}
+
+impl pallet_balances::Config for Test {
+    type Balance = Balance;
+    type RuntimeEvent = RuntimeEvent;
+    type DustRemoval = ();
+    type ExistentialDeposit = ConstU128<500>;
+    type AccountStore = System;
+    type WeightInfo = pallet_balances::weights::SubstrateWeight<Test>;
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type FreezeIdentifier = ();
+    type MaxHolds = ();
+    type MaxFreezes = ();
+    type RuntimeHoldReason = ();
+    type RuntimeFreezeReason = ();
+}
+
+impl sugarfunge_asset::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type CreateAssetClassDeposit = CreateAssetClassDeposit;
+    type Currency = Balances;
+    type AssetId = u64;
+    type ClassId = u64;
+    type MaxClassMetadata = ConstU32<1>;
+    type MaxAssetMetadata = ConstU32<1>;
+    type MaxVestingSchedules = ConstU32<2>;
+    type MaxMultiItems = ConstU32<4>;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
+}
+
+impl sugarfunge_airdrop::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type PalletId = AirdropModuleId;
+    type MaxProofLength = ConstU32<8>;
+}
+
+frame_support::construct_runtime!(
+    pub enum Test
+    {
+        System: frame_system,
+        Balances: pallet_balances,
+        Asset: sugarfunge_asset,
+        Airdrop: sugarfunge_airdrop,
+    }
+);
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut t = frame_system::GenesisConfig::<Test>::default()
+        .build_storage()
+        .unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(1, 1000000 * DOLLARS), (2, 1000000 * DOLLARS)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+    t.into()
+}
@@ -0,0 +1,241 @@
+use crate::{mock::*, DistributionSource, Error, ProofOf};
+use frame_support::{assert_noop, assert_ok, bounded_vec};
+use sp_core::H256;
+use sugarfunge_asset::{GiftPolicy, Royalty};
+
+fn last_event() -> RuntimeEvent {
+    frame_system::Pallet::<Test>::events()
+        .pop()
+        .expect("Event expected")
+        .event
+}
+
+/// Leaves of the test tree as `(account, asset_id, amount)` of class 1000
+const LEAVES: [(u64, u64, u128); 4] = [(2, 1, 100), (3, 1, 50), (4, 2, 10), (2, 2, 5)];
+
+/// Root of the test tree and the proof of each leaf
+fn tree() -> (H256, Vec<Vec<H256>>) {
+    let leaves: Vec<H256> = LEAVES
+        .iter()
+        .map(|(account, asset_id, amount)| Airdrop::leaf_hash(account, 1000, *asset_id, *amount))
+        .collect();
+    let left = Airdrop::node_hash(&leaves[0], &leaves[1]);
+    let right = Airdrop::node_hash(&leaves[2], &leaves[3]);
+    let root = Airdrop::node_hash(&left, &right);
+    let proofs = vec![
+        vec![leaves[1], right],
+        vec![leaves[0], right],
+        vec![leaves[3], left],
+        vec![leaves[2], left],
+    ];
+    (root, proofs)
+}
+
+fn proof(index: usize) -> ProofOf<Test> {
+    tree().1[index].clone().try_into().unwrap()
+}
+
+pub fn before_distribution(source: DistributionSource) {
+    System::set_block_number(1);
+
+    assert_ok!(Asset::do_create_class(&1, &1, 1000, bounded_vec![0]));
+    assert_ok!(Airdrop::create(
+        RuntimeOrigin::signed(1),
+        1000,
+        tree().0,
+        10,
+        source
+    ));
+}
+
+#[test]
+fn create_distribution_fails() {
+    new_test_ext().execute_with(|| {
+        before_distribution(DistributionSource::Mint);
+
+        assert_noop!(
+            Airdrop::create(
+                RuntimeOrigin::signed(2),
+                1000,
+                tree().0,
+                10,
+                DistributionSource::Mint
+            ),
+            Error::<Test>::NoPermission
+        );
+        System::set_block_number(11);
+        assert_noop!(
+            Airdrop::create(
+                RuntimeOrigin::signed(1),
+                1000,
+                tree().0,
+                10,
+                DistributionSource::Mint
+            ),
+            Error::<Test>::InvalidExpiry
+        );
+        assert_noop!(
+            Airdrop::fund(RuntimeOrigin::signed(1), 0, 1, 100),
+            Error::<Test>::NotPoolDistribution
+        );
+    })
+}
+
+#[test]
+fn claims_mint_assets() {
+    new_test_ext().execute_with(|| {
+        before_distribution(DistributionSource::Mint);
+
+        assert_ok!(Airdrop::claim(
+            RuntimeOrigin::signed(2),
+            0,
+            0,
+            1,
+            100,
+            proof(0)
+        ));
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Airdrop(crate::Event::Claimed {
+                distribution_id: 0,
+                who: 2,
+                asset_id: 1,
+                amount: 100,
+            })
+        );
+        assert_eq!(Asset::balance_of(&2, 1000, 1), 100);
+        assert!(Airdrop::is_claimed(0, 0));
+
+        assert_noop!(
+            Airdrop::claim(RuntimeOrigin::signed(2), 0, 0, 1, 100, proof(0)),
+            Error::<Test>::AlreadyClaimed
+        );
+        // Leaves can only be claimed by their account, amount and index
+        assert_noop!(
+            Airdrop::claim(RuntimeOrigin::signed(4), 0, 1, 1, 50, proof(1)),
+            Error::<Test>::InvalidProof
+        );
+        assert_noop!(
+            Airdrop::claim(RuntimeOrigin::signed(3), 0, 1, 1, 51, proof(1)),
+            Error::<Test>::InvalidProof
+        );
+        assert_noop!(
+            Airdrop::claim(RuntimeOrigin::signed(2), 0, 7, 2, 5, proof(3)),
+            Error::<Test>::InvalidProof
+        );
+
+        assert_ok!(Airdrop::claim(
+            RuntimeOrigin::signed(2),
+            0,
+            3,
+            2,
+            5,
+            proof(3)
+        ));
+        assert_eq!(Asset::balance_of(&2, 1000, 2), 5);
+
+        System::set_block_number(11);
+        assert_noop!(
+            Airdrop::claim(RuntimeOrigin::signed(3), 0, 1, 1, 50, proof(1)),
+            Error::<Test>::DistributionExpired
+        );
+    })
+}
+
+#[test]
+fn claims_transfer_from_pool() {
+    new_test_ext().execute_with(|| {
+        before_distribution(DistributionSource::Pool);
+
+        assert_ok!(Asset::do_mint(&1, &1, 1000, 1, 150));
+        assert_ok!(Asset::do_mint(&1, &1, 1000, 2, 15));
+        assert_noop!(
+            Airdrop::fund(RuntimeOrigin::signed(2), 0, 1, 150),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(Airdrop::fund(RuntimeOrigin::signed(1), 0, 1, 150));
+        assert_ok!(Airdrop::fund(RuntimeOrigin::signed(1), 0, 2, 15));
+
+        assert_ok!(Airdrop::claim(
+            RuntimeOrigin::signed(3),
+            0,
+            1,
+            1,
+            50,
+            proof(1)
+        ));
+        assert_eq!(Asset::balance_of(&3, 1000, 1), 50);
+        assert_eq!(Asset::balance_of(&Airdrop::pool_account(0), 1000, 1), 100);
+
+        assert_noop!(
+            Airdrop::reclaim(RuntimeOrigin::signed(1), 0),
+            Error::<Test>::DistributionNotExpired
+        );
+    })
+}
+
+#[test]
+fn unclaimed_pool_is_reclaimed_after_expiry() {
+    new_test_ext().execute_with(|| {
+        before_distribution(DistributionSource::Pool);
+
+        assert_ok!(Asset::do_mint(&1, &1, 1000, 1, 150));
+        assert_ok!(Airdrop::fund(RuntimeOrigin::signed(1), 0, 1, 150));
+        assert_ok!(Airdrop::claim(
+            RuntimeOrigin::signed(2),
+            0,
+            0,
+            1,
+            100,
+            proof(0)
+        ));
+
+        System::set_block_number(11);
+        assert_noop!(
+            Airdrop::reclaim(RuntimeOrigin::signed(2), 0),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(Airdrop::reclaim(RuntimeOrigin::signed(1), 0));
+
+        assert_eq!(Asset::balance_of(&1, 1000, 1), 50);
+        assert_eq!(Asset::balance_of(&Airdrop::pool_account(0), 1000, 1), 0);
+        assert!(!Airdrop::is_claimed(0, 0));
+        assert_noop!(
+            Airdrop::claim(RuntimeOrigin::signed(3), 0, 1, 1, 50, proof(1)),
+            Error::<Test>::InvalidDistribution
+        );
+    })
+}
+
+#[test]
+fn gift_forbidden_pool_is_claimed_and_reclaimed() {
+    new_test_ext().execute_with(|| {
+        before_distribution(DistributionSource::Pool);
+        assert_ok!(Asset::set_royalty(
+            RuntimeOrigin::signed(1),
+            1000,
+            None,
+            Some(Royalty {
+                beneficiary: 1,
+                bps: 250,
+                gifts: GiftPolicy::Forbidden,
+            })
+        ));
+
+        assert_ok!(Asset::do_mint(&1, &1, 1000, 1, 150));
+        assert_ok!(Airdrop::fund(RuntimeOrigin::signed(1), 0, 1, 150));
+        assert_ok!(Airdrop::claim(
+            RuntimeOrigin::signed(2),
+            0,
+            0,
+            1,
+            100,
+            proof(0)
+        ));
+        assert_eq!(Asset::balance_of(&2, 1000, 1), 100);
+
+        System::set_block_number(11);
+        assert_ok!(Airdrop::reclaim(RuntimeOrigin::signed(1), 0));
+        assert_eq!(Asset::balance_of(&1, 1000, 1), 50);
+    })
+}
@@ -0,0 +1,17 @@
+//! Runtime API for checking asset metadata.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+
+sp_api::decl_runtime_apis! {
+    pub trait AssetApi<ClassId, AssetId>
+    where
+        ClassId: Codec,
+        AssetId: Codec,
+    {
+        /// Whether `content` is the metadata of the class, or of one asset when `asset_id` is given
+        fn verify_metadata(class_id: ClassId, asset_id: Option<AssetId>, content: Vec<u8>) -> bool;
+    }
+}
@@ -211,5 +211,59 @@ benchmarks! {
 
   }: _(RawOrigin::Signed(caller.clone()), CLASS_ID.into(), ASSET_ID.into(), BoundedVec::try_from([1].to_vec()).unwrap())
 
+  multi_transfer {
+    let n in 1 .. T::MaxMultiItems::get();
+
+    //Fund the account
+    let caller: T::AccountId = whitelisted_caller();
+    let sugar_value: BalanceOf<T> = BASE_SUGAR.saturated_into::<BalanceOf<T>>();
+    T::Currency::make_free_balance_be(&caller, sugar_value);
+
+    //Create the class and mint the assets to be sent
+    let _ = Assets::<T>::create_class(RawOrigin::Signed(caller.clone()).into(), caller.clone(), CLASS_ID.into(), BoundedVec::try_from([0].to_vec()).unwrap());
+    let _ = Assets::<T>::mint(RawOrigin::Signed(caller.clone()).into(), caller.clone(), CLASS_ID.into(), ASSET_ID.into(), MINT_VALUE.into());
+
+    //Record checkpoints of both sides of every transfer
+    let _ = Assets::<T>::enable_checkpoints(RawOrigin::Signed(caller.clone()).into(), CLASS_ID.into());
+
+    //Send to a different account on each item
+    let items: MultiItemsOf<T> = (0..n)
+      .map(|i| (account("1", i, SEED), CLASS_ID.into(), ASSET_ID.into(), TRANSFER_VALUE))
+      .collect::<Vec<_>>()
+      .try_into()
+      .unwrap();
+
+  }: _(RawOrigin::Signed(caller.clone()), items)
+  verify {
+        assert_event::<T>(Event::MultiTransferred { from: caller, items: n }.into());
+  }
+
+  multi_mint {
+    let n in 1 .. T::MaxMultiItems::get();
+
+    //Fund the account
+    let caller: T::AccountId = whitelisted_caller();
+    let sugar_value: BalanceOf<T> = BASE_SUGAR.saturated_into::<BalanceOf<T>>();
+    T::Currency::make_free_balance_be(&caller, sugar_value);
+
+    //Create the class before minting
+    let _ = Assets::<T>::create_class(RawOrigin::Signed(caller.clone()).into(), caller.clone(), CLASS_ID.into(), BoundedVec::try_from([0].to_vec()).unwrap());
+
+    //Record checkpoints and freeze the metadata of every minted asset
+    let _ = Assets::<T>::enable_checkpoints(RawOrigin::Signed(caller.clone()).into(), CLASS_ID.into());
+    let _ = Assets::<T>::freeze_metadata_on_mint(RawOrigin::Signed(caller.clone()).into(), CLASS_ID.into());
+
+    //Mint a different asset to a different account on each item
+    let items: MultiItemsOf<T> = (0..n)
+      .map(|i| (account("1", i, SEED), CLASS_ID.into(), (ASSET_ID + i as u64).into(), MINT_VALUE))
+      .collect::<Vec<_>>()
+      .try_into()
+      .unwrap();
+
+  }: _(RawOrigin::Signed(caller.clone()), items)
+  verify {
+        assert_event::<T>(Event::MultiMinted { who: caller, items: n }.into());
+  }
+
   impl_benchmark_test_suite!(Assets, crate::mock::new_test_ext(), crate::mock::Test);
 }
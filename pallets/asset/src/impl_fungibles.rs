@@ -0,0 +1,367 @@
+//! Implementations of the `fungibles` traits over `(ClassId, AssetId)` pairs
+
+use super::*;
+use frame_support::{
+    storage::KeyPrefixIterator,
+    traits::tokens::{
+        fungibles::{self, Dust},
+        DepositConsequence, Fortitude, Preservation, Provenance, WithdrawConsequence,
+    },
+};
+
+impl<T: Config> fungibles::Inspect<T::AccountId> for Pallet<T> {
+    type AssetId = (T::ClassId, T::AssetId);
+    type Balance = Balance;
+
+    fn total_issuance((class_id, asset_id): Self::AssetId) -> Balance {
+        Supply::<T>::get(class_id, asset_id)
+    }
+
+    fn minimum_balance(_: Self::AssetId) -> Balance {
+        Zero::zero()
+    }
+
+    fn total_balance((class_id, asset_id): Self::AssetId, who: &T::AccountId) -> Balance {
+        Balances::<T>::get((who, class_id, asset_id))
+    }
+
+    fn balance((class_id, asset_id): Self::AssetId, who: &T::AccountId) -> Balance {
+        Balances::<T>::get((who, class_id, asset_id))
+    }
+
+    fn reducible_balance(
+        (class_id, asset_id): Self::AssetId,
+        who: &T::AccountId,
+        _preservation: Preservation,
+        _force: Fortitude,
+    ) -> Balance {
+        if Self::is_frozen(who, class_id, asset_id) {
+            return Zero::zero();
+        }
+        Self::spendable_balance_of(who, class_id, asset_id)
+    }
+
+    fn can_deposit(
+        (class_id, asset_id): Self::AssetId,
+        who: &T::AccountId,
+        amount: Balance,
+        provenance: Provenance,
+    ) -> DepositConsequence {
+        if !Self::class_exists(class_id) {
+            return DepositConsequence::UnknownAsset;
+        }
+        if Destroying::<T>::contains_key(class_id, asset_id) {
+            return DepositConsequence::UnknownAsset;
+        }
+        if provenance == Provenance::Minted {
+            match Supply::<T>::get(class_id, asset_id).checked_add(amount) {
+                None => return DepositConsequence::Overflow,
+                Some(supply) if supply > 1 && Self::is_unique(class_id) => {
+                    return DepositConsequence::Blocked
+                }
+                _ => {}
+            }
+        }
+        if Balances::<T>::get((who, class_id, asset_id))
+            .checked_add(amount)
+            .is_none()
+        {
+            return DepositConsequence::Overflow;
+        }
+        DepositConsequence::Success
+    }
+
+    fn can_withdraw(
+        (class_id, asset_id): Self::AssetId,
+        who: &T::AccountId,
+        amount: Balance,
+    ) -> WithdrawConsequence<Balance> {
+        if !Self::class_exists(class_id) {
+            return WithdrawConsequence::UnknownAsset;
+        }
+        if Balances::<T>::get((who, class_id, asset_id)) < amount {
+            return WithdrawConsequence::BalanceLow;
+        }
+        if Self::is_frozen(who, class_id, asset_id)
+            || Self::spendable_balance_of(who, class_id, asset_id) < amount
+        {
+            return WithdrawConsequence::Frozen;
+        }
+        WithdrawConsequence::Success
+    }
+
+    fn asset_exists((class_id, _): Self::AssetId) -> bool {
+        Self::class_exists(class_id)
+    }
+}
+
+impl<T: Config> fungibles::Unbalanced<T::AccountId> for Pallet<T> {
+    // There is no existential deposit, so no dust is ever created
+    fn handle_dust(_: Dust<T::AccountId, Self>) {}
+
+    fn write_balance(
+        (class_id, asset_id): Self::AssetId,
+        who: &T::AccountId,
+        amount: Balance,
+    ) -> Result<Option<Balance>, DispatchError> {
+        let balance = Balances::<T>::get((who, class_id, asset_id));
+        if amount > balance {
+            Self::add_balance_to(who, class_id, asset_id, amount - balance)?;
+        } else if amount < balance {
+            Self::remove_balance_from(who, class_id, asset_id, balance - amount)?;
+        }
+        Ok(None)
+    }
+
+    fn set_total_issuance((class_id, asset_id): Self::AssetId, amount: Balance) {
+        if amount.is_zero() {
+            Supply::<T>::remove(class_id, asset_id);
+        } else {
+            Supply::<T>::insert(class_id, asset_id, amount);
+        }
+    }
+}
+
+impl<T: Config> fungibles::Mutate<T::AccountId> for Pallet<T> {
+    fn done_mint_into((class_id, asset_id): Self::AssetId, who: &T::AccountId, amount: Balance) {
+        if !amount.is_zero() {
+            Self::freeze_metadata_if_minted(class_id, asset_id);
+        }
+        Self::deposit_event(Event::Mint {
+            who: who.clone(),
+            to: who.clone(),
+            class_id,
+            asset_id,
+            amount,
+        });
+    }
+
+    fn done_burn_from((class_id, asset_id): Self::AssetId, who: &T::AccountId, amount: Balance) {
+        Self::deposit_event(Event::Burn {
+            who: who.clone(),
+            from: who.clone(),
+            class_id,
+            asset_id,
+            amount,
+        });
+    }
+
+    /// Transfers through `do_transfer_from`, so the gift policy of the asset applies
+    fn transfer(
+        (class_id, asset_id): Self::AssetId,
+        source: &T::AccountId,
+        dest: &T::AccountId,
+        amount: Balance,
+        _preservation: Preservation,
+    ) -> Result<Balance, DispatchError> {
+        Self::do_transfer_from(source, source, dest, class_id, asset_id, amount)?;
+        Ok(amount)
+    }
+
+    fn done_transfer(
+        (class_id, asset_id): Self::AssetId,
+        source: &T::AccountId,
+        dest: &T::AccountId,
+        amount: Balance,
+    ) {
+        Self::deposit_event(Event::Transferred {
+            who: source.clone(),
+            from: source.clone(),
+            to: dest.clone(),
+            class_id,
+            asset_id,
+            amount,
+        });
+    }
+}
+
+impl<T: Config> fungibles::Create<T::AccountId> for Pallet<T> {
+    /// Create the asset, and its class owned by `admin` if it does not exist yet
+    ///
+    /// Balances have no existential deposit, so `is_sufficient` and `min_balance` are ignored.
+    fn create(
+        (class_id, asset_id): Self::AssetId,
+        admin: T::AccountId,
+        _is_sufficient: bool,
+        _min_balance: Balance,
+    ) -> DispatchResult {
+        if !Self::class_exists(class_id) {
+            Self::do_create_class(&admin, &admin, class_id, Default::default())?;
+        }
+        Self::do_create_asset(&admin, class_id, asset_id, Default::default())
+    }
+}
+
+impl<T: Config> fungibles::Destroy<T::AccountId> for Pallet<T> {
+    fn start_destroy(
+        (class_id, asset_id): Self::AssetId,
+        maybe_check_owner: Option<T::AccountId>,
+    ) -> DispatchResult {
+        match maybe_check_owner {
+            Some(who) => Self::maybe_check_owner(&who, class_id)?,
+            None => ensure!(Self::class_exists(class_id), Error::<T>::InvalidClassId),
+        }
+        ensure!(
+            !Destroying::<T>::contains_key(class_id, asset_id),
+            Error::<T>::AssetDestroying
+        );
+
+        Destroying::<T>::insert(class_id, asset_id, DestroyCursor::default());
+
+        Ok(())
+    }
+
+    /// Visit up to `max_items` keys of the balances, user rights and checkpoints of all
+    /// assets, removing the entries of the asset. Returns the number of entries removed.
+    ///
+    /// These maps are keyed by account first, so each call resumes from where the last one
+    /// stopped until every map is scanned.
+    fn destroy_accounts(
+        (class_id, asset_id): Self::AssetId,
+        max_items: u32,
+    ) -> Result<u32, DispatchError> {
+        let mut cursor =
+            Destroying::<T>::get(class_id, asset_id).ok_or(Error::<T>::NotDestroying)?;
+
+        let mut visited = 0u32;
+        let mut removed = 0u32;
+        while cursor.stage != DestroyStage::Done && visited < max_items {
+            let budget = max_items - visited;
+            let start = cursor.last_key.take().map(|key| key.into_inner());
+            let iter = match (cursor.stage, start) {
+                (DestroyStage::Balances, Some(key)) => Balances::<T>::iter_keys_from(key),
+                (DestroyStage::Balances, None) => Balances::<T>::iter_keys(),
+                (DestroyStage::UserRights, Some(key)) => UserRights::<T>::iter_keys_from(key),
+                (DestroyStage::UserRights, None) => UserRights::<T>::iter_keys(),
+                (DestroyStage::Checkpoints, Some(key)) => CheckpointCount::<T>::iter_keys_from(key),
+                (DestroyStage::Checkpoints, None) => CheckpointCount::<T>::iter_keys(),
+                (DestroyStage::Done, _) => break,
+            };
+            let (accounts, scanned, last_key) =
+                Self::scan_keys(iter, budget, |(who, class, asset)| {
+                    (class == class_id && asset == asset_id).then_some(who)
+                });
+            visited = visited.saturating_add(scanned);
+
+            for who in accounts.iter() {
+                let key = (who, class_id, asset_id);
+                match cursor.stage {
+                    DestroyStage::Balances => {
+                        let balance = Balances::<T>::take(key);
+                        let _ = Holds::<T>::clear_prefix(key, u32::MAX, None);
+                        Held::<T>::remove(key);
+                        Lent::<T>::remove(key);
+                        Vesting::<T>::remove(key);
+                        VestingLocked::<T>::remove(key);
+                        Self::decrease_supply(class_id, asset_id, balance);
+                    }
+                    DestroyStage::UserRights => UserRights::<T>::remove(key),
+                    DestroyStage::Checkpoints => {
+                        CheckpointCount::<T>::remove(key);
+                        let _ = Checkpoints::<T>::clear_prefix(key, u32::MAX, None);
+                    }
+                    DestroyStage::Done => {}
+                }
+            }
+            removed = removed.saturating_add(accounts.len() as u32);
+
+            match last_key {
+                Some(key) => {
+                    cursor.last_key = Some(key.try_into().map_err(|_| Error::<T>::NumOverflow)?)
+                }
+                None => {
+                    cursor.stage = match cursor.stage {
+                        DestroyStage::Balances => DestroyStage::UserRights,
+                        DestroyStage::UserRights => DestroyStage::Checkpoints,
+                        _ => DestroyStage::Done,
+                    }
+                }
+            }
+        }
+
+        Destroying::<T>::insert(class_id, asset_id, cursor);
+
+        Ok(removed)
+    }
+
+    /// Visit up to `max_items` approvals of all assets, removing those of the asset.
+    /// Returns the number of approvals removed.
+    fn destroy_approvals(
+        (class_id, asset_id): Self::AssetId,
+        max_items: u32,
+    ) -> Result<u32, DispatchError> {
+        let mut cursor =
+            Destroying::<T>::get(class_id, asset_id).ok_or(Error::<T>::NotDestroying)?;
+        if cursor.approvals_cleared {
+            return Ok(0);
+        }
+
+        let iter = match cursor.last_approval.take() {
+            Some(key) => Approvals::<T>::iter_keys_from(key.into_inner()),
+            None => Approvals::<T>::iter_keys(),
+        };
+        let (approvals, _, last_key) =
+            Self::scan_keys(iter, max_items, |(owner, spender, class, asset)| {
+                (class == class_id && asset == asset_id).then_some((owner, spender))
+            });
+
+        for (owner, spender) in approvals.iter() {
+            Approvals::<T>::remove((owner, spender, class_id, asset_id));
+        }
+
+        match last_key {
+            Some(key) => {
+                cursor.last_approval = Some(key.try_into().map_err(|_| Error::<T>::NumOverflow)?)
+            }
+            None => cursor.approvals_cleared = true,
+        }
+        Destroying::<T>::insert(class_id, asset_id, cursor);
+
+        Ok(approvals.len() as u32)
+    }
+
+    fn finish_destroy((class_id, asset_id): Self::AssetId) -> DispatchResult {
+        let cursor = Destroying::<T>::get(class_id, asset_id).ok_or(Error::<T>::NotDestroying)?;
+        ensure!(
+            cursor.stage == DestroyStage::Done && cursor.approvals_cleared,
+            Error::<T>::InUse
+        );
+
+        if Assets::<T>::take(class_id, asset_id).is_some() {
+            AssetCount::<T>::mutate(class_id, |count| *count = count.saturating_sub(1));
+        }
+        Supply::<T>::remove(class_id, asset_id);
+        UniqueOwners::<T>::remove(class_id, asset_id);
+        FrozenAssets::<T>::remove(class_id, asset_id);
+        AssetRoyalties::<T>::remove(class_id, asset_id);
+        FrozenAssetMetadata::<T>::remove(class_id, asset_id);
+        Destroying::<T>::remove(class_id, asset_id);
+
+        Self::deposit_event(Event::AssetDestroyed { class_id, asset_id });
+
+        Ok(())
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Visits up to `max_items` keys, returning what `select` picks from them, the number of
+    /// keys visited and the raw key to resume after, `None` once the map is exhausted
+    fn scan_keys<K, R>(
+        mut iter: KeyPrefixIterator<K>,
+        max_items: u32,
+        mut select: impl FnMut(K) -> Option<R>,
+    ) -> (Vec<R>, u32, Option<Vec<u8>>) {
+        let mut selected = Vec::new();
+        let mut visited = 0u32;
+        while visited < max_items {
+            match iter.next() {
+                Some(key) => {
+                    visited += 1;
+                    selected.extend(select(key));
+                }
+                None => return (selected, visited, None),
+            }
+        }
+        (selected, visited, Some(iter.last_raw_key().to_vec()))
+    }
+}
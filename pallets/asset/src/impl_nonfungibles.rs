@@ -0,0 +1,30 @@
+//! Implementation of `nonfungibles_v2::Inspect` for classes made unique
+
+use super::*;
+use frame_support::traits::tokens::nonfungibles_v2;
+
+impl<T: Config> nonfungibles_v2::Inspect<T::AccountId> for Pallet<T> {
+    type ItemId = T::AssetId;
+    type CollectionId = T::ClassId;
+
+    fn owner(collection: &T::ClassId, item: &T::AssetId) -> Option<T::AccountId> {
+        UniqueOwners::<T>::get(collection, item)
+    }
+
+    fn collection_owner(collection: &T::ClassId) -> Option<T::AccountId> {
+        if !Self::is_unique(*collection) {
+            return None;
+        }
+        Classes::<T>::get(collection).map(|class| class.owner)
+    }
+
+    fn can_transfer(collection: &T::ClassId, item: &T::AssetId) -> bool {
+        match UniqueOwners::<T>::get(collection, item) {
+            Some(owner) => {
+                !Self::is_frozen(&owner, *collection, *item)
+                    && !Self::spendable_balance_of(&owner, *collection, *item).is_zero()
+            }
+            None => false,
+        }
+    }
+}
@@ -6,18 +6,23 @@ use frame_support::pallet_prelude::DispatchError;
 use frame_support::{
     dispatch::DispatchResult,
     ensure,
-    traits::{Currency, Get, ReservableCurrency},
+    traits::{ConstU32, Currency, Get, ReservableCurrency},
     BoundedVec,
 };
+use frame_system::pallet_prelude::BlockNumberFor;
 use scale_info::TypeInfo;
+use sp_core::H256;
 use sp_runtime::{
-    traits::{AtLeast32BitUnsigned, One, Zero},
-    RuntimeDebug,
+    traits::{
+        AtLeast32BitUnsigned, IdentifyAccount, One, Saturating, UniqueSaturatedInto, Verify, Zero,
+    },
+    PerThing, Permill, RuntimeDebug,
 };
 use sp_std::fmt::Debug;
+use sp_std::ops::Deref;
 use sp_std::prelude::*;
 use sugarfunge_primitives::Balance;
-pub use weights::WeightInfo;
+pub use weights::{SubstrateWeight, WeightInfo};
 
 pub use pallet::*;
 
@@ -30,6 +35,10 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+mod impl_fungibles;
+mod impl_nonfungibles;
+pub mod migrations;
+
 pub mod weights;
 
 pub trait AssetInterface {
@@ -70,6 +79,7 @@ pub trait AssetInterface {
         + Into<u128>
         + Ord
         + Zero;
+    type BlockNumber;
 
     fn create_class(
         who: Self::AccountId,
@@ -121,22 +131,281 @@ pub trait AssetInterface {
     fn balances_of_owner(
         owner: Self::AccountId,
     ) -> Result<Vec<(Self::ClassId, Self::AssetId, Self::Balance)>, DispatchError>;
+
+    /// Balance at the end of `block`, `None` unless the class records checkpoints since then
+    fn balance_at(
+        owner: Self::AccountId,
+        class_id: Self::ClassId,
+        asset_id: Self::AssetId,
+        block: Self::BlockNumber,
+    ) -> Option<Self::Balance>;
+
+    /// Balance that can be moved out of the account: free minus held and locked
+    fn spendable_balance_of(
+        owner: Self::AccountId,
+        class_id: Self::ClassId,
+        asset_id: Self::AssetId,
+    ) -> Self::Balance;
+
+    fn balance_on_hold(
+        reason: HoldReason,
+        owner: Self::AccountId,
+        class_id: Self::ClassId,
+        asset_id: Self::AssetId,
+    ) -> Self::Balance;
+
+    /// Keep `amount` in the account of `who`, unspendable until released
+    fn hold(
+        reason: HoldReason,
+        who: Self::AccountId,
+        class_id: Self::ClassId,
+        asset_id: Self::AssetId,
+        amount: Self::Balance,
+    ) -> DispatchResult;
+
+    fn release(
+        reason: HoldReason,
+        who: Self::AccountId,
+        class_id: Self::ClassId,
+        asset_id: Self::AssetId,
+        amount: Self::Balance,
+    ) -> DispatchResult;
+
+    /// Release `amount` held from `from` and transfer it to `to`
+    fn transfer_on_hold(
+        reason: HoldReason,
+        from: Self::AccountId,
+        to: Self::AccountId,
+        class_id: Self::ClassId,
+        asset_id: Self::AssetId,
+        amount: Self::Balance,
+    ) -> DispatchResult;
+
+    fn is_frozen(owner: Self::AccountId, class_id: Self::ClassId, asset_id: Self::AssetId) -> bool;
 }
 
+/// Identifier of the pallet or feature holding part of a balance
+pub type HoldReason = [u8; 8];
+
+/// Prefix of the payload signed for a `TransferPermit`, followed by the genesis hash of the
+/// chain and the permit
+pub const TRANSFER_PERMIT: [u8; 8] = *b"sug/xfer";
+/// Prefix of the payload signed for an `ApprovalPermit`, followed by the genesis hash of the
+/// chain and the permit
+pub const APPROVAL_PERMIT: [u8; 8] = *b"sug/appr";
+
 type BalanceOf<T> =
     <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
 #[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
 pub struct Class<AccountId, ClassMetadataOf> {
     owner: AccountId,
-    metadata: ClassMetadataOf,
+    metadata: Metadata<ClassMetadataOf>,
 }
 
 #[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
 pub struct Asset<ClassId, AccountId, AssetMetadataOf> {
     class_id: ClassId,
     creator: AccountId,
-    metadata: AssetMetadataOf,
+    metadata: Metadata<AssetMetadataOf>,
+}
+
+/// Latest schema version a `MetadataRecord` can declare
+pub const METADATA_SCHEMA_VERSION: u16 = 1;
+
+pub type MetadataUri = BoundedVec<u8, ConstU32<256>>;
+pub type MetadataMime = BoundedVec<u8, ConstU32<64>>;
+
+/// Off-chain content described by its location and hash
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct MetadataRecord {
+    /// Location of the content, such as `ipfs://<cid>`
+    pub uri: MetadataUri,
+    /// Blake2-256 hash of the content
+    pub content_hash: H256,
+    /// Media type of the content, such as `application/json`
+    pub mime: MetadataMime,
+    /// Version of the schema the content follows
+    pub schema_version: u16,
+}
+
+impl MetadataRecord {
+    /// Whether the record has a `scheme://` uri, a `type/subtype` mime, a hash and a known schema
+    pub fn is_valid(&self) -> bool {
+        let printable = |bytes: &[u8]| bytes.iter().all(|b| b.is_ascii_graphic());
+
+        let uri_valid = match self.uri.windows(3).position(|w| w == b"://") {
+            Some(at) => {
+                at > 0
+                    && self.uri[..at]
+                        .iter()
+                        .all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(b))
+                    && self.uri.len() > at + 3
+                    && printable(&self.uri)
+            }
+            None => false,
+        };
+        let mime_valid = match self.mime.iter().position(|b| *b == b'/') {
+            Some(at) => {
+                at > 0
+                    && self.mime.len() > at + 1
+                    && !self.mime[at + 1..].contains(&b'/')
+                    && printable(&self.mime)
+            }
+            None => false,
+        };
+
+        uri_valid
+            && mime_valid
+            && !self.content_hash.is_zero()
+            && (1..=METADATA_SCHEMA_VERSION).contains(&self.schema_version)
+    }
+
+    /// Whether `content` hashes to the content hash of the record
+    pub fn verify(&self, content: &[u8]) -> bool {
+        H256(sp_io::hashing::blake2_256(content)) == self.content_hash
+    }
+}
+
+/// Metadata of a class or asset, either opaque bytes or a content-addressed record
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum Metadata<Raw> {
+    Raw(Raw),
+    Record(MetadataRecord),
+}
+
+impl<Raw: Deref<Target = Vec<u8>>> Metadata<Raw> {
+    /// Whether `content` is the metadata: equal to the raw bytes or matching the record hash
+    pub fn verify(&self, content: &[u8]) -> bool {
+        match self {
+            Metadata::Raw(raw) => raw.as_slice() == content,
+            Metadata::Record(record) => record.verify(content),
+        }
+    }
+}
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Checkpoint<BlockNumber> {
+    /// Block the balance was recorded at
+    pub block: BlockNumber,
+    /// Balance at the end of the block
+    pub balance: Balance,
+}
+
+/// Raw storage key an asset destruction resumes scanning after
+pub type DestroyCursorKey = BoundedVec<u8, ConstU32<256>>;
+
+/// Account keyed map an asset destruction is scanning for entries of the asset
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum DestroyStage {
+    Balances,
+    UserRights,
+    Checkpoints,
+    Done,
+}
+
+/// Progress of `fungibles::Destroy::destroy_accounts` through the account keyed maps
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct DestroyCursor {
+    /// Map being scanned
+    pub stage: DestroyStage,
+    /// Last key visited in the map, `None` to start from its first key
+    pub last_key: Option<DestroyCursorKey>,
+    /// Last approval visited by `destroy_approvals`, `None` to start from the first one
+    pub last_approval: Option<DestroyCursorKey>,
+    /// Whether every approval of the asset is removed
+    pub approvals_cleared: bool,
+}
+
+impl Default for DestroyCursor {
+    fn default() -> Self {
+        DestroyCursor {
+            stage: DestroyStage::Balances,
+            last_key: None,
+            last_approval: None,
+            approvals_cleared: false,
+        }
+    }
+}
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct VestingSchedule<BlockNumber> {
+    /// Amount locked when the schedule was created
+    pub locked: Balance,
+    /// Amount unlocked every block after `start`
+    pub per_block: Balance,
+    /// Block linear unlocking starts from
+    pub start: BlockNumber,
+    /// Nothing unlocks before this block
+    pub cliff: BlockNumber,
+}
+
+impl<BlockNumber: AtLeast32BitUnsigned + Copy> VestingSchedule<BlockNumber> {
+    /// Amount still locked at block `now`
+    pub fn locked_at(&self, now: BlockNumber) -> Balance {
+        if now < self.cliff {
+            return self.locked;
+        }
+        let vested_blocks: Balance = now.saturating_sub(self.start).unique_saturated_into();
+        self.locked
+            .saturating_sub(vested_blocks.saturating_mul(self.per_block))
+    }
+}
+
+/// Largest royalty rate, the whole price
+pub const MAX_ROYALTY_BPS: u16 = 10_000;
+
+/// Whether assets under a royalty can move without a payment
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum GiftPolicy {
+    /// Holders may give the assets away royalty free
+    Exempt,
+    /// Assets only change hands through sales paying the royalty
+    Forbidden,
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Royalty<AccountId> {
+    /// Account receiving the royalty
+    pub beneficiary: AccountId,
+    /// Share of the sale price in basis points
+    pub bps: u16,
+    pub gifts: GiftPolicy,
+}
+
+impl<AccountId> Royalty<AccountId> {
+    /// Royalty owed on a sale for `price`
+    pub fn amount_of(&self, price: Balance) -> Balance {
+        Permill::from_parts(u32::from(self.bps) * 100).mul_floor(price)
+    }
+}
+
+/// Transfer signed off chain by `from` and submitted by a relayer
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct TransferPermit<AccountId, ClassId, AssetId, BlockNumber> {
+    pub from: AccountId,
+    pub to: AccountId,
+    pub class_id: ClassId,
+    pub asset_id: AssetId,
+    pub amount: Balance,
+    /// Must match the permit nonce of `from`
+    pub nonce: u64,
+    /// Last block the permit can be used in
+    pub deadline: BlockNumber,
+}
+
+/// Approval signed off chain by `from` and submitted by a relayer
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct ApprovalPermit<AccountId, ClassId, AssetId, BlockNumber> {
+    pub from: AccountId,
+    pub spender: AccountId,
+    pub class_id: ClassId,
+    pub asset_id: AssetId,
+    pub amount: Balance,
+    /// Must match the permit nonce of `from`
+    pub nonce: u64,
+    /// Last block the permit can be used in
+    pub deadline: BlockNumber,
 }
 
 #[frame_support::pallet]
@@ -182,6 +451,20 @@ pub mod pallet {
 
         #[pallet::constant]
         type MaxAssetMetadata: Get<u32>;
+
+        /// Max number of vesting schedules per account and asset
+        #[pallet::constant]
+        type MaxVestingSchedules: Get<u32>;
+
+        /// Max number of items in a `multi_transfer` or `multi_mint`
+        #[pallet::constant]
+        type MaxMultiItems: Get<u32>;
+
+        /// Signature of off-chain permits
+        type OffchainSignature: Verify<Signer = Self::OffchainPublic> + Parameter;
+
+        /// Key that signs off-chain permits on behalf of an account
+        type OffchainPublic: IdentifyAccount<AccountId = Self::AccountId>;
     }
 
     pub type ClassMetadataOf<T> = BoundedVec<u8, <T as Config>::MaxClassMetadata>;
@@ -191,7 +474,34 @@ pub mod pallet {
     pub type AssetOf<T> =
         Asset<<T as Config>::ClassId, <T as frame_system::Config>::AccountId, AssetMetadataOf<T>>;
 
+    pub type TransferPermitOf<T> = TransferPermit<
+        <T as frame_system::Config>::AccountId,
+        <T as Config>::ClassId,
+        <T as Config>::AssetId,
+        BlockNumberFor<T>,
+    >;
+    pub type ApprovalPermitOf<T> = ApprovalPermit<
+        <T as frame_system::Config>::AccountId,
+        <T as Config>::ClassId,
+        <T as Config>::AssetId,
+        BlockNumberFor<T>,
+    >;
+
+    /// Items of a `multi_transfer` or `multi_mint` as `(to, class_id, asset_id, amount)`
+    pub type MultiItemsOf<T> = BoundedVec<
+        (
+            <T as frame_system::Config>::AccountId,
+            <T as Config>::ClassId,
+            <T as Config>::AssetId,
+            Balance,
+        ),
+        <T as Config>::MaxMultiItems,
+    >;
+
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     #[pallet::storage]
@@ -208,6 +518,50 @@ pub mod pallet {
     pub(super) type AssetCount<T: Config> =
         StorageMap<_, Blake2_128Concat, T::ClassId, u64, ValueQuery>;
 
+    /// Amount of an asset in existence
+    #[pallet::storage]
+    #[pallet::getter(fn supply)]
+    pub(super) type Supply<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::ClassId,
+        Blake2_128Concat,
+        T::AssetId,
+        Balance,
+        ValueQuery,
+    >;
+
+    /// Classes whose assets have a supply of at most one
+    #[pallet::storage]
+    #[pallet::getter(fn unique_classes)]
+    pub(super) type UniqueClasses<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::ClassId, (), OptionQuery>;
+
+    /// Holder of each asset of a unique class
+    #[pallet::storage]
+    #[pallet::getter(fn unique_owner)]
+    pub(super) type UniqueOwners<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::ClassId,
+        Blake2_128Concat,
+        T::AssetId,
+        T::AccountId,
+        OptionQuery,
+    >;
+
+    /// Assets being destroyed through `fungibles::Destroy` and how far their accounts are cleared
+    #[pallet::storage]
+    pub(super) type Destroying<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::ClassId,
+        Blake2_128Concat,
+        T::AssetId,
+        DestroyCursor,
+        OptionQuery,
+    >;
+
     #[pallet::storage]
     #[pallet::getter(fn balances)]
     pub(super) type Balances<T: Config> = StorageNMap<
@@ -221,6 +575,213 @@ pub mod pallet {
         ValueQuery,
     >;
 
+    /// Amount of an owner's balance currently lent out and locked from transfer
+    #[pallet::storage]
+    #[pallet::getter(fn lent)]
+    pub(super) type Lent<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+        ),
+        Balance,
+        ValueQuery,
+    >;
+
+    /// Amount of assets an account may use without owning them
+    #[pallet::storage]
+    #[pallet::getter(fn user_rights)]
+    pub(super) type UserRights<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+        ),
+        Balance,
+        ValueQuery,
+    >;
+
+    /// Vesting schedules of an account's balance
+    #[pallet::storage]
+    #[pallet::getter(fn vesting)]
+    pub(super) type Vesting<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+        ),
+        BoundedVec<VestingSchedule<BlockNumberFor<T>>, T::MaxVestingSchedules>,
+        ValueQuery,
+    >;
+
+    /// Amount of an account's balance locked by vesting schedules, updated on `vest`
+    #[pallet::storage]
+    #[pallet::getter(fn vesting_locked)]
+    pub(super) type VestingLocked<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+        ),
+        Balance,
+        ValueQuery,
+    >;
+
+    /// Amount of an account's balance held for a reason
+    #[pallet::storage]
+    #[pallet::getter(fn holds)]
+    pub(super) type Holds<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+            NMapKey<Blake2_128Concat, HoldReason>,
+        ),
+        Balance,
+        ValueQuery,
+    >;
+
+    /// Total amount of an account's balance held for any reason
+    #[pallet::storage]
+    #[pallet::getter(fn held)]
+    pub(super) type Held<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+        ),
+        Balance,
+        ValueQuery,
+    >;
+
+    /// Accounts whose assets of a class cannot be moved
+    #[pallet::storage]
+    #[pallet::getter(fn frozen_accounts)]
+    pub(super) type FrozenAccounts<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::ClassId,
+        Blake2_128Concat,
+        T::AccountId,
+        (),
+        OptionQuery,
+    >;
+
+    /// Assets that cannot be moved by any account
+    #[pallet::storage]
+    #[pallet::getter(fn frozen_assets)]
+    pub(super) type FrozenAssets<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::ClassId,
+        Blake2_128Concat,
+        T::AssetId,
+        (),
+        OptionQuery,
+    >;
+
+    /// Amount of an owner's balance a spender may transfer
+    #[pallet::storage]
+    #[pallet::getter(fn allowance)]
+    pub(super) type Approvals<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+        ),
+        Balance,
+        ValueQuery,
+    >;
+
+    /// Royalty paid on sales of any asset of a class
+    #[pallet::storage]
+    #[pallet::getter(fn class_royalties)]
+    pub(super) type ClassRoyalties<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::ClassId, Royalty<T::AccountId>>;
+
+    /// Royalty paid on sales of an asset, overriding the class royalty
+    #[pallet::storage]
+    #[pallet::getter(fn asset_royalties)]
+    pub(super) type AssetRoyalties<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::ClassId,
+        Blake2_128Concat,
+        T::AssetId,
+        Royalty<T::AccountId>,
+    >;
+
+    /// Classes whose metadata can no longer be updated
+    #[pallet::storage]
+    #[pallet::getter(fn frozen_class_metadata)]
+    pub(super) type FrozenClassMetadata<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::ClassId, (), OptionQuery>;
+
+    /// Assets whose metadata can no longer be updated
+    #[pallet::storage]
+    #[pallet::getter(fn frozen_asset_metadata)]
+    pub(super) type FrozenAssetMetadata<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::ClassId,
+        Blake2_128Concat,
+        T::AssetId,
+        (),
+        OptionQuery,
+    >;
+
+    /// Classes whose asset metadata freezes once the asset is minted
+    #[pallet::storage]
+    #[pallet::getter(fn freezes_metadata_on_mint)]
+    pub(super) type FreezeMetadataOnMint<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::ClassId, (), OptionQuery>;
+
+    /// Nonce the next off-chain permit of an account must use
+    #[pallet::storage]
+    #[pallet::getter(fn permit_nonce)]
+    pub(super) type PermitNonces<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, u64, ValueQuery>;
+
+    /// Classes recording balance checkpoints and the block recording started
+    #[pallet::storage]
+    #[pallet::getter(fn checkpointed_classes)]
+    pub(super) type CheckpointedClasses<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::ClassId, BlockNumberFor<T>>;
+
+    /// Number of balance checkpoints of an account
+    #[pallet::storage]
+    pub(super) type CheckpointCount<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+        ),
+        u32,
+        ValueQuery,
+    >;
+
+    /// Balance checkpoints of an account, ordered by block
+    #[pallet::storage]
+    pub(super) type Checkpoints<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+            NMapKey<Twox64Concat, u32>,
+        ),
+        Checkpoint<BlockNumberFor<T>>,
+    >;
+
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
@@ -289,21 +850,148 @@ pub mod pallet {
             class_id: T::ClassId,
             approved: bool,
         },
-    }
-
-    #[pallet::error]
-    pub enum Error<T> {
-        Unknown,
-        InUse,
-        InvalidAssetId,
-        InsufficientBalance,
-        NumOverflow,
-        InvalidArrayLength,
-        Overflow,
-        InvalidClassId,
-        NoPermission,
-        ClassNotFound,
+        Lent {
+            owner: T::AccountId,
+            user: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        },
+        Reclaimed {
+            owner: T::AccountId,
+            user: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        },
+        CheckpointsEnabled {
+            class_id: T::ClassId,
+            who: T::AccountId,
+        },
+        VestingScheduleAdded {
+            from: T::AccountId,
+            to: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            schedule: VestingSchedule<BlockNumberFor<T>>,
+        },
+        Vested {
+            who: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            locked: Balance,
+        },
+        Held {
+            reason: HoldReason,
+            who: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        },
+        Released {
+            reason: HoldReason,
+            who: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        },
+        AccountFrozen {
+            class_id: T::ClassId,
+            who: T::AccountId,
+        },
+        AccountThawed {
+            class_id: T::ClassId,
+            who: T::AccountId,
+        },
+        AssetFrozen {
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        },
+        AssetThawed {
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        },
+        ClassMadeUnique {
+            class_id: T::ClassId,
+            who: T::AccountId,
+        },
+        AssetDestroyed {
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        },
+        Approved {
+            owner: T::AccountId,
+            spender: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        },
+        RoyaltySet {
+            class_id: T::ClassId,
+            asset_id: Option<T::AssetId>,
+            royalty: Option<Royalty<T::AccountId>>,
+        },
+        MultiTransferred {
+            from: T::AccountId,
+            items: u32,
+        },
+        MultiMinted {
+            who: T::AccountId,
+            items: u32,
+        },
+        MetadataRecordSet {
+            who: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: Option<T::AssetId>,
+            record: MetadataRecord,
+        },
+        ClassMetadataFrozen {
+            class_id: T::ClassId,
+            who: T::AccountId,
+        },
+        AssetMetadataFrozen {
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        },
+        MetadataFreezeOnMintEnabled {
+            class_id: T::ClassId,
+            who: T::AccountId,
+        },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        Unknown,
+        InUse,
+        InvalidAssetId,
+        InsufficientBalance,
+        NumOverflow,
+        InvalidArrayLength,
+        Overflow,
+        InvalidClassId,
+        NoPermission,
+        ClassNotFound,
         AssetNotFound,
+        BalanceLocked,
+        CheckpointsAlreadyEnabled,
+        InvalidVestingSchedule,
+        TooManyVestingSchedules,
+        NotVesting,
+        InsufficientHeld,
+        Frozen,
+        ClassNotEmpty,
+        UniqueSupplyExceeded,
+        AssetDestroying,
+        NotDestroying,
+        InsufficientAllowance,
+        PermitExpired,
+        InvalidPermitNonce,
+        InvalidPermitSignature,
+        InvalidRoyalty,
+        GiftsForbidden,
+        InvalidMetadata,
+        MetadataFrozen,
+        FreezeOnMintAlreadyEnabled,
     }
 
     #[pallet::hooks]
@@ -474,204 +1162,1467 @@ pub mod pallet {
 
             Ok(().into())
         }
-    }
 
-    impl<T: Config> Pallet<T> {
-        pub fn do_create_class(
-            who: &T::AccountId,
-            owner: &T::AccountId,
+        #[pallet::call_index(10)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn enable_checkpoints(
+            origin: OriginFor<T>,
             class_id: T::ClassId,
-            metadata: ClassMetadataOf<T>,
-        ) -> DispatchResult {
-            ensure!(
-                !Classes::<T>::contains_key(class_id),
-                Error::<T>::InvalidClassId
-            );
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
 
-            let deposit = T::CreateAssetClassDeposit::get();
-            T::Currency::reserve(&who, deposit.clone())?;
+            Self::do_enable_checkpoints(&who, class_id)?;
 
-            let class = ClassOf::<T> {
-                owner: owner.clone(),
-                metadata,
-            };
+            Ok(().into())
+        }
 
-            Classes::<T>::insert(class_id, class);
+        /// Transfer assets to `to`, locked under a vesting schedule
+        #[pallet::call_index(11)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn vested_transfer(
+            origin: OriginFor<T>,
+            to: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            schedule: VestingSchedule<BlockNumberFor<T>>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
 
-            Self::deposit_event(Event::ClassCreated {
-                class_id,
-                who: who.clone(),
-            });
+            Self::do_vested_transfer(&who, &to, class_id, asset_id, schedule)?;
 
-            Ok(())
+            Ok(().into())
         }
 
-        pub fn do_create_asset(
-            who: &T::AccountId,
+        /// Unlock the vested part of the caller's assets
+        #[pallet::call_index(12)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn vest(
+            origin: OriginFor<T>,
             class_id: T::ClassId,
             asset_id: T::AssetId,
-            metadata: AssetMetadataOf<T>,
-        ) -> DispatchResult {
-            Self::maybe_check_owner(who, class_id)?;
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
 
-            ensure!(
-                !Assets::<T>::contains_key(class_id, asset_id),
-                Error::<T>::InUse
-            );
+            Self::do_vest(&who, class_id, asset_id)?;
 
-            Assets::<T>::insert(
-                class_id,
-                asset_id,
-                AssetOf::<T> {
-                    class_id,
-                    creator: who.clone(),
-                    metadata,
-                },
-            );
+            Ok(().into())
+        }
 
-            AssetCount::<T>::try_mutate(class_id, |count| -> DispatchResult {
-                *count = count
-                    .checked_add(One::one())
-                    .ok_or(Error::<T>::NumOverflow)?;
-                Ok(())
-            })?;
+        /// Stop `account` from moving any asset of the class
+        #[pallet::call_index(13)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn freeze_account(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            account: T::AccountId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
 
-            Self::deposit_event(Event::AssetCreated {
-                class_id,
-                asset_id,
-                who: who.clone(),
-            });
+            Self::do_freeze_account(&who, class_id, &account)?;
 
-            Ok(())
+            Ok(().into())
         }
 
-        pub fn do_update_class_metadata(
-            who: &T::AccountId,
+        #[pallet::call_index(14)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn thaw_account(
+            origin: OriginFor<T>,
             class_id: T::ClassId,
-            metadata: ClassMetadataOf<T>,
-        ) -> DispatchResult {
-            Self::maybe_check_owner(who, class_id)?;
-            ensure!(
-                Classes::<T>::contains_key(class_id),
-                Error::<T>::InvalidClassId
-            );
-            Classes::<T>::try_mutate(class_id, |class| -> DispatchResult {
-                if let Some(class) = class {
-                    class.metadata = metadata.clone();
-                }
-                Ok(())
-            })?;
-            Ok(())
+            account: T::AccountId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_thaw_account(&who, class_id, &account)?;
+
+            Ok(().into())
         }
 
-        pub fn do_update_asset_metadata(
-            who: &T::AccountId,
+        /// Stop every account from moving the asset
+        #[pallet::call_index(15)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn freeze_asset(
+            origin: OriginFor<T>,
             class_id: T::ClassId,
             asset_id: T::AssetId,
-            metadata: AssetMetadataOf<T>,
-        ) -> DispatchResult {
-            Self::maybe_check_owner(who, class_id)?;
-            ensure!(
-                Assets::<T>::contains_key(class_id, asset_id),
-                Error::<T>::InvalidAssetId
-            );
-            Assets::<T>::try_mutate(class_id, asset_id, |asset| -> DispatchResult {
-                if let Some(asset) = asset {
-                    asset.metadata = metadata.clone();
-                }
-                Ok(())
-            })?;
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
 
-            Self::deposit_event(Event::AssetMetadataUpdated {
-                class_id,
-                asset_id,
-                who: who.clone(),
-                metadata: metadata.to_vec(),
-            });
+            Self::do_freeze_asset(&who, class_id, asset_id)?;
 
-            Ok(())
+            Ok(().into())
         }
 
-        pub fn class_exists(class_id: T::ClassId) -> bool {
-            Classes::<T>::contains_key(class_id)
+        #[pallet::call_index(16)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn thaw_asset(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_thaw_asset(&who, class_id, asset_id)?;
+
+            Ok(().into())
         }
 
-        pub fn asset_exists(class_id: T::ClassId, asset_id: T::AssetId) -> bool {
-            Assets::<T>::contains_key(class_id, asset_id)
+        /// Limit every asset of an empty class to a supply of one
+        #[pallet::call_index(17)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn make_class_unique(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_make_class_unique(&who, class_id)?;
+
+            Ok(().into())
+        }
+
+        /// Allow `spender` to transfer up to `amount` of the caller's assets
+        #[pallet::call_index(18)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn approve(
+            origin: OriginFor<T>,
+            spender: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_approve(&who, &spender, class_id, asset_id, amount)?;
+
+            Ok(().into())
+        }
+
+        /// Transfer assets of `from` within the allowance it approved for the caller
+        #[pallet::call_index(19)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn transfer_approved(
+            origin: OriginFor<T>,
+            from: T::AccountId,
+            to: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_transfer_approved(&who, &from, &to, class_id, asset_id, amount)?;
+
+            Ok(().into())
+        }
+
+        /// Submit a transfer signed off chain by its sender
+        #[pallet::call_index(20)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn transfer_with_permit(
+            origin: OriginFor<T>,
+            permit: TransferPermitOf<T>,
+            signature: T::OffchainSignature,
+        ) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
+
+            Self::do_transfer_with_permit(permit, &signature)?;
+
+            Ok(().into())
+        }
+
+        /// Submit an approval signed off chain by the owner of the assets
+        #[pallet::call_index(21)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn approve_with_permit(
+            origin: OriginFor<T>,
+            permit: ApprovalPermitOf<T>,
+            signature: T::OffchainSignature,
+        ) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
+
+            Self::do_approve_with_permit(permit, &signature)?;
+
+            Ok(().into())
+        }
+
+        /// Set or clear the royalty of the class, or of one asset when `asset_id` is given
+        #[pallet::call_index(22)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn set_royalty(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            asset_id: Option<T::AssetId>,
+            royalty: Option<Royalty<T::AccountId>>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_set_royalty(&who, class_id, asset_id, royalty)?;
+
+            Ok(().into())
+        }
+
+        /// Send assets of any class to many accounts at once
+        #[pallet::call_index(23)]
+        #[pallet::weight(SubstrateWeight::<T>::multi_transfer(items.len() as u32))]
+        pub fn multi_transfer(
+            origin: OriginFor<T>,
+            items: MultiItemsOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_multi_transfer(&who, items)?;
+
+            Ok(().into())
+        }
+
+        /// Mint assets of classes owned by the caller to many accounts at once
+        #[pallet::call_index(24)]
+        #[pallet::weight(SubstrateWeight::<T>::multi_mint(items.len() as u32))]
+        pub fn multi_mint(
+            origin: OriginFor<T>,
+            items: MultiItemsOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_multi_mint(&who, items)?;
+
+            Ok(().into())
+        }
+
+        /// Describe the class, or one asset when `asset_id` is given, with a metadata record
+        #[pallet::call_index(25)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn set_metadata_record(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            asset_id: Option<T::AssetId>,
+            record: MetadataRecord,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_set_metadata_record(&who, class_id, asset_id, record)?;
+
+            Ok(().into())
+        }
+
+        /// Make the class metadata immutable, irreversibly
+        #[pallet::call_index(26)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn freeze_class_metadata(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_freeze_class_metadata(&who, class_id)?;
+
+            Ok(().into())
+        }
+
+        /// Make the metadata of an asset immutable, irreversibly
+        #[pallet::call_index(27)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn freeze_asset_metadata(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_freeze_asset_metadata(&who, class_id, asset_id)?;
+
+            Ok(().into())
+        }
+
+        /// Make the metadata of every asset of the class immutable once it is minted, irreversibly
+        #[pallet::call_index(28)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn freeze_metadata_on_mint(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_freeze_metadata_on_mint(&who, class_id)?;
+
+            Ok(().into())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        pub fn do_create_class(
+            who: &T::AccountId,
+            owner: &T::AccountId,
+            class_id: T::ClassId,
+            metadata: ClassMetadataOf<T>,
+        ) -> DispatchResult {
+            ensure!(
+                !Classes::<T>::contains_key(class_id),
+                Error::<T>::InvalidClassId
+            );
+
+            let deposit = T::CreateAssetClassDeposit::get();
+            T::Currency::reserve(&who, deposit.clone())?;
+
+            let class = ClassOf::<T> {
+                owner: owner.clone(),
+                metadata: Metadata::Raw(metadata),
+            };
+
+            Classes::<T>::insert(class_id, class);
+
+            Self::deposit_event(Event::ClassCreated {
+                class_id,
+                who: who.clone(),
+            });
+
+            Ok(())
+        }
+
+        pub fn do_create_asset(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            metadata: AssetMetadataOf<T>,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+
+            ensure!(
+                !Assets::<T>::contains_key(class_id, asset_id),
+                Error::<T>::InUse
+            );
+
+            Assets::<T>::insert(
+                class_id,
+                asset_id,
+                AssetOf::<T> {
+                    class_id,
+                    creator: who.clone(),
+                    metadata: Metadata::Raw(metadata),
+                },
+            );
+
+            AssetCount::<T>::try_mutate(class_id, |count| -> DispatchResult {
+                *count = count
+                    .checked_add(One::one())
+                    .ok_or(Error::<T>::NumOverflow)?;
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::AssetCreated {
+                class_id,
+                asset_id,
+                who: who.clone(),
+            });
+
+            Ok(())
+        }
+
+        pub fn do_update_class_metadata(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            metadata: ClassMetadataOf<T>,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+            ensure!(
+                Classes::<T>::contains_key(class_id),
+                Error::<T>::InvalidClassId
+            );
+            ensure!(
+                !Self::is_metadata_frozen(class_id, None),
+                Error::<T>::MetadataFrozen
+            );
+            Classes::<T>::try_mutate(class_id, |class| -> DispatchResult {
+                if let Some(class) = class {
+                    class.metadata = Metadata::Raw(metadata.clone());
+                }
+                Ok(())
+            })?;
+            Ok(())
+        }
+
+        pub fn do_update_asset_metadata(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            metadata: AssetMetadataOf<T>,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+            ensure!(
+                Assets::<T>::contains_key(class_id, asset_id),
+                Error::<T>::InvalidAssetId
+            );
+            ensure!(
+                !Self::is_metadata_frozen(class_id, Some(asset_id)),
+                Error::<T>::MetadataFrozen
+            );
+            Assets::<T>::try_mutate(class_id, asset_id, |asset| -> DispatchResult {
+                if let Some(asset) = asset {
+                    asset.metadata = Metadata::Raw(metadata.clone());
+                }
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::AssetMetadataUpdated {
+                class_id,
+                asset_id,
+                who: who.clone(),
+                metadata: metadata.to_vec(),
+            });
+
+            Ok(())
+        }
+
+        pub fn do_set_metadata_record(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: Option<T::AssetId>,
+            record: MetadataRecord,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+            ensure!(record.is_valid(), Error::<T>::InvalidMetadata);
+            ensure!(
+                !Self::is_metadata_frozen(class_id, asset_id),
+                Error::<T>::MetadataFrozen
+            );
+
+            match asset_id {
+                Some(asset_id) => {
+                    Assets::<T>::try_mutate(class_id, asset_id, |asset| -> DispatchResult {
+                        let asset = asset.as_mut().ok_or(Error::<T>::InvalidAssetId)?;
+                        asset.metadata = Metadata::Record(record.clone());
+                        Ok(())
+                    })?;
+                }
+                None => {
+                    Classes::<T>::try_mutate(class_id, |class| -> DispatchResult {
+                        let class = class.as_mut().ok_or(Error::<T>::InvalidClassId)?;
+                        class.metadata = Metadata::Record(record.clone());
+                        Ok(())
+                    })?;
+                }
+            }
+
+            Self::deposit_event(Event::MetadataRecordSet {
+                who: who.clone(),
+                class_id,
+                asset_id,
+                record,
+            });
+
+            Ok(())
+        }
+
+        pub fn do_freeze_class_metadata(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+            ensure!(
+                !FrozenClassMetadata::<T>::contains_key(class_id),
+                Error::<T>::MetadataFrozen
+            );
+
+            FrozenClassMetadata::<T>::insert(class_id, ());
+
+            Self::deposit_event(Event::ClassMetadataFrozen {
+                class_id,
+                who: who.clone(),
+            });
+
+            Ok(())
+        }
+
+        pub fn do_freeze_asset_metadata(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+            ensure!(
+                Assets::<T>::contains_key(class_id, asset_id),
+                Error::<T>::InvalidAssetId
+            );
+            ensure!(
+                !Self::is_metadata_frozen(class_id, Some(asset_id)),
+                Error::<T>::MetadataFrozen
+            );
+
+            FrozenAssetMetadata::<T>::insert(class_id, asset_id, ());
+
+            Self::deposit_event(Event::AssetMetadataFrozen { class_id, asset_id });
+
+            Ok(())
+        }
+
+        pub fn do_freeze_metadata_on_mint(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+            ensure!(
+                !FreezeMetadataOnMint::<T>::contains_key(class_id),
+                Error::<T>::FreezeOnMintAlreadyEnabled
+            );
+
+            FreezeMetadataOnMint::<T>::insert(class_id, ());
+
+            Self::deposit_event(Event::MetadataFreezeOnMintEnabled {
+                class_id,
+                who: who.clone(),
+            });
+
+            Ok(())
+        }
+
+        /// Whether the metadata of the class, or of one asset when `asset_id` is given, is immutable
+        ///
+        /// Assets of classes freezing on mint are frozen while they have supply, even if minted
+        /// before the policy was enabled. The supply of assets minted before it was tracked is
+        /// rebuilt by the version 2 storage migration.
+        pub fn is_metadata_frozen(class_id: T::ClassId, asset_id: Option<T::AssetId>) -> bool {
+            match asset_id {
+                Some(asset_id) => {
+                    FrozenAssetMetadata::<T>::contains_key(class_id, asset_id)
+                        || (FreezeMetadataOnMint::<T>::contains_key(class_id)
+                            && !Supply::<T>::get(class_id, asset_id).is_zero())
+                }
+                None => FrozenClassMetadata::<T>::contains_key(class_id),
+            }
+        }
+
+        /// Freeze the asset metadata on its first mint if the class policy asks for it
+        pub(crate) fn freeze_metadata_if_minted(class_id: T::ClassId, asset_id: T::AssetId) {
+            if FreezeMetadataOnMint::<T>::contains_key(class_id)
+                && !FrozenAssetMetadata::<T>::contains_key(class_id, asset_id)
+            {
+                FrozenAssetMetadata::<T>::insert(class_id, asset_id, ());
+
+                Self::deposit_event(Event::AssetMetadataFrozen { class_id, asset_id });
+            }
+        }
+
+        /// Whether `content` is the metadata of the class, or of one asset when `asset_id` is given
+        pub fn verify_metadata(
+            class_id: T::ClassId,
+            asset_id: Option<T::AssetId>,
+            content: &[u8],
+        ) -> bool {
+            match asset_id {
+                Some(asset_id) => Assets::<T>::get(class_id, asset_id)
+                    .map_or(false, |asset| asset.metadata.verify(content)),
+                None => Classes::<T>::get(class_id)
+                    .map_or(false, |class| class.metadata.verify(content)),
+            }
+        }
+
+        pub fn class_exists(class_id: T::ClassId) -> bool {
+            Classes::<T>::contains_key(class_id)
+        }
+
+        pub fn asset_exists(class_id: T::ClassId, asset_id: T::AssetId) -> bool {
+            Assets::<T>::contains_key(class_id, asset_id)
+        }
+
+        pub fn account_is_owner(account: &T::AccountId, class_id: T::ClassId) -> bool {
+            let value = Self::classes(class_id);
+            if let Some(class) = value {
+                if class.owner == account.clone() {
+                    return true;
+                }
+            }
+            return false;
+        }
+
+        pub fn do_mint(
+            who: &T::AccountId,
+            to: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResult {
+            Self::add_balance_to(to, class_id, asset_id, amount)?;
+            Self::increase_supply(class_id, asset_id, amount)?;
+
+            Self::deposit_event(Event::Mint {
+                who: who.clone(),
+                to: to.clone(),
+                class_id,
+                asset_id,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        pub fn do_batch_mint(
+            who: &T::AccountId,
+            to: &T::AccountId,
+            class_id: T::ClassId,
+            asset_ids: Vec<T::AssetId>,
+            amounts: Vec<Balance>,
+        ) -> DispatchResult {
+            ensure!(
+                asset_ids.len() == amounts.len(),
+                Error::<T>::InvalidArrayLength
+            );
+
+            let n = asset_ids.len();
+            for i in 0..n {
+                let asset_id = asset_ids[i];
+                let amount = amounts[i];
+                Self::add_balance_to(to, class_id, asset_id, amount)?;
+                Self::increase_supply(class_id, asset_id, amount)?;
+            }
+
+            Self::deposit_event(Event::BatchMint {
+                who: who.clone(),
+                to: to.clone(),
+                class_id,
+                asset_ids,
+                amounts,
+            });
+
+            Ok(())
+        }
+
+        pub fn do_burn(
+            who: &T::AccountId,
+            from: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResult {
+            Self::remove_balance_from(from, class_id, asset_id, amount)?;
+            Self::decrease_supply(class_id, asset_id, amount);
+
+            Self::deposit_event(Event::Burn {
+                who: who.clone(),
+                from: from.clone(),
+                class_id,
+                asset_id,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        pub fn do_batch_burn(
+            who: &T::AccountId,
+            from: &T::AccountId,
+            class_id: T::ClassId,
+            asset_ids: Vec<T::AssetId>,
+            amounts: Vec<Balance>,
+        ) -> DispatchResult {
+            ensure!(
+                asset_ids.len() == amounts.len(),
+                Error::<T>::InvalidArrayLength
+            );
+
+            let n = asset_ids.len();
+            for i in 0..n {
+                let asset_id = asset_ids[i];
+                let amount = amounts[i];
+
+                Self::remove_balance_from(from, class_id, asset_id, amount)?;
+                Self::decrease_supply(class_id, asset_id, amount);
+            }
+
+            Self::deposit_event(Event::BatchBurn {
+                who: who.clone(),
+                from: from.clone(),
+                class_id,
+                asset_ids,
+                amounts,
+            });
+
+            Ok(())
+        }
+
+        /// Transfer moved by `who`, which must own the class unless the gift policy of the
+        /// asset allows moving it without a sale
+        pub fn do_transfer_from(
+            who: &T::AccountId,
+            from: &T::AccountId,
+            to: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResult {
+            if from == to || amount == Zero::zero() {
+                return Ok(());
+            }
+
+            Self::ensure_gift_allowed_by(who, class_id, asset_id)?;
+            Self::move_from(who, from, to, class_id, asset_id, amount)
+        }
+
+        /// Transfer settling a sale that pays the royalty of the asset, so its gift policy
+        /// does not apply
+        pub fn do_sale_transfer_from(
+            who: &T::AccountId,
+            from: &T::AccountId,
+            to: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResult {
+            Self::move_from(who, from, to, class_id, asset_id, amount)
+        }
+
+        /// Transfer into or out of the custody of a pallet, such as a bag, a bundle vault or a
+        /// lock, so the gift policy of the asset does not apply to the move itself
+        pub fn do_custody_transfer_from(
+            who: &T::AccountId,
+            from: &T::AccountId,
+            to: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResult {
+            Self::move_from(who, from, to, class_id, asset_id, amount)
+        }
+
+        /// Batch transfer moved by `who`, which must own the class unless the gift policy of
+        /// every asset allows moving it without a sale
+        pub fn do_batch_transfer_from(
+            who: &T::AccountId,
+            from: &T::AccountId,
+            to: &T::AccountId,
+            class_id: T::ClassId,
+            asset_ids: Vec<T::AssetId>,
+            amounts: Vec<Balance>,
+        ) -> DispatchResult {
+            if from == to {
+                return Ok(());
+            }
+
+            for asset_id in asset_ids.iter() {
+                Self::ensure_gift_allowed_by(who, class_id, *asset_id)?;
+            }
+            Self::batch_move_from(who, from, to, class_id, asset_ids, amounts)
+        }
+
+        /// Batch transfer into or out of the custody of a pallet, so the gift policies of the
+        /// assets do not apply to the move itself
+        pub fn do_batch_custody_transfer_from(
+            who: &T::AccountId,
+            from: &T::AccountId,
+            to: &T::AccountId,
+            class_id: T::ClassId,
+            asset_ids: Vec<T::AssetId>,
+            amounts: Vec<Balance>,
+        ) -> DispatchResult {
+            Self::batch_move_from(who, from, to, class_id, asset_ids, amounts)
+        }
+
+        pub fn do_multi_transfer(from: &T::AccountId, items: MultiItemsOf<T>) -> DispatchResult {
+            for (to, class_id, asset_id, amount) in items.iter() {
+                if from == to || *amount == Zero::zero() {
+                    continue;
+                }
+                Self::ensure_gift_allowed_by(from, *class_id, *asset_id)?;
+                Self::remove_balance_from(from, *class_id, *asset_id, *amount)?;
+                Self::add_balance_to(to, *class_id, *asset_id, *amount)?;
+            }
+
+            Self::deposit_event(Event::MultiTransferred {
+                from: from.clone(),
+                items: items.len() as u32,
+            });
+
+            Ok(())
+        }
+
+        pub fn do_multi_mint(who: &T::AccountId, items: MultiItemsOf<T>) -> DispatchResult {
+            for (to, class_id, asset_id, amount) in items.iter() {
+                Self::maybe_check_owner(who, *class_id)?;
+                Self::add_balance_to(to, *class_id, *asset_id, *amount)?;
+                Self::increase_supply(*class_id, *asset_id, *amount)?;
+            }
+
+            Self::deposit_event(Event::MultiMinted {
+                who: who.clone(),
+                items: items.len() as u32,
+            });
+
+            Ok(())
+        }
+
+        pub fn balance_of(
+            owner: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> Balance {
+            Self::balances((owner, class_id, asset_id))
+        }
+
+        pub fn balance_of_batch(
+            owners: &Vec<T::AccountId>,
+            class_id: T::ClassId,
+            asset_ids: Vec<T::AssetId>,
+        ) -> Result<Vec<Balance>, DispatchError> {
+            ensure!(
+                owners.len() == asset_ids.len(),
+                Error::<T>::InvalidArrayLength
+            );
+
+            let mut batch_balances = Vec::new();
+
+            for _i in 0..owners.len() {
+                batch_balances.push(Balance::from(0u32));
+            }
+
+            let n = owners.len();
+            for i in 0..n {
+                let owner = &owners[i];
+                let asset_id = asset_ids[i];
+                batch_balances[i] = Self::balances((owner, class_id, asset_id));
+            }
+
+            Ok(batch_balances)
+        }
+
+        pub fn balance_of_single_owner_batch(
+            owner: &T::AccountId,
+            class_id: T::ClassId,
+            asset_ids: Vec<T::AssetId>,
+        ) -> Result<Vec<Balance>, DispatchError> {
+            let mut batch_balances = Vec::new();
+
+            for _i in 0..asset_ids.len() {
+                batch_balances.push(Balance::from(0u32));
+            }
+
+            let n = asset_ids.len();
+            for i in 0..n {
+                let owner = owner.clone();
+                let asset_id = asset_ids[i];
+
+                batch_balances[i] = Self::balances((owner, class_id, asset_id));
+            }
+
+            Ok(batch_balances)
+        }
+
+        pub fn balances_of_owner(
+            owner: &T::AccountId,
+        ) -> Result<Vec<(T::ClassId, T::AssetId, Balance)>, DispatchError> {
+            let mut balances = Vec::new();
+            let assets = Balances::<T>::iter_key_prefix((owner,));
+            for (class_id, asset_id) in assets {
+                let balance = Balances::<T>::get((owner, class_id, asset_id));
+                balances.push((class_id, asset_id, balance));
+            }
+            Ok(balances)
+        }
+
+        pub fn class_balances_of_owner(
+            owner: &T::AccountId,
+            class_id: T::ClassId,
+        ) -> Result<Vec<(T::AssetId, Balance)>, DispatchError> {
+            let mut balances = Vec::new();
+            let assets = Balances::<T>::iter_key_prefix((owner, class_id));
+            for asset_id in assets {
+                let balance = Balances::<T>::get((owner, class_id, asset_id));
+                balances.push((asset_id, balance));
+            }
+            Ok(balances)
+        }
+
+        /// Balance of an owner that cannot be moved out of its account
+        pub fn locked_balance_of(
+            owner: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> Balance {
+            Self::lent((owner, class_id, asset_id))
+                .saturating_add(Self::vesting_locked((owner, class_id, asset_id)))
+                .saturating_add(Self::held((owner, class_id, asset_id)))
+        }
+
+        /// Balance of an owner that can be moved out of its account
+        pub fn spendable_balance_of(
+            owner: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> Balance {
+            Self::balances((owner, class_id, asset_id))
+                .saturating_sub(Self::locked_balance_of(owner, class_id, asset_id))
+        }
+
+        /// Amount of assets an account may use: owned and not lent out, plus user rights
+        pub fn usable_balance_of(
+            account: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> Balance {
+            Self::balances((account, class_id, asset_id))
+                .saturating_sub(Self::lent((account, class_id, asset_id)))
+                .saturating_add(Self::user_rights((account, class_id, asset_id)))
+        }
+
+        /// Lock `amount` of `owner` assets and grant `user` the right to use them
+        pub fn do_lend(
+            owner: &T::AccountId,
+            user: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResult {
+            let unlocked = Self::spendable_balance_of(owner, class_id, asset_id);
+            ensure!(unlocked >= amount, Error::<T>::InsufficientBalance);
+
+            Lent::<T>::try_mutate((owner, class_id, asset_id), |lent| -> DispatchResult {
+                *lent = lent.checked_add(amount).ok_or(Error::<T>::NumOverflow)?;
+                Ok(())
+            })?;
+
+            UserRights::<T>::try_mutate((user, class_id, asset_id), |rights| -> DispatchResult {
+                *rights = rights.checked_add(amount).ok_or(Error::<T>::NumOverflow)?;
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::Lent {
+                owner: owner.clone(),
+                user: user.clone(),
+                class_id,
+                asset_id,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Revoke `user` rights over `amount` of `owner` assets and unlock them
+        pub fn do_reclaim(
+            owner: &T::AccountId,
+            user: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResult {
+            Lent::<T>::try_mutate((owner, class_id, asset_id), |lent| -> DispatchResult {
+                *lent = lent.checked_sub(amount).ok_or(Error::<T>::NumOverflow)?;
+                Ok(())
+            })?;
+
+            UserRights::<T>::try_mutate((user, class_id, asset_id), |rights| -> DispatchResult {
+                *rights = rights.checked_sub(amount).ok_or(Error::<T>::NumOverflow)?;
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::Reclaimed {
+                owner: owner.clone(),
+                user: user.clone(),
+                class_id,
+                asset_id,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        pub fn do_vested_transfer(
+            who: &T::AccountId,
+            to: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            schedule: VestingSchedule<BlockNumberFor<T>>,
+        ) -> DispatchResult {
+            ensure!(
+                schedule.locked > 0 && schedule.per_block > 0,
+                Error::<T>::InvalidVestingSchedule
+            );
+            ensure!(who != to, Error::<T>::InvalidVestingSchedule);
+
+            let mut schedules = Vesting::<T>::get((to, class_id, asset_id));
+            schedules
+                .try_push(schedule)
+                .map_err(|_| Error::<T>::TooManyVestingSchedules)?;
+
+            Self::do_transfer_from(who, who, to, class_id, asset_id, schedule.locked)?;
+
+            Vesting::<T>::insert((to, class_id, asset_id), schedules);
+
+            VestingLocked::<T>::try_mutate((to, class_id, asset_id), |locked| -> DispatchResult {
+                *locked = locked
+                    .checked_add(schedule.locked)
+                    .ok_or(Error::<T>::NumOverflow)?;
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::VestingScheduleAdded {
+                from: who.clone(),
+                to: to.clone(),
+                class_id,
+                asset_id,
+                schedule,
+            });
+
+            Ok(())
+        }
+
+        /// Drop matured vesting schedules and unlock what has vested
+        pub fn do_vest(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> DispatchResult {
+            let key = (who, class_id, asset_id);
+            let mut schedules = Vesting::<T>::get(key);
+            ensure!(!schedules.is_empty(), Error::<T>::NotVesting);
+
+            let now = frame_system::Pallet::<T>::block_number();
+            schedules.retain(|schedule| !schedule.locked_at(now).is_zero());
+            let locked = schedules.iter().fold(Balance::zero(), |total, schedule| {
+                total.saturating_add(schedule.locked_at(now))
+            });
+
+            if schedules.is_empty() {
+                Vesting::<T>::remove(key);
+                VestingLocked::<T>::remove(key);
+            } else {
+                Vesting::<T>::insert(key, schedules);
+                VestingLocked::<T>::insert(key, locked);
+            }
+
+            Self::deposit_event(Event::Vested {
+                who: who.clone(),
+                class_id,
+                asset_id,
+                locked,
+            });
+
+            Ok(())
+        }
+
+        pub fn balance_on_hold(
+            reason: HoldReason,
+            owner: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> Balance {
+            Self::holds((owner, class_id, asset_id, reason))
+        }
+
+        /// Hold `amount` of `who` spendable balance in place for `reason`
+        pub fn do_hold(
+            reason: HoldReason,
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResult {
+            ensure!(
+                !Self::is_frozen(who, class_id, asset_id),
+                Error::<T>::Frozen
+            );
+            ensure!(
+                Self::spendable_balance_of(who, class_id, asset_id) >= amount,
+                Error::<T>::InsufficientBalance
+            );
+
+            Holds::<T>::try_mutate(
+                (who, class_id, asset_id, reason),
+                |held| -> DispatchResult {
+                    *held = held.checked_add(amount).ok_or(Error::<T>::NumOverflow)?;
+                    Ok(())
+                },
+            )?;
+
+            Held::<T>::try_mutate((who, class_id, asset_id), |held| -> DispatchResult {
+                *held = held.checked_add(amount).ok_or(Error::<T>::NumOverflow)?;
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::Held {
+                reason,
+                who: who.clone(),
+                class_id,
+                asset_id,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Release `amount` held for `reason` back to `who` spendable balance
+        pub fn do_release(
+            reason: HoldReason,
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResult {
+            Holds::<T>::try_mutate_exists(
+                (who, class_id, asset_id, reason),
+                |held| -> DispatchResult {
+                    let remaining = held
+                        .unwrap_or_default()
+                        .checked_sub(amount)
+                        .ok_or(Error::<T>::InsufficientHeld)?;
+                    *held = Some(remaining).filter(|remaining| !remaining.is_zero());
+                    Ok(())
+                },
+            )?;
+
+            Held::<T>::mutate_exists((who, class_id, asset_id), |held| {
+                *held = held
+                    .map(|held| held.saturating_sub(amount))
+                    .filter(|held| !held.is_zero());
+            });
+
+            Self::deposit_event(Event::Released {
+                reason,
+                who: who.clone(),
+                class_id,
+                asset_id,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Release `amount` held from `from` for `reason` and transfer it to `to`
+        pub fn do_transfer_on_hold(
+            reason: HoldReason,
+            from: &T::AccountId,
+            to: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResult {
+            ensure!(
+                !Self::is_frozen(from, class_id, asset_id),
+                Error::<T>::Frozen
+            );
+
+            Self::do_release(reason, from, class_id, asset_id, amount)?;
+            Self::do_custody_transfer_from(from, from, to, class_id, asset_id, amount)
+        }
+
+        pub fn is_frozen(
+            account: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> bool {
+            FrozenAccounts::<T>::contains_key(class_id, account)
+                || FrozenAssets::<T>::contains_key(class_id, asset_id)
+        }
+
+        pub fn do_freeze_account(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            account: &T::AccountId,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+
+            FrozenAccounts::<T>::insert(class_id, account, ());
+
+            Self::deposit_event(Event::AccountFrozen {
+                class_id,
+                who: account.clone(),
+            });
+
+            Ok(())
+        }
+
+        pub fn do_thaw_account(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            account: &T::AccountId,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+
+            FrozenAccounts::<T>::remove(class_id, account);
+
+            Self::deposit_event(Event::AccountThawed {
+                class_id,
+                who: account.clone(),
+            });
+
+            Ok(())
+        }
+
+        pub fn do_freeze_asset(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+
+            FrozenAssets::<T>::insert(class_id, asset_id, ());
+
+            Self::deposit_event(Event::AssetFrozen { class_id, asset_id });
+
+            Ok(())
+        }
+
+        pub fn do_thaw_asset(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+
+            FrozenAssets::<T>::remove(class_id, asset_id);
+
+            Self::deposit_event(Event::AssetThawed { class_id, asset_id });
+
+            Ok(())
+        }
+
+        pub fn do_make_class_unique(who: &T::AccountId, class_id: T::ClassId) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+            ensure!(
+                Supply::<T>::iter_prefix(class_id).next().is_none(),
+                Error::<T>::ClassNotEmpty
+            );
+
+            UniqueClasses::<T>::insert(class_id, ());
+
+            Self::deposit_event(Event::ClassMadeUnique {
+                class_id,
+                who: who.clone(),
+            });
+
+            Ok(())
+        }
+
+        pub fn is_unique(class_id: T::ClassId) -> bool {
+            UniqueClasses::<T>::contains_key(class_id)
+        }
+
+        fn increase_supply(
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResult {
+            Supply::<T>::try_mutate(class_id, asset_id, |supply| -> DispatchResult {
+                *supply = supply.checked_add(amount).ok_or(Error::<T>::NumOverflow)?;
+                ensure!(
+                    *supply <= 1 || !Self::is_unique(class_id),
+                    Error::<T>::UniqueSupplyExceeded
+                );
+                Ok(())
+            })?;
+
+            if !amount.is_zero() {
+                Self::freeze_metadata_if_minted(class_id, asset_id);
+            }
+
+            Ok(())
+        }
+
+        pub(crate) fn decrease_supply(class_id: T::ClassId, asset_id: T::AssetId, amount: Balance) {
+            Supply::<T>::mutate_exists(class_id, asset_id, |supply| {
+                *supply = supply
+                    .map(|supply| supply.saturating_sub(amount))
+                    .filter(|supply| !supply.is_zero());
+            });
+        }
+
+        /// Start recording balance checkpoints for every asset of a class
+        pub fn do_enable_checkpoints(who: &T::AccountId, class_id: T::ClassId) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+            ensure!(
+                !CheckpointedClasses::<T>::contains_key(class_id),
+                Error::<T>::CheckpointsAlreadyEnabled
+            );
+
+            CheckpointedClasses::<T>::insert(class_id, frame_system::Pallet::<T>::block_number());
+
+            Self::deposit_event(Event::CheckpointsEnabled {
+                class_id,
+                who: who.clone(),
+            });
+
+            Ok(())
+        }
+
+        pub fn checkpoints_enabled(class_id: T::ClassId) -> bool {
+            CheckpointedClasses::<T>::contains_key(class_id)
+        }
+
+        /// Balance of an owner at the end of `block`
+        ///
+        /// Returns `None` if the class does not record checkpoints or started recording
+        /// after `block`.
+        pub fn balance_at(
+            owner: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            block: BlockNumberFor<T>,
+        ) -> Option<Balance> {
+            let enabled_at = CheckpointedClasses::<T>::get(class_id)?;
+            if block < enabled_at {
+                return None;
+            }
+
+            let count = CheckpointCount::<T>::get((owner, class_id, asset_id));
+            if count == 0 {
+                // Balance has not changed since recording started
+                return Some(Self::balance_of(owner, class_id, asset_id));
+            }
+
+            // Find the last checkpoint at or before `block`
+            let (mut low, mut high) = (0u32, count);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                let checkpoint = Checkpoints::<T>::get((owner, class_id, asset_id, mid))?;
+                if checkpoint.block > block {
+                    high = mid;
+                } else {
+                    low = mid + 1;
+                }
+            }
+
+            if low == 0 {
+                // Every checkpoint is later, the first one was recorded from a zero balance
+                return Some(Zero::zero());
+            }
+            Checkpoints::<T>::get((owner, class_id, asset_id, low - 1)).map(|c| c.balance)
         }
 
-        pub fn account_is_owner(account: &T::AccountId, class_id: T::ClassId) -> bool {
-            let value = Self::classes(class_id);
-            if let Some(class) = value {
-                if class.owner == account.clone() {
-                    return true;
+        fn record_checkpoint(
+            owner: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            previous: Balance,
+            balance: Balance,
+        ) {
+            let enabled_at = match CheckpointedClasses::<T>::get(class_id) {
+                Some(enabled_at) => enabled_at,
+                None => return,
+            };
+            let now = frame_system::Pallet::<T>::block_number();
+            let key = (owner, class_id, asset_id);
+
+            let mut count = CheckpointCount::<T>::get(key);
+            if count == 0 && !previous.is_zero() {
+                // Keep the balance held since before recording started
+                Checkpoints::<T>::insert(
+                    (owner, class_id, asset_id, 0),
+                    Checkpoint {
+                        block: enabled_at,
+                        balance: previous,
+                    },
+                );
+                count = 1;
+            }
+
+            if count > 0 {
+                let last = Checkpoints::<T>::get((owner, class_id, asset_id, count - 1));
+                if last.map_or(false, |c| c.block == now) {
+                    Checkpoints::<T>::insert(
+                        (owner, class_id, asset_id, count - 1),
+                        Checkpoint {
+                            block: now,
+                            balance,
+                        },
+                    );
+                    CheckpointCount::<T>::insert(key, count);
+                    return;
                 }
             }
-            return false;
+
+            Checkpoints::<T>::insert(
+                (owner, class_id, asset_id, count),
+                Checkpoint {
+                    block: now,
+                    balance,
+                },
+            );
+            CheckpointCount::<T>::insert(key, count.saturating_add(1));
         }
 
-        pub fn do_mint(
-            who: &T::AccountId,
+        pub(crate) fn add_balance_to(
             to: &T::AccountId,
             class_id: T::ClassId,
             asset_id: T::AssetId,
             amount: Balance,
         ) -> DispatchResult {
-            Self::add_balance_to(to, class_id, asset_id, amount)?;
+            ensure!(
+                !Destroying::<T>::contains_key(class_id, asset_id),
+                Error::<T>::AssetDestroying
+            );
 
-            Self::deposit_event(Event::Mint {
-                who: who.clone(),
-                to: to.clone(),
-                class_id,
-                asset_id,
-                amount,
-            });
+            let (previous, balance) = Balances::<T>::try_mutate(
+                (to, class_id, asset_id),
+                |balance| -> Result<(Balance, Balance), DispatchError> {
+                    let previous = *balance;
+                    *balance = balance.checked_add(amount).ok_or(Error::<T>::NumOverflow)?;
+                    Ok((previous, *balance))
+                },
+            )?;
+
+            if !balance.is_zero() && Self::is_unique(class_id) {
+                UniqueOwners::<T>::insert(class_id, asset_id, to);
+            }
+
+            Self::record_checkpoint(to, class_id, asset_id, previous, balance);
 
             Ok(())
         }
 
-        pub fn do_batch_mint(
-            who: &T::AccountId,
-            to: &T::AccountId,
+        pub(crate) fn remove_balance_from(
+            from: &T::AccountId,
             class_id: T::ClassId,
-            asset_ids: Vec<T::AssetId>,
-            amounts: Vec<Balance>,
+            asset_id: T::AssetId,
+            amount: Balance,
         ) -> DispatchResult {
             ensure!(
-                asset_ids.len() == amounts.len(),
-                Error::<T>::InvalidArrayLength
+                !Self::is_frozen(from, class_id, asset_id),
+                Error::<T>::Frozen
             );
 
-            let n = asset_ids.len();
-            for i in 0..n {
-                let asset_id = asset_ids[i];
-                let amount = amounts[i];
-                Self::add_balance_to(to, class_id, asset_id, amount)?;
+            let locked = Self::locked_balance_of(from, class_id, asset_id);
+            let (previous, balance) = Balances::<T>::try_mutate(
+                (from, class_id, asset_id),
+                |balance| -> Result<(Balance, Balance), DispatchError> {
+                    let previous = *balance;
+                    let remaining = balance.checked_sub(amount).ok_or(Error::<T>::NumOverflow)?;
+                    ensure!(remaining >= locked, Error::<T>::BalanceLocked);
+                    *balance = remaining;
+                    Ok((previous, remaining))
+                },
+            )?;
+
+            if balance.is_zero() && Self::is_unique(class_id) {
+                UniqueOwners::<T>::remove(class_id, asset_id);
             }
 
-            Self::deposit_event(Event::BatchMint {
-                who: who.clone(),
-                to: to.clone(),
-                class_id,
-                asset_ids,
-                amounts,
-            });
+            Self::record_checkpoint(from, class_id, asset_id, previous, balance);
 
             Ok(())
         }
 
-        pub fn do_burn(
-            who: &T::AccountId,
-            from: &T::AccountId,
+        pub fn do_approve(
+            owner: &T::AccountId,
+            spender: &T::AccountId,
             class_id: T::ClassId,
             asset_id: T::AssetId,
             amount: Balance,
         ) -> DispatchResult {
-            Self::remove_balance_from(from, class_id, asset_id, amount)?;
+            ensure!(
+                Classes::<T>::contains_key(class_id),
+                Error::<T>::InvalidClassId
+            );
 
-            Self::deposit_event(Event::Burn {
-                who: who.clone(),
-                from: from.clone(),
+            if amount.is_zero() {
+                Approvals::<T>::remove((owner, spender, class_id, asset_id));
+            } else {
+                ensure!(
+                    !Destroying::<T>::contains_key(class_id, asset_id),
+                    Error::<T>::AssetDestroying
+                );
+                Approvals::<T>::insert((owner, spender, class_id, asset_id), amount);
+            }
+
+            Self::deposit_event(Event::Approved {
+                owner: owner.clone(),
+                spender: spender.clone(),
                 class_id,
                 asset_id,
                 amount,
@@ -680,38 +2631,114 @@ pub mod pallet {
             Ok(())
         }
 
-        pub fn do_batch_burn(
-            who: &T::AccountId,
+        pub fn do_transfer_approved(
+            spender: &T::AccountId,
             from: &T::AccountId,
+            to: &T::AccountId,
             class_id: T::ClassId,
-            asset_ids: Vec<T::AssetId>,
-            amounts: Vec<Balance>,
+            asset_id: T::AssetId,
+            amount: Balance,
         ) -> DispatchResult {
-            ensure!(
-                asset_ids.len() == amounts.len(),
-                Error::<T>::InvalidArrayLength
-            );
+            // Fail before consuming the allowance
+            Self::ensure_gift_allowed_by(spender, class_id, asset_id)?;
+
+            Approvals::<T>::try_mutate_exists(
+                (from, spender, class_id, asset_id),
+                |allowance| -> DispatchResult {
+                    let remaining = allowance
+                        .unwrap_or_default()
+                        .checked_sub(amount)
+                        .ok_or(Error::<T>::InsufficientAllowance)?;
+                    *allowance = Some(remaining).filter(|remaining| !remaining.is_zero());
+                    Ok(())
+                },
+            )?;
 
-            let n = asset_ids.len();
-            for i in 0..n {
-                let asset_id = asset_ids[i];
-                let amount = amounts[i];
+            Self::do_transfer_from(spender, from, to, class_id, asset_id, amount)
+        }
 
-                Self::remove_balance_from(from, class_id, asset_id, amount)?;
+        pub fn do_transfer_with_permit(
+            permit: TransferPermitOf<T>,
+            signature: &T::OffchainSignature,
+        ) -> DispatchResult {
+            // Fail before consuming the nonce
+            Self::ensure_gift_allowed_by(&permit.from, permit.class_id, permit.asset_id)?;
+            Self::use_permit(
+                &permit.from,
+                permit.nonce,
+                permit.deadline,
+                TRANSFER_PERMIT,
+                &permit,
+                signature,
+            )?;
+
+            Self::do_transfer_from(
+                &permit.from,
+                &permit.from,
+                &permit.to,
+                permit.class_id,
+                permit.asset_id,
+                permit.amount,
+            )
+        }
+
+        pub fn do_approve_with_permit(
+            permit: ApprovalPermitOf<T>,
+            signature: &T::OffchainSignature,
+        ) -> DispatchResult {
+            Self::use_permit(
+                &permit.from,
+                permit.nonce,
+                permit.deadline,
+                APPROVAL_PERMIT,
+                &permit,
+                signature,
+            )?;
+
+            Self::do_approve(
+                &permit.from,
+                &permit.spender,
+                permit.class_id,
+                permit.asset_id,
+                permit.amount,
+            )
+        }
+
+        pub fn do_set_royalty(
+            who: &T::AccountId,
+            class_id: T::ClassId,
+            asset_id: Option<T::AssetId>,
+            royalty: Option<Royalty<T::AccountId>>,
+        ) -> DispatchResult {
+            Self::maybe_check_owner(who, class_id)?;
+            if let Some(royalty) = &royalty {
+                ensure!(royalty.bps <= MAX_ROYALTY_BPS, Error::<T>::InvalidRoyalty);
             }
 
-            Self::deposit_event(Event::BatchBurn {
-                who: who.clone(),
-                from: from.clone(),
+            match asset_id {
+                Some(asset_id) => AssetRoyalties::<T>::set(class_id, asset_id, royalty.clone()),
+                None => ClassRoyalties::<T>::set(class_id, royalty.clone()),
+            }
+
+            Self::deposit_event(Event::RoyaltySet {
                 class_id,
-                asset_ids,
-                amounts,
+                asset_id,
+                royalty,
             });
 
             Ok(())
         }
 
-        pub fn do_transfer_from(
+        /// Royalty owed on sales of the asset
+        pub fn royalty_of(
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> Option<Royalty<T::AccountId>> {
+            AssetRoyalties::<T>::get(class_id, asset_id)
+                .or_else(|| ClassRoyalties::<T>::get(class_id))
+        }
+
+        fn move_from(
             who: &T::AccountId,
             from: &T::AccountId,
             to: &T::AccountId,
@@ -739,7 +2766,7 @@ pub mod pallet {
             Ok(())
         }
 
-        pub fn do_batch_transfer_from(
+        fn batch_move_from(
             who: &T::AccountId,
             from: &T::AccountId,
             to: &T::AccountId,
@@ -778,116 +2805,60 @@ pub mod pallet {
             Ok(())
         }
 
-        pub fn balance_of(
-            owner: &T::AccountId,
-            class_id: T::ClassId,
-            asset_id: T::AssetId,
-        ) -> Balance {
-            Self::balances((owner, class_id, asset_id))
-        }
-
-        pub fn balance_of_batch(
-            owners: &Vec<T::AccountId>,
-            class_id: T::ClassId,
-            asset_ids: Vec<T::AssetId>,
-        ) -> Result<Vec<Balance>, DispatchError> {
+        /// Fail if the royalty of the asset forbids moving it without a sale
+        fn ensure_gift_allowed(class_id: T::ClassId, asset_id: T::AssetId) -> DispatchResult {
             ensure!(
-                owners.len() == asset_ids.len(),
-                Error::<T>::InvalidArrayLength
+                Self::royalty_of(class_id, asset_id)
+                    .map_or(true, |royalty| royalty.gifts == GiftPolicy::Exempt),
+                Error::<T>::GiftsForbidden
             );
-
-            let mut batch_balances = Vec::new();
-
-            for _i in 0..owners.len() {
-                batch_balances.push(Balance::from(0u32));
-            }
-
-            let n = owners.len();
-            for i in 0..n {
-                let owner = &owners[i];
-                let asset_id = asset_ids[i];
-                batch_balances[i] = Self::balances((owner, class_id, asset_id));
-            }
-
-            Ok(batch_balances)
-        }
-
-        pub fn balance_of_single_owner_batch(
-            owner: &T::AccountId,
-            class_id: T::ClassId,
-            asset_ids: Vec<T::AssetId>,
-        ) -> Result<Vec<Balance>, DispatchError> {
-            let mut batch_balances = Vec::new();
-
-            for _i in 0..asset_ids.len() {
-                batch_balances.push(Balance::from(0u32));
-            }
-
-            let n = asset_ids.len();
-            for i in 0..n {
-                let owner = owner.clone();
-                let asset_id = asset_ids[i];
-
-                batch_balances[i] = Self::balances((owner, class_id, asset_id));
-            }
-
-            Ok(batch_balances)
-        }
-
-        pub fn balances_of_owner(
-            owner: &T::AccountId,
-        ) -> Result<Vec<(T::ClassId, T::AssetId, Balance)>, DispatchError> {
-            let mut balances = Vec::new();
-            let assets = Balances::<T>::iter_key_prefix((owner,));
-            for (class_id, asset_id) in assets {
-                let balance = Balances::<T>::get((owner, class_id, asset_id));
-                balances.push((class_id, asset_id, balance));
-            }
-            Ok(balances)
+            Ok(())
         }
 
-        pub fn class_balances_of_owner(
-            owner: &T::AccountId,
+        /// Fail unless `who` owns the class or the gift policy of the asset allows moving it
+        pub fn ensure_gift_allowed_by(
+            who: &T::AccountId,
             class_id: T::ClassId,
-        ) -> Result<Vec<(T::AssetId, Balance)>, DispatchError> {
-            let mut balances = Vec::new();
-            let assets = Balances::<T>::iter_key_prefix((owner, class_id));
-            for asset_id in assets {
-                let balance = Balances::<T>::get((owner, class_id, asset_id));
-                balances.push((asset_id, balance));
+            asset_id: T::AssetId,
+        ) -> DispatchResult {
+            if Classes::<T>::get(class_id).map_or(false, |class| class.owner == *who) {
+                return Ok(());
             }
-            Ok(balances)
+            Self::ensure_gift_allowed(class_id, asset_id)
         }
 
-        fn add_balance_to(
-            to: &T::AccountId,
-            class_id: T::ClassId,
-            asset_id: T::AssetId,
-            amount: Balance,
+        /// Check a permit signed by `signer` and consume its nonce
+        fn use_permit(
+            signer: &T::AccountId,
+            nonce: u64,
+            deadline: BlockNumberFor<T>,
+            prefix: [u8; 8],
+            permit: &impl Encode,
+            signature: &T::OffchainSignature,
         ) -> DispatchResult {
-            Balances::<T>::try_mutate((to, class_id, asset_id), |balance| -> DispatchResult {
-                *balance = balance.checked_add(amount).ok_or(Error::<T>::NumOverflow)?;
-                Ok(())
-            })?;
+            ensure!(
+                frame_system::Pallet::<T>::block_number() <= deadline,
+                Error::<T>::PermitExpired
+            );
+            // Binding the genesis hash keeps permits from being replayed on other chains
+            let genesis_hash = frame_system::Pallet::<T>::block_hash(BlockNumberFor::<T>::zero());
+            ensure!(
+                (prefix, genesis_hash, permit)
+                    .using_encoded(|message| signature.verify(message, signer)),
+                Error::<T>::InvalidPermitSignature
+            );
 
-            Ok(())
+            PermitNonces::<T>::try_mutate(signer, |current| -> DispatchResult {
+                ensure!(*current == nonce, Error::<T>::InvalidPermitNonce);
+                *current = current.checked_add(1).ok_or(Error::<T>::NumOverflow)?;
+                Ok(())
+            })
         }
 
-        fn remove_balance_from(
-            from: &T::AccountId,
+        pub(crate) fn maybe_check_owner(
+            who: &T::AccountId,
             class_id: T::ClassId,
-            asset_id: T::AssetId,
-            amount: Balance,
         ) -> DispatchResult {
-            Balances::<T>::try_mutate((from, class_id, asset_id), |balance| -> DispatchResult {
-                *balance = balance.checked_sub(amount).ok_or(Error::<T>::NumOverflow)?;
-                Ok(())
-            })?;
-
-            Ok(())
-        }
-
-        fn maybe_check_owner(who: &T::AccountId, class_id: T::ClassId) -> DispatchResult {
             let class = Classes::<T>::get(class_id).ok_or(Error::<T>::InvalidClassId)?;
             ensure!(*who == class.owner, Error::<T>::NoPermission);
             Ok(())
@@ -900,6 +2871,7 @@ pub mod pallet {
         type ClassId = T::ClassId;
         type Metadata = BoundedVec<u8, <T as Config>::MaxClassMetadata>;
         type Balance = Balance;
+        type BlockNumber = BlockNumberFor<T>;
 
         fn create_class(
             who: Self::AccountId,
@@ -965,5 +2937,70 @@ pub mod pallet {
         ) -> Result<Vec<(Self::ClassId, Self::AssetId, Self::Balance)>, DispatchError> {
             Self::balances_of_owner(&owner)
         }
+
+        fn balance_at(
+            owner: Self::AccountId,
+            class_id: Self::ClassId,
+            asset_id: Self::AssetId,
+            block: Self::BlockNumber,
+        ) -> Option<Self::Balance> {
+            Self::balance_at(&owner, class_id, asset_id, block)
+        }
+
+        fn spendable_balance_of(
+            owner: Self::AccountId,
+            class_id: Self::ClassId,
+            asset_id: Self::AssetId,
+        ) -> Self::Balance {
+            Self::spendable_balance_of(&owner, class_id, asset_id)
+        }
+
+        fn balance_on_hold(
+            reason: HoldReason,
+            owner: Self::AccountId,
+            class_id: Self::ClassId,
+            asset_id: Self::AssetId,
+        ) -> Self::Balance {
+            Self::balance_on_hold(reason, &owner, class_id, asset_id)
+        }
+
+        fn hold(
+            reason: HoldReason,
+            who: Self::AccountId,
+            class_id: Self::ClassId,
+            asset_id: Self::AssetId,
+            amount: Self::Balance,
+        ) -> DispatchResult {
+            Self::do_hold(reason, &who, class_id, asset_id, amount)
+        }
+
+        fn release(
+            reason: HoldReason,
+            who: Self::AccountId,
+            class_id: Self::ClassId,
+            asset_id: Self::AssetId,
+            amount: Self::Balance,
+        ) -> DispatchResult {
+            Self::do_release(reason, &who, class_id, asset_id, amount)
+        }
+
+        fn transfer_on_hold(
+            reason: HoldReason,
+            from: Self::AccountId,
+            to: Self::AccountId,
+            class_id: Self::ClassId,
+            asset_id: Self::AssetId,
+            amount: Self::Balance,
+        ) -> DispatchResult {
+            Self::do_transfer_on_hold(reason, &from, &to, class_id, asset_id, amount)
+        }
+
+        fn is_frozen(
+            owner: Self::AccountId,
+            class_id: Self::ClassId,
+            asset_id: Self::AssetId,
+        ) -> bool {
+            Self::is_frozen(&owner, class_id, asset_id)
+        }
     }
 }
@@ -0,0 +1,136 @@
+//! Storage migrations of `sugarfunge_asset`
+
+use super::*;
+use frame_support::{
+    traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+    weights::Weight,
+};
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+use sp_std::{collections::btree_map::BTreeMap, marker::PhantomData};
+
+/// Version 1 wraps the raw class and asset metadata into `Metadata::Raw`
+pub mod v1 {
+    use super::*;
+
+    #[derive(Decode)]
+    struct OldClass<AccountId, ClassMetadataOf> {
+        owner: AccountId,
+        metadata: ClassMetadataOf,
+    }
+
+    #[derive(Decode)]
+    struct OldAsset<ClassId, AccountId, AssetMetadataOf> {
+        class_id: ClassId,
+        creator: AccountId,
+        metadata: AssetMetadataOf,
+    }
+
+    pub struct MigrateToV1<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 0 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0u64;
+            Classes::<T>::translate::<OldClass<T::AccountId, ClassMetadataOf<T>>, _>(|_, old| {
+                translated += 1;
+                Some(ClassOf::<T> {
+                    owner: old.owner,
+                    metadata: Metadata::Raw(old.metadata),
+                })
+            });
+            Assets::<T>::translate::<OldAsset<T::ClassId, T::AccountId, AssetMetadataOf<T>>, _>(
+                |_, _, old| {
+                    translated += 1;
+                    Some(AssetOf::<T> {
+                        class_id: old.class_id,
+                        creator: old.creator,
+                        metadata: Metadata::Raw(old.metadata),
+                    })
+                },
+            );
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+    }
+}
+
+/// Version 2 records the supply of assets minted before `Supply` was tracked
+pub mod v2 {
+    use super::*;
+
+    /// Rebuilds `Supply` from the sum of the balances of each asset in a single block, so it
+    /// only runs while there are at most `Limit` balances. Above that the upgrade is skipped
+    /// and the storage version stays at 1.
+    pub struct MigrateToV2<T, Limit>(PhantomData<(T, Limit)>);
+
+    impl<T: Config, Limit: Get<u32>> OnRuntimeUpgrade for MigrateToV2<T, Limit> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 1 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let limit = Limit::get() as usize;
+            let counted = Balances::<T>::iter_keys().take(limit + 1).count();
+            if counted > limit {
+                return T::DbWeight::get().reads(counted as u64 + 1);
+            }
+
+            let mut balances = 0u64;
+            let mut supplies: BTreeMap<(T::ClassId, T::AssetId), Balance> = BTreeMap::new();
+            for ((_, class_id, asset_id), balance) in Balances::<T>::iter() {
+                balances += 1;
+                let supply = supplies.entry((class_id, asset_id)).or_default();
+                *supply = supply.saturating_add(balance);
+            }
+
+            let mut written = 0u64;
+            for ((class_id, asset_id), supply) in supplies {
+                if !supply.is_zero() {
+                    written += 1;
+                    Supply::<T>::insert(class_id, asset_id, supply);
+                }
+            }
+
+            StorageVersion::new(2).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(counted as u64 + balances + 1, written + 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+            if Pallet::<T>::on_chain_storage_version() == 1 {
+                ensure!(
+                    Balances::<T>::iter_keys().count() <= Limit::get() as usize,
+                    "too many balances to backfill the supply in one block"
+                );
+            }
+            Ok(Vec::new())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            ensure!(
+                Pallet::<T>::on_chain_storage_version() == 2,
+                "supply backfill did not run"
+            );
+            let mut supplies: BTreeMap<(T::ClassId, T::AssetId), Balance> = BTreeMap::new();
+            for ((_, class_id, asset_id), balance) in Balances::<T>::iter() {
+                let supply = supplies.entry((class_id, asset_id)).or_default();
+                *supply = supply.saturating_add(balance);
+            }
+            for ((class_id, asset_id), supply) in supplies {
+                ensure!(
+                    Supply::<T>::get(class_id, asset_id) == supply,
+                    "supply does not match the balances of the asset"
+                );
+            }
+            Ok(())
+        }
+    }
+}
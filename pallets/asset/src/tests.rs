@@ -1,7 +1,24 @@
 use crate::mock::Asset;
 use crate::mock::*;
-use frame_support::assert_ok;
-use sp_core::bounded_vec;
+use crate::{
+    migrations, ApprovalPermit, CheckpointCount, Checkpoints, Error, GiftPolicy, Metadata,
+    MetadataRecord, Royalty, TransferPermit, UserRights, VestingSchedule, APPROVAL_PERMIT,
+    TRANSFER_PERMIT,
+};
+use codec::Encode;
+use frame_support::{
+    assert_noop, assert_ok,
+    storage::unhashed,
+    traits::{
+        tokens::{
+            fungibles::{Create, Destroy, Inspect, Mutate},
+            nonfungibles_v2, Fortitude, Precision, Preservation,
+        },
+        ConstU32, GetStorageVersion, OnRuntimeUpgrade, StorageVersion,
+    },
+};
+use sp_core::{bounded_vec, H256};
+use sp_runtime::testing::TestSignature;
 
 fn test_create_class() {
     assert_ok!(Asset::do_create_class(&1, &1, 2000, bounded_vec![0]));
@@ -64,3 +81,1039 @@ fn test_verify_account_owner_failed_wrong_class_id() {
         assert_eq!(Asset::account_is_owner(&2, 2000), false);
     })
 }
+
+#[test]
+fn lent_assets_are_locked_from_transfer() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+        assert_ok!(Asset::do_mint(&1, &1, 2000, 1, 100));
+        assert_ok!(Asset::do_lend(&1, &2, 2000, 1, 60));
+
+        assert_eq!(Asset::balance_of(&1, 2000, 1), 100);
+        assert_eq!(Asset::usable_balance_of(&1, 2000, 1), 40);
+        assert_eq!(Asset::usable_balance_of(&2, 2000, 1), 60);
+
+        assert_noop!(
+            Asset::do_transfer_from(&1, &1, &3, 2000, 1, 41),
+            Error::<Test>::BalanceLocked
+        );
+        assert_noop!(
+            Asset::do_lend(&1, &3, 2000, 1, 41),
+            Error::<Test>::InsufficientBalance
+        );
+        assert_ok!(Asset::do_transfer_from(&1, &1, &3, 2000, 1, 40));
+    })
+}
+
+#[test]
+fn reclaim_unlocks_lent_assets() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+        assert_ok!(Asset::do_mint(&1, &1, 2000, 1, 100));
+        assert_ok!(Asset::do_lend(&1, &2, 2000, 1, 60));
+        assert_ok!(Asset::do_reclaim(&1, &2, 2000, 1, 60));
+
+        assert_eq!(Asset::usable_balance_of(&1, 2000, 1), 100);
+        assert_eq!(Asset::usable_balance_of(&2, 2000, 1), 0);
+        assert_ok!(Asset::do_transfer_from(&1, &1, &3, 2000, 1, 100));
+    })
+}
+
+#[test]
+fn balance_at_reads_checkpoints() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        test_create_class();
+        assert_ok!(Asset::do_mint(&1, &1, 2000, 1, 100));
+
+        assert_noop!(
+            Asset::enable_checkpoints(RuntimeOrigin::signed(2), 2000),
+            Error::<Test>::NoPermission
+        );
+        System::set_block_number(2);
+        assert_ok!(Asset::enable_checkpoints(RuntimeOrigin::signed(1), 2000));
+        assert_noop!(
+            Asset::enable_checkpoints(RuntimeOrigin::signed(1), 2000),
+            Error::<Test>::CheckpointsAlreadyEnabled
+        );
+
+        System::set_block_number(5);
+        assert_ok!(Asset::do_transfer_from(&1, &1, &2, 2000, 1, 30));
+        assert_ok!(Asset::do_transfer_from(&1, &1, &2, 2000, 1, 10));
+        System::set_block_number(8);
+        assert_ok!(Asset::do_burn(&1, &2, 2000, 1, 40));
+
+        // Recording started at block 2
+        assert_eq!(Asset::balance_at(&1, 2000, 1, 1), None);
+        assert_eq!(Asset::balance_at(&1, 2000, 1, 2), Some(100));
+        assert_eq!(Asset::balance_at(&1, 2000, 1, 4), Some(100));
+        assert_eq!(Asset::balance_at(&1, 2000, 1, 5), Some(60));
+        assert_eq!(Asset::balance_at(&2, 2000, 1, 4), Some(0));
+        assert_eq!(Asset::balance_at(&2, 2000, 1, 7), Some(40));
+        assert_eq!(Asset::balance_at(&2, 2000, 1, 8), Some(0));
+        assert_eq!(Asset::balance_at(&3, 2000, 1, 8), Some(0));
+
+        // Classes without checkpoints have no history
+        assert_eq!(Asset::balance_at(&1, 3000, 1, 8), None);
+    })
+}
+
+#[test]
+fn vested_assets_unlock_over_time() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        test_create_class();
+        assert_ok!(Asset::do_mint(&1, &1, 2000, 1, 1000));
+
+        // 100 unlocking 10 per block from block 10, nothing before block 15
+        let schedule = VestingSchedule {
+            locked: 100,
+            per_block: 10,
+            start: 10,
+            cliff: 15,
+        };
+        assert_ok!(Asset::vested_transfer(
+            RuntimeOrigin::signed(1),
+            2,
+            2000,
+            1,
+            schedule
+        ));
+        assert_eq!(Asset::balance_of(&2, 2000, 1), 100);
+        assert_eq!(Asset::locked_balance_of(&2, 2000, 1), 100);
+
+        assert_noop!(
+            Asset::do_transfer_from(&2, &2, &3, 2000, 1, 1),
+            Error::<Test>::BalanceLocked
+        );
+        assert_noop!(
+            Asset::do_burn(&2, &2, 2000, 1, 1),
+            Error::<Test>::BalanceLocked
+        );
+
+        System::set_block_number(14);
+        assert_ok!(Asset::vest(RuntimeOrigin::signed(2), 2000, 1));
+        assert_eq!(Asset::locked_balance_of(&2, 2000, 1), 100);
+
+        System::set_block_number(15);
+        assert_ok!(Asset::vest(RuntimeOrigin::signed(2), 2000, 1));
+        assert_eq!(Asset::locked_balance_of(&2, 2000, 1), 50);
+        assert_noop!(
+            Asset::do_transfer_from(&2, &2, &3, 2000, 1, 51),
+            Error::<Test>::BalanceLocked
+        );
+        assert_ok!(Asset::do_transfer_from(&2, &2, &3, 2000, 1, 50));
+
+        System::set_block_number(20);
+        assert_ok!(Asset::vest(RuntimeOrigin::signed(2), 2000, 1));
+        assert_eq!(Asset::locked_balance_of(&2, 2000, 1), 0);
+        assert_eq!(Asset::vesting((2, 2000, 1)).len(), 0);
+        assert_noop!(
+            Asset::vest(RuntimeOrigin::signed(2), 2000, 1),
+            Error::<Test>::NotVesting
+        );
+    })
+}
+
+#[test]
+fn vested_transfer_fails() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+        assert_ok!(Asset::do_mint(&1, &1, 2000, 1, 1000));
+
+        let schedule = VestingSchedule {
+            locked: 100,
+            per_block: 10,
+            start: 10,
+            cliff: 10,
+        };
+        assert_noop!(
+            Asset::vested_transfer(
+                RuntimeOrigin::signed(1),
+                2,
+                2000,
+                1,
+                VestingSchedule {
+                    per_block: 0,
+                    ..schedule
+                }
+            ),
+            Error::<Test>::InvalidVestingSchedule
+        );
+        assert_noop!(
+            Asset::vested_transfer(RuntimeOrigin::signed(3), 2, 2000, 1, schedule),
+            Error::<Test>::NumOverflow
+        );
+
+        assert_ok!(Asset::vested_transfer(
+            RuntimeOrigin::signed(1),
+            2,
+            2000,
+            1,
+            schedule
+        ));
+        assert_ok!(Asset::vested_transfer(
+            RuntimeOrigin::signed(1),
+            2,
+            2000,
+            1,
+            schedule
+        ));
+        assert_noop!(
+            Asset::vested_transfer(RuntimeOrigin::signed(1), 2, 2000, 1, schedule),
+            Error::<Test>::TooManyVestingSchedules
+        );
+    })
+}
+
+#[test]
+fn held_assets_stay_in_place_until_released() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+        assert_ok!(Asset::do_mint(&1, &2, 2000, 1, 100));
+
+        assert_ok!(Asset::do_hold(*b"market/0", &2, 2000, 1, 60));
+        assert_ok!(Asset::do_hold(*b"bundle/0", &2, 2000, 1, 30));
+        assert_eq!(Asset::balance_of(&2, 2000, 1), 100);
+        assert_eq!(Asset::spendable_balance_of(&2, 2000, 1), 10);
+        assert_eq!(Asset::balance_on_hold(*b"market/0", &2, 2000, 1), 60);
+
+        assert_noop!(
+            Asset::do_hold(*b"market/0", &2, 2000, 1, 11),
+            Error::<Test>::InsufficientBalance
+        );
+        assert_noop!(
+            Asset::do_transfer_from(&2, &2, &3, 2000, 1, 11),
+            Error::<Test>::BalanceLocked
+        );
+        assert_noop!(
+            Asset::do_release(*b"bundle/0", &2, 2000, 1, 31),
+            Error::<Test>::InsufficientHeld
+        );
+
+        assert_ok!(Asset::do_release(*b"bundle/0", &2, 2000, 1, 30));
+        assert_eq!(Asset::spendable_balance_of(&2, 2000, 1), 40);
+
+        assert_ok!(Asset::do_transfer_on_hold(
+            *b"market/0",
+            &2,
+            &3,
+            2000,
+            1,
+            60
+        ));
+        assert_eq!(Asset::balance_of(&2, 2000, 1), 40);
+        assert_eq!(Asset::balance_of(&3, 2000, 1), 60);
+        assert_eq!(Asset::held((2, 2000, 1)), 0);
+    })
+}
+
+#[test]
+fn frozen_assets_cannot_move() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+        assert_ok!(Asset::do_mint(&1, &2, 2000, 1, 100));
+        assert_ok!(Asset::do_mint(&1, &2, 2000, 2, 100));
+
+        assert_noop!(
+            Asset::freeze_account(RuntimeOrigin::signed(2), 2000, 2),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(Asset::freeze_account(RuntimeOrigin::signed(1), 2000, 2));
+        assert_noop!(
+            Asset::do_transfer_from(&2, &2, &3, 2000, 1, 10),
+            Error::<Test>::Frozen
+        );
+        assert_noop!(
+            Asset::do_hold(*b"market/0", &2, 2000, 1, 10),
+            Error::<Test>::Frozen
+        );
+        // Frozen accounts can still receive
+        assert_ok!(Asset::do_mint(&1, &2, 2000, 1, 10));
+
+        assert_ok!(Asset::thaw_account(RuntimeOrigin::signed(1), 2000, 2));
+        assert_ok!(Asset::freeze_asset(RuntimeOrigin::signed(1), 2000, 1));
+        assert_noop!(
+            Asset::do_transfer_from(&2, &2, &3, 2000, 1, 10),
+            Error::<Test>::Frozen
+        );
+        assert_ok!(Asset::do_transfer_from(&2, &2, &3, 2000, 2, 10));
+
+        assert_ok!(Asset::thaw_asset(RuntimeOrigin::signed(1), 2000, 1));
+        assert_ok!(Asset::do_transfer_from(&2, &2, &3, 2000, 1, 10));
+    })
+}
+
+#[test]
+fn fungibles_adapter_moves_balances() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+
+        assert_ok!(<Asset as Mutate<u64>>::mint_into((2000, 1), &2, 100));
+        assert_eq!(Asset::balance_of(&2, 2000, 1), 100);
+        assert_eq!(<Asset as Inspect<u64>>::total_issuance((2000, 1)), 100);
+
+        assert_ok!(<Asset as Mutate<u64>>::transfer(
+            (2000, 1),
+            &2,
+            &3,
+            40,
+            Preservation::Expendable
+        ));
+        assert_eq!(Asset::balance_of(&3, 2000, 1), 40);
+
+        // Holds are not reducible
+        assert_ok!(Asset::do_hold(*b"market/0", &2, 2000, 1, 50));
+        assert_eq!(
+            <Asset as Inspect<u64>>::reducible_balance(
+                (2000, 1),
+                &2,
+                Preservation::Expendable,
+                Fortitude::Polite
+            ),
+            10
+        );
+        assert!(
+            <Asset as Mutate<u64>>::transfer((2000, 1), &2, &3, 11, Preservation::Expendable)
+                .is_err()
+        );
+
+        assert_ok!(<Asset as Mutate<u64>>::burn_from(
+            (2000, 1),
+            &3,
+            40,
+            Precision::Exact,
+            Fortitude::Polite
+        ));
+        assert_eq!(<Asset as Inspect<u64>>::total_issuance((2000, 1)), 60);
+        assert_eq!(Asset::supply(2000, 1), 60);
+
+        assert!(<Asset as Mutate<u64>>::mint_into((3000, 1), &2, 1).is_err());
+    })
+}
+
+#[test]
+fn fungibles_adapter_creates_and_destroys_assets() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(<Asset as Create<u64>>::create((3000, 1), 1, false, 1));
+        assert!(Asset::account_is_owner(&1, 3000));
+        assert!(Asset::asset_exists(3000, 1));
+        assert_ok!(Asset::do_enable_checkpoints(&1, 3000));
+
+        assert_ok!(Asset::do_mint(&1, &2, 3000, 1, 10));
+        assert_ok!(Asset::do_mint(&1, &3, 3000, 1, 10));
+        UserRights::<Test>::insert((4, 3000, 1), 5);
+        assert_ok!(Asset::do_approve(&2, &4, 3000, 1, 5));
+        assert_ok!(Asset::do_approve(&2, &4, 3000, 2, 5));
+
+        assert_noop!(
+            <Asset as Destroy<u64>>::start_destroy((3000, 1), Some(2)),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(<Asset as Destroy<u64>>::start_destroy((3000, 1), Some(1)));
+        assert_noop!(
+            Asset::do_mint(&1, &2, 3000, 1, 10),
+            Error::<Test>::AssetDestroying
+        );
+        assert_noop!(
+            Asset::do_approve(&3, &4, 3000, 1, 5),
+            Error::<Test>::AssetDestroying
+        );
+
+        assert_eq!(
+            <Asset as Destroy<u64>>::destroy_accounts((3000, 1), 1),
+            Ok(1)
+        );
+        assert_noop!(
+            <Asset as Destroy<u64>>::finish_destroy((3000, 1)),
+            Error::<Test>::InUse
+        );
+        // The last balance, the user rights of 4 and the checkpoints of 2 and 3
+        assert_eq!(
+            <Asset as Destroy<u64>>::destroy_accounts((3000, 1), 5),
+            Ok(4)
+        );
+        assert_eq!(UserRights::<Test>::get((4, 3000, 1)), 0);
+        assert_eq!(CheckpointCount::<Test>::get((2, 3000, 1)), 0);
+        assert_eq!(Checkpoints::<Test>::iter_keys().count(), 0);
+        assert_noop!(
+            <Asset as Destroy<u64>>::finish_destroy((3000, 1)),
+            Error::<Test>::InUse
+        );
+        // Only the approval of the destroyed asset goes
+        assert_eq!(
+            <Asset as Destroy<u64>>::destroy_approvals((3000, 1), 5),
+            Ok(1)
+        );
+        assert_eq!(Asset::allowance((2, 4, 3000, 1)), 0);
+        assert_eq!(Asset::allowance((2, 4, 3000, 2)), 5);
+        assert_ok!(<Asset as Destroy<u64>>::finish_destroy((3000, 1)));
+
+        assert!(!Asset::asset_exists(3000, 1));
+        assert_eq!(Asset::supply(3000, 1), 0);
+        assert_ok!(Asset::do_mint(&1, &2, 3000, 1, 10));
+    })
+}
+
+#[test]
+fn unique_classes_are_nonfungible() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+        assert_ok!(Asset::do_create_class(&1, &1, 3000, bounded_vec![0]));
+        assert_ok!(Asset::do_mint(&1, &2, 3000, 1, 1));
+
+        assert_noop!(
+            Asset::make_class_unique(RuntimeOrigin::signed(1), 3000),
+            Error::<Test>::ClassNotEmpty
+        );
+        assert_ok!(Asset::make_class_unique(RuntimeOrigin::signed(1), 2000));
+
+        assert_ok!(Asset::do_mint(&1, &2, 2000, 1, 1));
+        assert_noop!(
+            Asset::do_mint(&1, &3, 2000, 1, 1),
+            Error::<Test>::UniqueSupplyExceeded
+        );
+        assert_eq!(
+            <Asset as nonfungibles_v2::Inspect<u64>>::owner(&2000, &1),
+            Some(2)
+        );
+        assert_eq!(
+            <Asset as nonfungibles_v2::Inspect<u64>>::collection_owner(&2000),
+            Some(1)
+        );
+        assert_eq!(
+            <Asset as nonfungibles_v2::Inspect<u64>>::collection_owner(&3000),
+            None
+        );
+
+        assert_ok!(Asset::do_transfer_from(&2, &2, &3, 2000, 1, 1));
+        assert_eq!(
+            <Asset as nonfungibles_v2::Inspect<u64>>::owner(&2000, &1),
+            Some(3)
+        );
+
+        assert_ok!(Asset::freeze_asset(RuntimeOrigin::signed(1), 2000, 1));
+        assert!(!<Asset as nonfungibles_v2::Inspect<u64>>::can_transfer(
+            &2000, &1
+        ));
+
+        assert_ok!(Asset::thaw_asset(RuntimeOrigin::signed(1), 2000, 1));
+        assert_ok!(Asset::do_burn(&1, &3, 2000, 1, 1));
+        assert_eq!(
+            <Asset as nonfungibles_v2::Inspect<u64>>::owner(&2000, &1),
+            None
+        );
+    })
+}
+
+#[test]
+fn approved_spenders_transfer_within_allowance() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+        assert_ok!(Asset::do_mint(&1, &2, 2000, 1, 100));
+
+        assert_ok!(Asset::approve(RuntimeOrigin::signed(2), 3, 2000, 1, 60));
+        assert_eq!(Asset::allowance((2, 3, 2000, 1)), 60);
+
+        assert_noop!(
+            Asset::transfer_approved(RuntimeOrigin::signed(3), 2, 4, 2000, 1, 61),
+            Error::<Test>::InsufficientAllowance
+        );
+        assert_noop!(
+            Asset::transfer_approved(RuntimeOrigin::signed(4), 2, 4, 2000, 1, 1),
+            Error::<Test>::InsufficientAllowance
+        );
+
+        assert_ok!(Asset::transfer_approved(
+            RuntimeOrigin::signed(3),
+            2,
+            4,
+            2000,
+            1,
+            60
+        ));
+        assert_eq!(Asset::balance_of(&4, 2000, 1), 60);
+        assert_eq!(Asset::allowance((2, 3, 2000, 1)), 0);
+
+        assert_noop!(
+            Asset::approve(RuntimeOrigin::signed(2), 3, 3000, 1, 60),
+            Error::<Test>::InvalidClassId
+        );
+    })
+}
+
+/// Message an account signs for a permit on the test chain
+fn permit_message(prefix: [u8; 8], permit: &impl Encode) -> Vec<u8> {
+    (prefix, System::block_hash(0), permit).encode()
+}
+
+#[test]
+fn permits_are_signed_off_chain() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+        assert_ok!(Asset::do_mint(&1, &2, 2000, 1, 100));
+
+        let permit = TransferPermit {
+            from: 2,
+            to: 3,
+            class_id: 2000,
+            asset_id: 1,
+            amount: 40,
+            nonce: 0,
+            deadline: 10,
+        };
+        let signature = TestSignature(2, permit_message(TRANSFER_PERMIT, &permit));
+
+        // Only the sender can sign
+        assert_noop!(
+            Asset::transfer_with_permit(
+                RuntimeOrigin::signed(4),
+                TransferPermit {
+                    from: 5,
+                    ..permit.clone()
+                },
+                signature.clone()
+            ),
+            Error::<Test>::InvalidPermitSignature
+        );
+        // Permits signed for another chain are rejected
+        assert_noop!(
+            Asset::transfer_with_permit(
+                RuntimeOrigin::signed(4),
+                permit.clone(),
+                TestSignature(2, (TRANSFER_PERMIT, H256::zero(), &permit).encode())
+            ),
+            Error::<Test>::InvalidPermitSignature
+        );
+        // Transfer signatures cannot approve
+        assert_noop!(
+            Asset::approve_with_permit(
+                RuntimeOrigin::signed(4),
+                ApprovalPermit {
+                    from: 2,
+                    spender: 3,
+                    class_id: 2000,
+                    asset_id: 1,
+                    amount: 40,
+                    nonce: 0,
+                    deadline: 10,
+                },
+                signature.clone()
+            ),
+            Error::<Test>::InvalidPermitSignature
+        );
+
+        assert_ok!(Asset::transfer_with_permit(
+            RuntimeOrigin::signed(4),
+            permit.clone(),
+            signature.clone()
+        ));
+        assert_eq!(Asset::balance_of(&3, 2000, 1), 40);
+        assert_eq!(Asset::permit_nonce(2), 1);
+
+        // Permits cannot be replayed
+        assert_noop!(
+            Asset::transfer_with_permit(RuntimeOrigin::signed(4), permit, signature),
+            Error::<Test>::InvalidPermitNonce
+        );
+
+        let permit = ApprovalPermit {
+            from: 2,
+            spender: 4,
+            class_id: 2000,
+            asset_id: 1,
+            amount: 20,
+            nonce: 1,
+            deadline: 10,
+        };
+        let signature = TestSignature(2, permit_message(APPROVAL_PERMIT, &permit));
+
+        System::set_block_number(11);
+        assert_noop!(
+            Asset::approve_with_permit(RuntimeOrigin::signed(4), permit.clone(), signature.clone()),
+            Error::<Test>::PermitExpired
+        );
+
+        System::set_block_number(10);
+        assert_ok!(Asset::approve_with_permit(
+            RuntimeOrigin::signed(4),
+            permit,
+            signature
+        ));
+        assert_eq!(Asset::allowance((2, 4, 2000, 1)), 20);
+        assert_eq!(Asset::permit_nonce(2), 2);
+    })
+}
+
+#[test]
+fn royalties_resolve_per_asset_and_class() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+        assert_ok!(Asset::do_mint(&1, &2, 2000, 1, 100));
+        assert_ok!(Asset::do_mint(&1, &2, 2000, 2, 100));
+
+        let royalty = Royalty {
+            beneficiary: 1,
+            bps: 250,
+            gifts: GiftPolicy::Forbidden,
+        };
+        assert_noop!(
+            Asset::set_royalty(RuntimeOrigin::signed(2), 2000, None, Some(royalty.clone())),
+            Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            Asset::set_royalty(
+                RuntimeOrigin::signed(1),
+                2000,
+                None,
+                Some(Royalty {
+                    bps: 10_001,
+                    ..royalty.clone()
+                })
+            ),
+            Error::<Test>::InvalidRoyalty
+        );
+
+        assert_ok!(Asset::set_royalty(
+            RuntimeOrigin::signed(1),
+            2000,
+            None,
+            Some(royalty.clone())
+        ));
+        assert_ok!(Asset::set_royalty(
+            RuntimeOrigin::signed(1),
+            2000,
+            Some(2),
+            Some(Royalty {
+                bps: 1_000,
+                gifts: GiftPolicy::Exempt,
+                ..royalty.clone()
+            })
+        ));
+        assert_eq!(Asset::royalty_of(2000, 1), Some(royalty.clone()));
+        assert_eq!(
+            Asset::royalty_of(2000, 2).map(|r| r.amount_of(1_000)),
+            Some(100)
+        );
+        assert_eq!(royalty.amount_of(1_000), 25);
+
+        // Gifts of asset 1 are forbidden by the class royalty
+        assert_ok!(Asset::approve(RuntimeOrigin::signed(2), 3, 2000, 1, 10));
+        assert_ok!(Asset::approve(RuntimeOrigin::signed(2), 3, 2000, 2, 10));
+        assert_noop!(
+            Asset::transfer_approved(RuntimeOrigin::signed(3), 2, 3, 2000, 1, 10),
+            Error::<Test>::GiftsForbidden
+        );
+        assert_ok!(Asset::transfer_approved(
+            RuntimeOrigin::signed(3),
+            2,
+            3,
+            2000,
+            2,
+            10
+        ));
+        // Holders cannot move the asset through any other path either
+        assert_noop!(
+            <Asset as Mutate<u64>>::transfer((2000, 1), &2, &3, 10, Preservation::Expendable),
+            Error::<Test>::GiftsForbidden
+        );
+        assert_noop!(
+            Asset::do_batch_transfer_from(&2, &2, &3, 2000, vec![2, 1], vec![1, 1]),
+            Error::<Test>::GiftsForbidden
+        );
+        // Pallets still move the asset in and out of their custody
+        assert_ok!(Asset::do_custody_transfer_from(&2, &2, &4, 2000, 1, 5));
+        assert_ok!(Asset::do_batch_custody_transfer_from(
+            &4,
+            &4,
+            &2,
+            2000,
+            vec![1],
+            vec![5]
+        ));
+        assert_ok!(Asset::do_hold(*b"fee/0000", &2, 2000, 1, 5));
+        assert_ok!(Asset::do_transfer_on_hold(*b"fee/0000", &2, &4, 2000, 1, 5));
+        assert_eq!(Asset::balance_of(&4, 2000, 1), 5);
+        // The class owner still can
+        assert_ok!(Asset::transfer_from(
+            RuntimeOrigin::signed(1),
+            2,
+            3,
+            2000,
+            1,
+            10
+        ));
+        assert_noop!(
+            Asset::multi_transfer(RuntimeOrigin::signed(2), bounded_vec![(3, 2000, 1, 1)]),
+            Error::<Test>::GiftsForbidden
+        );
+        assert_ok!(Asset::do_mint(&1, &1, 2000, 1, 1));
+        assert_ok!(Asset::multi_transfer(
+            RuntimeOrigin::signed(1),
+            bounded_vec![(3, 2000, 1, 1)]
+        ));
+
+        assert_ok!(Asset::set_royalty(
+            RuntimeOrigin::signed(1),
+            2000,
+            None,
+            None
+        ));
+        assert_ok!(Asset::transfer_approved(
+            RuntimeOrigin::signed(3),
+            2,
+            3,
+            2000,
+            1,
+            10
+        ));
+        assert_eq!(Asset::balance_of(&3, 2000, 1), 21);
+    })
+}
+
+#[test]
+fn multi_items_span_classes_and_recipients() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        test_create_class();
+        assert_ok!(Asset::do_create_class(&2, &2, 3000, bounded_vec![0]));
+
+        // Every class of a multi mint must be owned by the caller
+        assert_noop!(
+            Asset::multi_mint(
+                RuntimeOrigin::signed(1),
+                bounded_vec![(2, 2000, 1, 100), (3, 3000, 1, 100)]
+            ),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(Asset::multi_mint(
+            RuntimeOrigin::signed(1),
+            bounded_vec![(1, 2000, 1, 100), (1, 2000, 2, 50), (3, 2000, 1, 10)]
+        ));
+        System::assert_last_event(RuntimeEvent::Asset(crate::Event::MultiMinted {
+            who: 1,
+            items: 3,
+        }));
+        assert_eq!(Asset::supply(2000, 1), 110);
+        assert_ok!(Asset::do_mint(&2, &1, 3000, 1, 20));
+
+        // Transfers are atomic
+        assert_noop!(
+            Asset::multi_transfer(
+                RuntimeOrigin::signed(1),
+                bounded_vec![(2, 2000, 1, 10), (3, 3000, 1, 21)]
+            ),
+            Error::<Test>::NumOverflow
+        );
+        assert_ok!(Asset::multi_transfer(
+            RuntimeOrigin::signed(1),
+            bounded_vec![(2, 2000, 1, 10), (3, 2000, 2, 50), (3, 3000, 1, 20)]
+        ));
+        System::assert_last_event(RuntimeEvent::Asset(crate::Event::MultiTransferred {
+            from: 1,
+            items: 3,
+        }));
+        assert_eq!(Asset::balance_of(&1, 2000, 1), 90);
+        assert_eq!(Asset::balance_of(&2, 2000, 1), 10);
+        assert_eq!(Asset::balance_of(&3, 2000, 2), 50);
+        assert_eq!(Asset::balance_of(&3, 3000, 1), 20);
+        assert_eq!(Asset::balance_of(&1, 3000, 1), 0);
+    })
+}
+
+fn metadata_record(content: &[u8]) -> MetadataRecord {
+    MetadataRecord {
+        uri: bounded_vec![],
+        content_hash: H256(sp_io::hashing::blake2_256(content)),
+        mime: b"application/json".to_vec().try_into().unwrap(),
+        schema_version: 1,
+    }
+}
+
+#[test]
+fn metadata_records_are_validated_and_verified() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+        assert_ok!(Asset::do_create_asset(&1, 2000, 1, bounded_vec![7]));
+
+        let content = br#"{"name":"Sword"}"#;
+        let record = MetadataRecord {
+            uri: b"ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"
+                .to_vec()
+                .try_into()
+                .unwrap(),
+            ..metadata_record(content)
+        };
+        assert!(record.is_valid());
+        for invalid in [
+            metadata_record(content),
+            MetadataRecord {
+                uri: b"://bafy".to_vec().try_into().unwrap(),
+                ..record.clone()
+            },
+            MetadataRecord {
+                uri: b"ipfs://bafy bafy".to_vec().try_into().unwrap(),
+                ..record.clone()
+            },
+            MetadataRecord {
+                mime: b"application".to_vec().try_into().unwrap(),
+                ..record.clone()
+            },
+            MetadataRecord {
+                mime: b"application/json/x".to_vec().try_into().unwrap(),
+                ..record.clone()
+            },
+            MetadataRecord {
+                content_hash: H256::zero(),
+                ..record.clone()
+            },
+            MetadataRecord {
+                schema_version: 0,
+                ..record.clone()
+            },
+            MetadataRecord {
+                schema_version: 2,
+                ..record.clone()
+            },
+        ] {
+            assert_noop!(
+                Asset::set_metadata_record(RuntimeOrigin::signed(1), 2000, Some(1), invalid),
+                Error::<Test>::InvalidMetadata
+            );
+        }
+        assert_noop!(
+            Asset::set_metadata_record(RuntimeOrigin::signed(2), 2000, Some(1), record.clone()),
+            Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            Asset::set_metadata_record(RuntimeOrigin::signed(1), 2000, Some(2), record.clone()),
+            Error::<Test>::InvalidAssetId
+        );
+
+        // Raw metadata verifies against its own bytes
+        assert!(Asset::verify_metadata(2000, Some(1), &[7]));
+        assert!(!Asset::verify_metadata(2000, Some(1), content));
+
+        assert_ok!(Asset::set_metadata_record(
+            RuntimeOrigin::signed(1),
+            2000,
+            Some(1),
+            record.clone()
+        ));
+        assert!(Asset::verify_metadata(2000, Some(1), content));
+        assert!(!Asset::verify_metadata(
+            2000,
+            Some(1),
+            br#"{"name":"Shield"}"#
+        ));
+        assert!(!Asset::verify_metadata(2000, None, content));
+        assert!(!Asset::verify_metadata(2000, Some(2), content));
+
+        assert_ok!(Asset::set_metadata_record(
+            RuntimeOrigin::signed(1),
+            2000,
+            None,
+            record
+        ));
+        assert!(Asset::verify_metadata(2000, None, content));
+    })
+}
+
+#[test]
+fn raw_metadata_migrates_to_records_layout() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<Asset>();
+        unhashed::put_raw(
+            &crate::Classes::<Test>::hashed_key_for(2000),
+            &(1u64, vec![1u8, 2]).encode(),
+        );
+        unhashed::put_raw(
+            &crate::Assets::<Test>::hashed_key_for(2000, 1),
+            &(2000u64, 1u64, vec![3u8]).encode(),
+        );
+
+        migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+        assert_eq!(Asset::on_chain_storage_version(), 1);
+        let class = Asset::classes(2000).unwrap();
+        assert_eq!(class.metadata, Metadata::Raw(bounded_vec![1, 2]));
+        let asset = Asset::assets(2000, 1).unwrap();
+        assert_eq!(asset.metadata, Metadata::Raw(bounded_vec![3]));
+        assert!(Asset::verify_metadata(2000, Some(1), &[3]));
+
+        // Running it again leaves the migrated layout untouched
+        migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+        assert_eq!(Asset::classes(2000).unwrap(), class);
+    })
+}
+
+#[test]
+fn supply_migration_counts_existing_balances() {
+    new_test_ext().execute_with(|| {
+        test_create_class();
+
+        // Balances minted before the supply was tracked
+        StorageVersion::new(1).put::<Asset>();
+        crate::Balances::<Test>::insert((2, 2000, 1), 3);
+        crate::Balances::<Test>::insert((3, 2000, 1), 4);
+        crate::Balances::<Test>::insert((3, 2000, 2), 1);
+
+        // Too many balances for one block
+        migrations::v2::MigrateToV2::<Test, ConstU32<2>>::on_runtime_upgrade();
+        assert_eq!(Asset::on_chain_storage_version(), 1);
+        assert_eq!(Asset::supply(2000, 1), 0);
+
+        migrations::v2::MigrateToV2::<Test, ConstU32<3>>::on_runtime_upgrade();
+
+        assert_eq!(Asset::on_chain_storage_version(), 2);
+        assert_eq!(Asset::supply(2000, 1), 7);
+        assert_eq!(Asset::supply(2000, 2), 1);
+        assert_noop!(
+            Asset::do_make_class_unique(&1, 2000),
+            Error::<Test>::ClassNotEmpty
+        );
+    })
+}
+
+#[test]
+fn freeze_on_mint_covers_assets_minted_before_supply_tracking() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        test_create_class();
+        assert_ok!(Asset::do_create_asset(&1, 2000, 1, bounded_vec![0]));
+
+        // Balance minted before the supply was tracked
+        StorageVersion::new(1).put::<Asset>();
+        crate::Balances::<Test>::insert((2, 2000, 1), 3);
+        assert_ok!(Asset::freeze_metadata_on_mint(
+            RuntimeOrigin::signed(1),
+            2000
+        ));
+
+        migrations::v2::MigrateToV2::<Test, ConstU32<100>>::on_runtime_upgrade();
+
+        assert!(Asset::is_metadata_frozen(2000, Some(1)));
+        assert_noop!(
+            Asset::update_asset_metadata(RuntimeOrigin::signed(1), 2000, 1, bounded_vec![1]),
+            Error::<Test>::MetadataFrozen
+        );
+    })
+}
+
+#[test]
+fn frozen_metadata_cannot_change() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        test_create_class();
+        assert_ok!(Asset::do_create_asset(&1, 2000, 1, bounded_vec![0]));
+
+        assert_noop!(
+            Asset::freeze_class_metadata(RuntimeOrigin::signed(2), 2000),
+            Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            Asset::freeze_asset_metadata(RuntimeOrigin::signed(1), 2000, 2),
+            Error::<Test>::InvalidAssetId
+        );
+
+        assert_ok!(Asset::freeze_class_metadata(RuntimeOrigin::signed(1), 2000));
+        System::assert_last_event(RuntimeEvent::Asset(crate::Event::ClassMetadataFrozen {
+            class_id: 2000,
+            who: 1,
+        }));
+        assert_noop!(
+            Asset::update_class_metadata(RuntimeOrigin::signed(1), 2000, bounded_vec![1]),
+            Error::<Test>::MetadataFrozen
+        );
+        assert_noop!(
+            Asset::set_metadata_record(RuntimeOrigin::signed(1), 2000, None, {
+                let mut record = metadata_record(b"{}");
+                record.uri = b"ipfs://bafy".to_vec().try_into().unwrap();
+                record
+            }),
+            Error::<Test>::MetadataFrozen
+        );
+        assert_noop!(
+            Asset::freeze_class_metadata(RuntimeOrigin::signed(1), 2000),
+            Error::<Test>::MetadataFrozen
+        );
+
+        // Asset metadata stays mutable until the asset is frozen itself
+        assert_ok!(Asset::update_asset_metadata(
+            RuntimeOrigin::signed(1),
+            2000,
+            1,
+            bounded_vec![1]
+        ));
+        assert_ok!(Asset::freeze_asset_metadata(
+            RuntimeOrigin::signed(1),
+            2000,
+            1
+        ));
+        System::assert_last_event(RuntimeEvent::Asset(crate::Event::AssetMetadataFrozen {
+            class_id: 2000,
+            asset_id: 1,
+        }));
+        assert_noop!(
+            Asset::update_asset_metadata(RuntimeOrigin::signed(1), 2000, 1, bounded_vec![2]),
+            Error::<Test>::MetadataFrozen
+        );
+        assert!(Asset::verify_metadata(2000, Some(1), &[1]));
+    })
+}
+
+#[test]
+fn class_policy_freezes_asset_metadata_on_mint() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        test_create_class();
+        assert_ok!(Asset::do_create_asset(&1, 2000, 1, bounded_vec![0]));
+        assert_ok!(Asset::do_create_asset(&1, 2000, 2, bounded_vec![0]));
+        assert_ok!(Asset::do_create_asset(&1, 2000, 3, bounded_vec![0]));
+        assert_ok!(Asset::do_mint(&1, &2, 2000, 3, 1));
+
+        assert_ok!(Asset::freeze_metadata_on_mint(
+            RuntimeOrigin::signed(1),
+            2000
+        ));
+        assert_noop!(
+            Asset::freeze_metadata_on_mint(RuntimeOrigin::signed(1), 2000),
+            Error::<Test>::FreezeOnMintAlreadyEnabled
+        );
+
+        // Assets minted before the policy are frozen while they have supply
+        assert!(Asset::is_metadata_frozen(2000, Some(3)));
+        assert!(!Asset::is_metadata_frozen(2000, Some(1)));
+        assert_ok!(Asset::update_asset_metadata(
+            RuntimeOrigin::signed(1),
+            2000,
+            1,
+            bounded_vec![1]
+        ));
+
+        assert_ok!(Asset::mint(RuntimeOrigin::signed(1), 2, 2000, 1, 1));
+        assert_eq!(Asset::frozen_asset_metadata(2000, 1), Some(()));
+        assert_noop!(
+            Asset::update_asset_metadata(RuntimeOrigin::signed(1), 2000, 1, bounded_vec![2]),
+            Error::<Test>::MetadataFrozen
+        );
+
+        // Burning the supply does not thaw the metadata
+        assert_ok!(Asset::burn(RuntimeOrigin::signed(1), 2, 2000, 1, 1));
+        assert!(Asset::is_metadata_frozen(2000, Some(1)));
+
+        // Fungible mints follow the policy too
+        assert_ok!(<Asset as Mutate<u64>>::mint_into((2000, 2), &2, 10));
+        assert!(Asset::is_metadata_frozen(2000, Some(2)));
+        assert_noop!(
+            Asset::update_asset_metadata(RuntimeOrigin::signed(1), 2000, 2, bounded_vec![2]),
+            Error::<Test>::MetadataFrozen
+        );
+        // The class metadata is not affected
+        assert_ok!(Asset::update_class_metadata(
+            RuntimeOrigin::signed(1),
+            2000,
+            bounded_vec![1]
+        ));
+    })
+}
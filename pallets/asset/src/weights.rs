@@ -6,6 +6,8 @@
 //! WORST CASE MAP SIZE: `1000000`
 //! HOSTNAME: `mauricio-HP-Pavilion-15-Notebook-PC`, CPU: `Intel(R) Core(TM) i5-4210U CPU @ 1.70GHz`
 //! EXECUTION: ``, WASM-EXECUTION: `Compiled`, CHAIN: `Some("dev")`, DB CACHE: 1024
+//!
+//! `multi_transfer` and `multi_mint` are hand-written estimates until they are benchmarked.
 
 // Executed Command:
 // ./target/release/node-template
@@ -44,6 +46,8 @@ pub trait WeightInfo {
 	fn batch_transfer_from() -> Weight;
 	fn update_class_metadata() -> Weight;
 	fn update_asset_metadata() -> Weight;
+	fn multi_transfer(n: u32, ) -> Weight;
+	fn multi_mint(n: u32, ) -> Weight;
 }
 
 /// Weight functions for `sugarfunge_asset`.
@@ -187,4 +191,77 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(T::DbWeight::get().reads(2))
 			.saturating_add(T::DbWeight::get().writes(1))
 	}
+	/// Storage: `Asset::AssetRoyalties` (r:128 w:0)
+	/// Proof: `Asset::AssetRoyalties` (`max_values`: None, `max_size`: Some(98), added: 2573, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::ClassRoyalties` (r:128 w:0)
+	/// Proof: `Asset::ClassRoyalties` (`max_values`: None, `max_size`: Some(67), added: 2542, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::FrozenAccounts` (r:128 w:0)
+	/// Proof: `Asset::FrozenAccounts` (`max_values`: None, `max_size`: Some(72), added: 2547, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::FrozenAssets` (r:128 w:0)
+	/// Proof: `Asset::FrozenAssets` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::Lent` (r:128 w:0)
+	/// Proof: `Asset::Lent` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::VestingLocked` (r:128 w:0)
+	/// Proof: `Asset::VestingLocked` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::Held` (r:128 w:0)
+	/// Proof: `Asset::Held` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::Balances` (r:256 w:256)
+	/// Proof: `Asset::Balances` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::UniqueClasses` (r:128 w:0)
+	/// Proof: `Asset::UniqueClasses` (`max_values`: None, `max_size`: Some(24), added: 2499, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::UniqueOwners` (r:0 w:128)
+	/// Proof: `Asset::UniqueOwners` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::CheckpointedClasses` (r:128 w:0)
+	/// Proof: `Asset::CheckpointedClasses` (`max_values`: None, `max_size`: Some(28), added: 2503, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::CheckpointCount` (r:256 w:256)
+	/// Proof: `Asset::CheckpointCount` (`max_values`: None, `max_size`: Some(100), added: 2575, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::Checkpoints` (r:256 w:512)
+	/// Proof: `Asset::Checkpoints` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::Destroying` (r:128 w:0)
+	/// Proof: `Asset::Destroying` (`max_values`: None, `max_size`: Some(568), added: 3043, mode: `MaxEncodedLen`)
+	/// The range of component `n` is `[1, 128]`.
+	fn multi_transfer(n: u32, ) -> Weight {
+		// Estimated by hand, not measured: the benchmark has not been run yet, so the ref time
+		// is a guess and the proof size assumes the worst case of the storage listed above.
+		Weight::from_parts(24_915_000, 0)
+			.saturating_add(Weight::from_parts(0, 3577))
+			.saturating_add(Weight::from_parts(101_847_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().reads((16_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes((9_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 41521).saturating_mul(n.into()))
+	}
+	/// Storage: `Asset::Classes` (r:128 w:0)
+	/// Proof: `Asset::Classes` (`max_values`: None, `max_size`: Some(4154), added: 6629, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::Destroying` (r:128 w:0)
+	/// Proof: `Asset::Destroying` (`max_values`: None, `max_size`: Some(568), added: 3043, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::Balances` (r:128 w:128)
+	/// Proof: `Asset::Balances` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::UniqueClasses` (r:128 w:0)
+	/// Proof: `Asset::UniqueClasses` (`max_values`: None, `max_size`: Some(24), added: 2499, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::UniqueOwners` (r:0 w:128)
+	/// Proof: `Asset::UniqueOwners` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::CheckpointedClasses` (r:128 w:0)
+	/// Proof: `Asset::CheckpointedClasses` (`max_values`: None, `max_size`: Some(28), added: 2503, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::CheckpointCount` (r:128 w:128)
+	/// Proof: `Asset::CheckpointCount` (`max_values`: None, `max_size`: Some(100), added: 2575, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::Checkpoints` (r:128 w:256)
+	/// Proof: `Asset::Checkpoints` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::Supply` (r:128 w:128)
+	/// Proof: `Asset::Supply` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::FreezeMetadataOnMint` (r:128 w:0)
+	/// Proof: `Asset::FreezeMetadataOnMint` (`max_values`: None, `max_size`: Some(24), added: 2499, mode: `MaxEncodedLen`)
+	/// Storage: `Asset::FrozenAssetMetadata` (r:128 w:128)
+	/// Proof: `Asset::FrozenAssetMetadata` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// The range of component `n` is `[1, 128]`.
+	fn multi_mint(n: u32, ) -> Weight {
+		// Estimated by hand, not measured: the benchmark has not been run yet, so the ref time
+		// is a guess and the proof size assumes the worst case of the storage listed above.
+		Weight::from_parts(31_208_000, 0)
+			.saturating_add(Weight::from_parts(0, 7619))
+			.saturating_add(Weight::from_parts(96_530_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads((10_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes((7_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 30016).saturating_mul(n.into()))
+	}
 }
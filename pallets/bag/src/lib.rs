@@ -1,16 +1,26 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use codec::{Decode, Encode, MaxEncodedLen};
+use codec::{Decode, Encode, EncodeLike, MaxEncodedLen};
 use frame_support::pallet_prelude::DispatchError;
 use frame_support::{
     dispatch::DispatchResult,
     ensure,
-    traits::{Currency, ExistenceRequirement::AllowDeath, Get, ReservableCurrency},
-    PalletId,
+    traits::{
+        Contains, Currency,
+        ExistenceRequirement::{AllowDeath, KeepAlive},
+        Get, ReservableCurrency,
+    },
+    BoundedVec, PalletId,
 };
+use frame_system::pallet_prelude::BlockNumberFor;
 use scale_info::TypeInfo;
-use sp_arithmetic::traits::UniqueSaturatedInto;
-use sp_runtime::{traits::AccountIdConversion, RuntimeDebug};
+use sp_arithmetic::{
+    helpers_128bit::multiply_by_rational_with_rounding, traits::UniqueSaturatedInto, Rounding,
+};
+use sp_runtime::{
+    traits::{AccountIdConversion, Dispatchable, One, Saturating, Zero},
+    PerThing, Perbill, RuntimeDebug,
+};
 use sp_std::prelude::*;
 use sugarfunge_primitives::Balance;
 
@@ -25,9 +35,13 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+pub mod migrations;
+
 type BalanceOf<T> =
     <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
+pub type ProposalId = u32;
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
@@ -51,9 +65,46 @@ pub mod pallet {
         type CreateBagDeposit: Get<BalanceOf<Self>>;
 
         type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+
+        /// Call proposed by share holders and dispatched as the bag account
+        type Proposal: Parameter
+            + Dispatchable<RuntimeOrigin = <Self as frame_system::Config>::RuntimeOrigin>
+            + EncodeLike;
+
+        /// Calls a bag is allowed to propose
+        type ProposalFilter: Contains<<Self as Config>::Proposal>;
+
+        /// Max size of an encoded proposal
+        #[pallet::constant]
+        type MaxProposalLength: Get<u32>;
+
+        /// Share of total shares that must vote, used when a bag has no settings
+        #[pallet::constant]
+        type DefaultQuorum: Get<Perbill>;
+
+        /// Share of votes in favor required to approve, used when a bag has no settings
+        #[pallet::constant]
+        type DefaultThreshold: Get<Perbill>;
+
+        /// Number of blocks a proposal is open for voting, used when a bag has no settings
+        #[pallet::constant]
+        type DefaultVotingPeriod: Get<BlockNumberFor<Self>>;
     }
 
+    pub type BagSettingsOf<T> = BagSettings<BlockNumberFor<T>>;
+    pub type BagProposalOf<T> = BagProposal<
+        <T as frame_system::Config>::AccountId,
+        BoundedVec<u8, <T as Config>::MaxProposalLength>,
+        BlockNumberFor<T>,
+    >;
+    pub type ReleaseConditionsOf<T> =
+        ReleaseConditions<<T as frame_system::Config>::AccountId, BlockNumberFor<T>>;
+    pub type EscrowOf<T> = Escrow<<T as frame_system::Config>::AccountId, BlockNumberFor<T>>;
+
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     #[pallet::storage]
@@ -67,6 +118,42 @@ pub mod pallet {
     #[pallet::storage]
     pub(super) type NextBagId<T: Config> = StorageMap<_, Blake2_128, T::ClassId, u64, ValueQuery>;
 
+    /// Number of bags of a class that have not been dissolved
+    #[pallet::storage]
+    pub(super) type LiveBags<T: Config> = StorageMap<_, Blake2_128, T::ClassId, u32, ValueQuery>;
+
+    #[pallet::storage]
+    pub(super) type Escrows<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, EscrowOf<T>>;
+
+    #[pallet::storage]
+    pub(super) type Settings<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, BagSettingsOf<T>>;
+
+    #[pallet::storage]
+    pub(super) type NextProposalId<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, ProposalId, ValueQuery>;
+
+    #[pallet::storage]
+    pub(super) type Proposals<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Twox64Concat,
+        ProposalId,
+        BagProposalOf<T>,
+    >;
+
+    #[pallet::storage]
+    pub(super) type Votes<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Twox64Concat, ProposalId>,
+            NMapKey<Blake2_128Concat, T::AccountId>,
+        ),
+        Vote,
+    >;
+
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
@@ -84,11 +171,82 @@ pub mod pallet {
         Deposit {
             bag: T::AccountId,
             who: T::AccountId,
+            native: BalanceOf<T>,
         },
         Sweep {
             bag: T::AccountId,
             who: T::AccountId,
             to: T::AccountId,
+            native: BalanceOf<T>,
+        },
+        Unregister {
+            who: T::AccountId,
+            class_id: T::ClassId,
+        },
+        Dissolved {
+            bag: T::AccountId,
+            creator: T::AccountId,
+            refund: BalanceOf<T>,
+        },
+        Withdraw {
+            bag: T::AccountId,
+            who: T::AccountId,
+            shares: Balance,
+            class_ids: Vec<T::ClassId>,
+            asset_ids: Vec<Vec<T::AssetId>>,
+            amounts: Vec<Vec<Balance>>,
+            native: BalanceOf<T>,
+        },
+        EscrowCreated {
+            bag: T::AccountId,
+            conditions: ReleaseConditionsOf<T>,
+        },
+        Disputed {
+            bag: T::AccountId,
+            who: T::AccountId,
+        },
+        Released {
+            bag: T::AccountId,
+            arbiter: T::AccountId,
+        },
+        Refunded {
+            bag: T::AccountId,
+            arbiter: T::AccountId,
+            to: T::AccountId,
+        },
+        Split {
+            bag: T::AccountId,
+            arbiter: T::AccountId,
+            recipients: Vec<T::AccountId>,
+            percents: Vec<Perbill>,
+        },
+        SettingsUpdated {
+            bag: T::AccountId,
+            settings: BagSettingsOf<T>,
+        },
+        Proposed {
+            bag: T::AccountId,
+            proposal_id: ProposalId,
+            who: T::AccountId,
+        },
+        Voted {
+            bag: T::AccountId,
+            proposal_id: ProposalId,
+            who: T::AccountId,
+            aye: bool,
+            shares: Balance,
+        },
+        Closed {
+            bag: T::AccountId,
+            proposal_id: ProposalId,
+            ayes: Balance,
+            nays: Balance,
+            approved: bool,
+        },
+        Executed {
+            bag: T::AccountId,
+            proposal_id: ProposalId,
+            result: DispatchResult,
         },
     }
 
@@ -103,6 +261,27 @@ pub mod pallet {
         InvalidBagOwner,
         InvalidArrayLength,
         InsufficientShares,
+        InvalidShares,
+        NumOverflow,
+        InvalidSettings,
+        InvalidProposal,
+        ProposalTooLong,
+        ProposalNotAllowed,
+        ProposalClosed,
+        VotingEnded,
+        VotingNotEnded,
+        AlreadyVoted,
+        BagNotEmpty,
+        BagClassInUse,
+        InvalidConditions,
+        InvalidEscrow,
+        EscrowLocked,
+        EscrowSettled,
+        InvalidArbiter,
+        DisputeWindowClosed,
+        InvalidPercents,
+        SharesNotRecorded,
+        NotEscrowPayer,
     }
 
     // Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -147,10 +326,11 @@ pub mod pallet {
             class_ids: Vec<T::ClassId>,
             asset_ids: Vec<Vec<T::AssetId>>,
             amounts: Vec<Vec<Balance>>,
+            native: BalanceOf<T>,
         ) -> DispatchResultWithPostInfo {
             let who = ensure_signed(origin)?;
 
-            Self::do_deposit(&who, &bag, class_ids, asset_ids, amounts)?;
+            Self::do_deposit(&who, &bag, class_ids, asset_ids, amounts, native)?;
 
             Ok(().into())
         }
@@ -168,6 +348,195 @@ pub mod pallet {
 
             Ok(().into())
         }
+
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn withdraw(
+            origin: OriginFor<T>,
+            bag: T::AccountId,
+            shares: Balance,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_withdraw(&who, &bag, shares)?;
+
+            Ok(().into())
+        }
+
+        /// Removes a bag once all shares are burned and it holds no assets, returning
+        /// the creation deposit to the creator. Callable by anyone.
+        #[pallet::call_index(10)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn dissolve(origin: OriginFor<T>, bag: T::AccountId) -> DispatchResultWithPostInfo {
+            let _who = ensure_signed(origin)?;
+
+            Self::do_dissolve(&bag)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(11)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn unregister(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_unregister(&who, class_id)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(12)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn create_escrow(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            owners: Vec<T::AccountId>,
+            shares: Vec<Balance>,
+            conditions: ReleaseConditionsOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_create_escrow(&who, class_id, &owners, &shares, conditions)?;
+
+            Ok(().into())
+        }
+
+        /// Disputes an escrow bag within its dispute window. Callable by the bag
+        /// creator or a share holder; only the arbiter can settle a disputed bag.
+        #[pallet::call_index(13)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn dispute(origin: OriginFor<T>, bag: T::AccountId) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_dispute(&who, &bag)?;
+
+            Ok(().into())
+        }
+
+        /// Approves release of an escrow bag to its share holders, resolving any dispute.
+        #[pallet::call_index(14)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn release(origin: OriginFor<T>, bag: T::AccountId) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_release(&who, &bag)?;
+
+            Ok(().into())
+        }
+
+        /// Returns all escrow holdings to the payer who funded the escrow, or to the bag
+        /// creator if nothing was deposited.
+        #[pallet::call_index(15)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn refund(origin: OriginFor<T>, bag: T::AccountId) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_refund(&who, &bag)?;
+
+            Ok(().into())
+        }
+
+        /// Splits all escrow holdings between `recipients` by `percents`, which must
+        /// add up to 100%.
+        #[pallet::call_index(16)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn split(
+            origin: OriginFor<T>,
+            bag: T::AccountId,
+            recipients: Vec<T::AccountId>,
+            percents: Vec<Perbill>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_split(&who, &bag, recipients, percents)?;
+
+            Ok(().into())
+        }
+
+        /// Transfers assets out of the bag. Must be dispatched by the bag account
+        /// through an approved proposal.
+        #[pallet::call_index(5)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn transfer(
+            origin: OriginFor<T>,
+            to: T::AccountId,
+            class_id: T::ClassId,
+            asset_ids: Vec<T::AssetId>,
+            amounts: Vec<Balance>,
+        ) -> DispatchResultWithPostInfo {
+            let bag = ensure_signed(origin)?;
+            ensure!(Bags::<T>::contains_key(&bag), Error::<T>::InvalidBag);
+            Self::ensure_released(&bag)?;
+
+            sugarfunge_asset::Pallet::<T>::do_batch_custody_transfer_from(
+                &bag, &bag, &to, class_id, asset_ids, amounts,
+            )?;
+
+            Ok(().into())
+        }
+
+        /// Updates governance settings. Must be dispatched by the bag account
+        /// through an approved proposal.
+        #[pallet::call_index(6)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn set_settings(
+            origin: OriginFor<T>,
+            settings: BagSettingsOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let bag = ensure_signed(origin)?;
+
+            Self::do_set_settings(&bag, settings)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(7)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn propose(
+            origin: OriginFor<T>,
+            bag: T::AccountId,
+            call: Box<<T as Config>::Proposal>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_propose(&who, &bag, *call)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(8)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn vote(
+            origin: OriginFor<T>,
+            bag: T::AccountId,
+            proposal_id: ProposalId,
+            aye: bool,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_vote(&who, &bag, proposal_id, aye)?;
+
+            Ok(().into())
+        }
+
+        /// Closes a proposal once voting ended or its outcome can no longer change,
+        /// dispatching the call as the bag account if approved. Callable by anyone.
+        #[pallet::call_index(9)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn close(
+            origin: OriginFor<T>,
+            bag: T::AccountId,
+            proposal_id: ProposalId,
+        ) -> DispatchResultWithPostInfo {
+            let _who = ensure_signed(origin)?;
+
+            Self::do_close(&bag, proposal_id)?;
+
+            Ok(().into())
+        }
     }
 }
 
@@ -183,6 +552,8 @@ pub struct BagClass<AccountId, ClassId> {
 pub struct Bag<AccountId, ClassId, AssetId> {
     /// The operator of the bag
     pub operator: AccountId,
+    /// The account that paid the creation deposit
+    pub creator: AccountId,
     /// The class_id for minting shares
     pub class_id: ClassId,
     /// The asset_id for minting shares
@@ -191,6 +562,79 @@ pub struct Bag<AccountId, ClassId, AssetId> {
     pub total_shares: Balance,
 }
 
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct ReleaseConditions<AccountId, BlockNumber> {
+    /// Earliest block the bag can be swept or withdrawn from
+    pub release_after: Option<BlockNumber>,
+    /// Account that must approve the release and settles disputes
+    pub arbiter: Option<AccountId>,
+    /// Number of blocks after `release_after` during which the bag can be disputed
+    pub dispute_window: BlockNumber,
+}
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum EscrowStatus {
+    Pending,
+    Approved,
+    Disputed,
+    Settled,
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Escrow<AccountId, BlockNumber> {
+    /// Conditions set when the bag was created
+    pub conditions: ReleaseConditions<AccountId, BlockNumber>,
+    /// Block the dispute window closes
+    pub dispute_end: BlockNumber,
+    /// Escrow status
+    pub status: EscrowStatus,
+    /// Account funding the escrow, recorded on its first deposit
+    pub payer: Option<AccountId>,
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BagSettings<BlockNumber> {
+    /// Share of total shares that must vote for a proposal to pass
+    pub quorum: Perbill,
+    /// Share of votes in favor required for a proposal to pass
+    pub threshold: Perbill,
+    /// Number of blocks a proposal is open for voting
+    pub voting_period: BlockNumber,
+}
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum ProposalStatus {
+    Active,
+    Approved,
+    Rejected,
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BagProposal<AccountId, EncodedCall, BlockNumber> {
+    /// The share holder who made the proposal
+    pub proposer: AccountId,
+    /// The encoded call dispatched as the bag account
+    pub call: EncodedCall,
+    /// Shares voting in favor
+    pub ayes: Balance,
+    /// Shares voting against
+    pub nays: Balance,
+    /// Block whose closing share balances are counted
+    pub snapshot: BlockNumber,
+    /// Last block open for voting
+    pub end: BlockNumber,
+    /// Proposal status
+    pub status: ProposalStatus,
+}
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Vote {
+    /// Whether the vote is in favor
+    pub aye: bool,
+    /// Shares counted for the vote
+    pub shares: Balance,
+}
+
 impl<T: Config> Pallet<T> {
     pub fn do_register(
         who: &T::AccountId,
@@ -204,6 +648,8 @@ impl<T: Config> Pallet<T> {
 
         let owner = <T as Config>::PalletId::get().into_account_truncating();
         sugarfunge_asset::Pallet::<T>::do_create_class(&who, &owner, class_id, metadata.clone())?;
+        // Shares are voted at their balance before a proposal was made
+        sugarfunge_asset::Pallet::<T>::do_enable_checkpoints(&owner, class_id)?;
 
         let bag_class = BagClass {
             operator: who.clone(),
@@ -265,12 +711,14 @@ impl<T: Config> Pallet<T> {
 
         let new_bag = Bag {
             operator: operator.clone(),
+            creator: who.clone(),
             class_id,
             asset_id,
             total_shares: shares.iter().sum(),
         };
 
         Bags::<T>::insert(&bag, &new_bag);
+        LiveBags::<T>::mutate(class_id, |count| *count = count.saturating_add(1));
 
         Self::deposit_event(Event::Created {
             bag: bag.clone(),
@@ -283,15 +731,78 @@ impl<T: Config> Pallet<T> {
         Ok(bag.clone())
     }
 
+    /// Removes a bag class with no live bags. The underlying asset class is kept,
+    /// so the class id can not be registered again.
+    pub fn do_unregister(who: &T::AccountId, class_id: T::ClassId) -> DispatchResult {
+        let bag_class = BagClasses::<T>::get(class_id).ok_or(Error::<T>::InvalidBagClass)?;
+        ensure!(bag_class.operator == *who, Error::<T>::InvalidBagOperator);
+        ensure!(LiveBags::<T>::get(class_id) == 0, Error::<T>::BagClassInUse);
+
+        BagClasses::<T>::remove(class_id);
+        NextBagId::<T>::remove(class_id);
+        LiveBags::<T>::remove(class_id);
+
+        Self::deposit_event(Event::Unregister {
+            who: who.clone(),
+            class_id,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_dissolve(bag: &T::AccountId) -> DispatchResult {
+        let bag_info = Bags::<T>::get(bag).ok_or(Error::<T>::InvalidBag)?;
+
+        ensure!(bag_info.total_shares == 0, Error::<T>::InvalidShares);
+        let (class_ids, _, _) = Self::bag_balances(bag)?;
+        ensure!(
+            class_ids.is_empty() && Self::native_balance(bag).is_zero(),
+            Error::<T>::BagNotEmpty
+        );
+
+        // Return the creation deposit along with any native dust left in the bag
+        let refund = <T as Config>::Currency::free_balance(bag);
+        <T as Config>::Currency::transfer(bag, &bag_info.creator, refund, AllowDeath)?;
+
+        Bags::<T>::remove(bag);
+        Escrows::<T>::remove(bag);
+        Settings::<T>::remove(bag);
+        NextProposalId::<T>::remove(bag);
+        let _ = Proposals::<T>::clear_prefix(bag, u32::MAX, None);
+        let _ = Votes::<T>::clear_prefix((bag,), u32::MAX, None);
+        LiveBags::<T>::mutate(bag_info.class_id, |count| *count = count.saturating_sub(1));
+
+        Self::deposit_event(Event::Dissolved {
+            bag: bag.clone(),
+            creator: bag_info.creator,
+            refund,
+        });
+
+        Ok(())
+    }
+
     pub fn do_deposit(
         who: &T::AccountId,
         bag: &T::AccountId,
         class_ids: Vec<T::ClassId>,
         asset_ids: Vec<Vec<T::AssetId>>,
         amounts: Vec<Vec<Balance>>,
+        native: BalanceOf<T>,
     ) -> DispatchResult {
         ensure!(Bags::<T>::contains_key(&bag), Error::<T>::InvalidBag);
 
+        // Unsettled escrows are funded by a single payer, so a refund returns every deposit
+        Escrows::<T>::try_mutate(bag, |escrow| -> DispatchResult {
+            if let Some(escrow) = escrow
+                .as_mut()
+                .filter(|escrow| escrow.status != EscrowStatus::Settled)
+            {
+                let payer = escrow.payer.get_or_insert_with(|| who.clone());
+                ensure!(payer == who, Error::<T>::NotEscrowPayer);
+            }
+            Ok(())
+        })?;
+
         ensure!(
             class_ids.len() == amounts.len(),
             Error::<T>::InvalidArrayLength
@@ -317,9 +828,14 @@ impl<T: Config> Pallet<T> {
             )?;
         }
 
+        if !native.is_zero() {
+            <T as Config>::Currency::transfer(who, bag, native, KeepAlive)?;
+        }
+
         Self::deposit_event(Event::Deposit {
             bag: bag.clone(),
             who: who.clone(),
+            native,
         });
 
         Ok(().into())
@@ -331,11 +847,12 @@ impl<T: Config> Pallet<T> {
         bag: &T::AccountId,
     ) -> Result<(Vec<T::ClassId>, Vec<Vec<T::AssetId>>, Vec<Vec<Balance>>), DispatchError> {
         let bag_info = Bags::<T>::get(bag).ok_or(Error::<T>::InvalidBag)?;
+        Self::ensure_released(bag)?;
 
         let shares =
             sugarfunge_asset::Pallet::<T>::balance_of(who, bag_info.class_id, bag_info.asset_id);
         ensure!(
-            shares == bag_info.total_shares,
+            bag_info.total_shares > 0 && shares == bag_info.total_shares,
             Error::<T>::InsufficientShares
         );
 
@@ -350,8 +867,402 @@ impl<T: Config> Pallet<T> {
             bag_info.total_shares,
         )?;
 
-        let balances = sugarfunge_asset::Pallet::<T>::balances_of_owner(&bag)?;
-        let balances = balances.iter().fold(
+        Bags::<T>::mutate(bag, |bag_info| {
+            if let Some(bag_info) = bag_info {
+                bag_info.total_shares = 0;
+            }
+        });
+
+        let balances = Self::bag_balances(bag)?;
+
+        for (idx, class_id) in balances.0.iter().enumerate() {
+            sugarfunge_asset::Pallet::<T>::do_batch_custody_transfer_from(
+                &bag,
+                &bag,
+                to,
+                *class_id,
+                balances.1[idx].clone(),
+                balances.2[idx].clone(),
+            )?;
+        }
+
+        let native = Self::native_balance(bag);
+        if !native.is_zero() {
+            <T as Config>::Currency::transfer(bag, to, native, KeepAlive)?;
+        }
+
+        Self::deposit_event(Event::Sweep {
+            bag: bag.clone(),
+            who: who.clone(),
+            to: to.clone(),
+            native,
+        });
+
+        Ok(balances)
+    }
+
+    /// Burn `shares` of `who` and transfer the proportional fraction of every bag balance.
+    /// Amounts are rounded down, the remaining dust stays in the bag.
+    pub fn do_withdraw(
+        who: &T::AccountId,
+        bag: &T::AccountId,
+        shares: Balance,
+    ) -> Result<(Vec<T::ClassId>, Vec<Vec<T::AssetId>>, Vec<Vec<Balance>>), DispatchError> {
+        let bag_info = Bags::<T>::get(bag).ok_or(Error::<T>::InvalidBag)?;
+        Self::ensure_released(bag)?;
+
+        ensure!(
+            shares > 0 && shares <= bag_info.total_shares,
+            Error::<T>::InvalidShares
+        );
+
+        let balance =
+            sugarfunge_asset::Pallet::<T>::balance_of(who, bag_info.class_id, bag_info.asset_id);
+        ensure!(balance >= shares, Error::<T>::InsufficientShares);
+
+        let (class_ids, asset_ids, balances) = Self::bag_balances(bag)?;
+
+        let mut amounts = Vec::<Vec<Balance>>::new();
+        for class_balances in balances.iter() {
+            let mut class_amounts = Vec::new();
+            for balance in class_balances.iter() {
+                let amount = multiply_by_rational_with_rounding(
+                    *balance,
+                    shares,
+                    bag_info.total_shares,
+                    Rounding::Down,
+                )
+                .ok_or(Error::<T>::NumOverflow)?;
+                class_amounts.push(amount);
+            }
+            amounts.push(class_amounts);
+        }
+
+        let native: BalanceOf<T> = multiply_by_rational_with_rounding(
+            Self::native_balance(bag).unique_saturated_into(),
+            shares,
+            bag_info.total_shares,
+            Rounding::Down,
+        )
+        .ok_or(Error::<T>::NumOverflow)?
+        .unique_saturated_into();
+
+        let operator: T::AccountId = <T as Config>::PalletId::get().into_account_truncating();
+
+        // Burn withdrawn shares
+        sugarfunge_asset::Pallet::<T>::do_burn(
+            &operator,
+            who,
+            bag_info.class_id,
+            bag_info.asset_id,
+            shares,
+        )?;
+
+        Bags::<T>::try_mutate(bag, |bag_info| -> DispatchResult {
+            let bag_info = bag_info.as_mut().ok_or(Error::<T>::InvalidBag)?;
+            bag_info.total_shares = bag_info
+                .total_shares
+                .checked_sub(shares)
+                .ok_or(Error::<T>::NumOverflow)?;
+            Ok(())
+        })?;
+
+        for (idx, class_id) in class_ids.iter().enumerate() {
+            sugarfunge_asset::Pallet::<T>::do_batch_custody_transfer_from(
+                bag,
+                bag,
+                who,
+                *class_id,
+                asset_ids[idx].clone(),
+                amounts[idx].clone(),
+            )?;
+        }
+
+        if !native.is_zero() {
+            <T as Config>::Currency::transfer(bag, who, native, KeepAlive)?;
+        }
+
+        Self::deposit_event(Event::Withdraw {
+            bag: bag.clone(),
+            who: who.clone(),
+            shares,
+            class_ids: class_ids.clone(),
+            asset_ids: asset_ids.clone(),
+            amounts: amounts.clone(),
+            native,
+        });
+
+        Ok((class_ids, asset_ids, amounts))
+    }
+
+    pub fn do_create_escrow(
+        who: &T::AccountId,
+        class_id: T::ClassId,
+        owners: &Vec<T::AccountId>,
+        shares: &Vec<Balance>,
+        conditions: ReleaseConditionsOf<T>,
+    ) -> Result<T::AccountId, DispatchError> {
+        ensure!(
+            conditions.release_after.is_some() || conditions.arbiter.is_some(),
+            Error::<T>::InvalidConditions
+        );
+        ensure!(
+            conditions.dispute_window.is_zero() || conditions.arbiter.is_some(),
+            Error::<T>::InvalidConditions
+        );
+
+        let bag = Self::do_create(who, class_id, owners, shares)?;
+
+        let now = frame_system::Pallet::<T>::block_number();
+        let dispute_end = conditions
+            .release_after
+            .unwrap_or(now)
+            .max(now)
+            .saturating_add(conditions.dispute_window);
+
+        Escrows::<T>::insert(
+            &bag,
+            Escrow {
+                conditions: conditions.clone(),
+                dispute_end,
+                status: EscrowStatus::Pending,
+                payer: None,
+            },
+        );
+
+        Self::deposit_event(Event::EscrowCreated {
+            bag: bag.clone(),
+            conditions,
+        });
+
+        Ok(bag)
+    }
+
+    /// Ensures assets can leave the bag through share holders
+    pub fn ensure_released(bag: &T::AccountId) -> DispatchResult {
+        let escrow = match Escrows::<T>::get(bag) {
+            Some(escrow) => escrow,
+            None => return Ok(()),
+        };
+
+        match escrow.status {
+            EscrowStatus::Settled => return Ok(()),
+            EscrowStatus::Disputed => return Err(Error::<T>::EscrowLocked.into()),
+            EscrowStatus::Pending => ensure!(
+                escrow.conditions.arbiter.is_none(),
+                Error::<T>::EscrowLocked
+            ),
+            EscrowStatus::Approved => {}
+        }
+
+        let now = frame_system::Pallet::<T>::block_number();
+        if let Some(release_after) = escrow.conditions.release_after {
+            ensure!(now >= release_after, Error::<T>::EscrowLocked);
+        }
+        if !escrow.conditions.dispute_window.is_zero() {
+            ensure!(now > escrow.dispute_end, Error::<T>::EscrowLocked);
+        }
+
+        Ok(())
+    }
+
+    pub fn do_dispute(who: &T::AccountId, bag: &T::AccountId) -> DispatchResult {
+        let bag_info = Bags::<T>::get(bag).ok_or(Error::<T>::InvalidBag)?;
+
+        Escrows::<T>::try_mutate(bag, |escrow| -> DispatchResult {
+            let escrow = escrow.as_mut().ok_or(Error::<T>::InvalidEscrow)?;
+            ensure!(
+                escrow.status != EscrowStatus::Settled,
+                Error::<T>::EscrowSettled
+            );
+            ensure!(
+                !escrow.conditions.dispute_window.is_zero()
+                    && frame_system::Pallet::<T>::block_number() <= escrow.dispute_end,
+                Error::<T>::DisputeWindowClosed
+            );
+
+            let shares = sugarfunge_asset::Pallet::<T>::balance_of(
+                who,
+                bag_info.class_id,
+                bag_info.asset_id,
+            );
+            ensure!(
+                *who == bag_info.creator || shares > 0,
+                Error::<T>::InsufficientShares
+            );
+
+            escrow.status = EscrowStatus::Disputed;
+            Ok(())
+        })?;
+
+        Self::deposit_event(Event::Disputed {
+            bag: bag.clone(),
+            who: who.clone(),
+        });
+
+        Ok(())
+    }
+
+    pub fn do_release(who: &T::AccountId, bag: &T::AccountId) -> DispatchResult {
+        Self::ensure_arbiter(who, bag)?;
+
+        Escrows::<T>::mutate(bag, |escrow| {
+            if let Some(escrow) = escrow {
+                escrow.status = EscrowStatus::Approved;
+            }
+        });
+
+        Self::deposit_event(Event::Released {
+            bag: bag.clone(),
+            arbiter: who.clone(),
+        });
+
+        Ok(())
+    }
+
+    pub fn do_refund(who: &T::AccountId, bag: &T::AccountId) -> DispatchResult {
+        Self::ensure_arbiter(who, bag)?;
+        let bag_info = Bags::<T>::get(bag).ok_or(Error::<T>::InvalidBag)?;
+        let to = Escrows::<T>::get(bag)
+            .and_then(|escrow| escrow.payer)
+            .unwrap_or(bag_info.creator);
+
+        Self::do_pay_out(bag, &[to.clone()], &[Perbill::one()])?;
+
+        Self::deposit_event(Event::Refunded {
+            bag: bag.clone(),
+            arbiter: who.clone(),
+            to,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_split(
+        who: &T::AccountId,
+        bag: &T::AccountId,
+        recipients: Vec<T::AccountId>,
+        percents: Vec<Perbill>,
+    ) -> DispatchResult {
+        Self::ensure_arbiter(who, bag)?;
+
+        ensure!(
+            !recipients.is_empty() && recipients.len() == percents.len(),
+            Error::<T>::InvalidArrayLength
+        );
+        ensure!(
+            recipients.len() <= T::MaxOwners::get() as usize,
+            Error::<T>::InvalidArrayLength
+        );
+        let total = percents
+            .iter()
+            .try_fold(0u32, |total, percent| {
+                total.checked_add(percent.deconstruct())
+            })
+            .ok_or(Error::<T>::InvalidPercents)?;
+        ensure!(
+            total == Perbill::one().deconstruct(),
+            Error::<T>::InvalidPercents
+        );
+
+        Self::do_pay_out(bag, &recipients, &percents)?;
+
+        Self::deposit_event(Event::Split {
+            bag: bag.clone(),
+            arbiter: who.clone(),
+            recipients,
+            percents,
+        });
+
+        Ok(())
+    }
+
+    fn ensure_arbiter(who: &T::AccountId, bag: &T::AccountId) -> DispatchResult {
+        let escrow = Escrows::<T>::get(bag).ok_or(Error::<T>::InvalidEscrow)?;
+        ensure!(
+            escrow.conditions.arbiter.as_ref() == Some(who),
+            Error::<T>::InvalidArbiter
+        );
+        ensure!(
+            escrow.status != EscrowStatus::Settled,
+            Error::<T>::EscrowSettled
+        );
+        Ok(())
+    }
+
+    /// Transfers all bag holdings to `recipients` by `percents` and settles the escrow.
+    /// Amounts are rounded down, the last recipient receives the remainder.
+    fn do_pay_out(
+        bag: &T::AccountId,
+        recipients: &[T::AccountId],
+        percents: &[Perbill],
+    ) -> DispatchResult {
+        let (class_ids, asset_ids, balances) = Self::bag_balances(bag)?;
+        let native = Self::native_balance(bag);
+        let last = recipients.len().saturating_sub(1);
+
+        let mut remaining = balances.clone();
+        let mut remaining_native = native;
+        for (idx, to) in recipients.iter().enumerate() {
+            for (class_idx, class_id) in class_ids.iter().enumerate() {
+                let amounts: Vec<Balance> = balances[class_idx]
+                    .iter()
+                    .enumerate()
+                    .map(|(asset_idx, balance)| {
+                        let amount = if idx == last {
+                            remaining[class_idx][asset_idx]
+                        } else {
+                            percents[idx].mul_floor(*balance)
+                        };
+                        remaining[class_idx][asset_idx] =
+                            remaining[class_idx][asset_idx].saturating_sub(amount);
+                        amount
+                    })
+                    .collect();
+
+                sugarfunge_asset::Pallet::<T>::do_batch_custody_transfer_from(
+                    bag,
+                    bag,
+                    to,
+                    *class_id,
+                    asset_ids[class_idx].clone(),
+                    amounts,
+                )?;
+            }
+
+            let amount = if idx == last {
+                remaining_native
+            } else {
+                percents[idx].mul_floor(native)
+            };
+            remaining_native = remaining_native.saturating_sub(amount);
+            if !amount.is_zero() {
+                <T as Config>::Currency::transfer(bag, to, amount, KeepAlive)?;
+            }
+        }
+
+        Escrows::<T>::mutate(bag, |escrow| {
+            if let Some(escrow) = escrow {
+                escrow.status = EscrowStatus::Settled;
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Native balance held by the bag on behalf of its share holders. The creation
+    /// deposit, or the existential deposit if higher, stays with the bag account.
+    pub fn native_balance(bag: &T::AccountId) -> BalanceOf<T> {
+        let reserved = T::CreateBagDeposit::get().max(<T as Config>::Currency::minimum_balance());
+        <T as Config>::Currency::free_balance(bag).saturating_sub(reserved)
+    }
+
+    /// Non-zero asset balances held by the bag, grouped by class
+    pub fn bag_balances(
+        bag: &T::AccountId,
+    ) -> Result<(Vec<T::ClassId>, Vec<Vec<T::AssetId>>, Vec<Vec<Balance>>), DispatchError> {
+        let balances = sugarfunge_asset::Pallet::<T>::balances_of_owner(bag)?;
+        let balances = balances.iter().filter(|(_, _, balance)| *balance > 0).fold(
             (
                 Vec::<T::ClassId>::new(),
                 Vec::<Vec<T::AssetId>>::new(),
@@ -378,24 +1289,225 @@ impl<T: Config> Pallet<T> {
                 (class_ids, asset_ids, balances)
             },
         );
+        Ok(balances)
+    }
 
-        for (idx, class_id) in balances.0.iter().enumerate() {
-            sugarfunge_asset::Pallet::<T>::do_batch_transfer_from(
-                &bag,
-                &bag,
-                to,
-                *class_id,
-                balances.1[idx].clone(),
-                balances.2[idx].clone(),
-            )?;
+    /// Governance settings of a bag, falling back to the configured defaults
+    pub fn settings(bag: &T::AccountId) -> BagSettingsOf<T> {
+        Settings::<T>::get(bag).unwrap_or_else(|| BagSettings {
+            quorum: T::DefaultQuorum::get(),
+            threshold: T::DefaultThreshold::get(),
+            voting_period: T::DefaultVotingPeriod::get(),
+        })
+    }
+
+    pub fn do_set_settings(bag: &T::AccountId, settings: BagSettingsOf<T>) -> DispatchResult {
+        ensure!(Bags::<T>::contains_key(bag), Error::<T>::InvalidBag);
+        ensure!(
+            settings.threshold.deconstruct() > 0 && !settings.voting_period.is_zero(),
+            Error::<T>::InvalidSettings
+        );
+
+        Settings::<T>::insert(bag, &settings);
+
+        Self::deposit_event(Event::SettingsUpdated {
+            bag: bag.clone(),
+            settings,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_propose(
+        who: &T::AccountId,
+        bag: &T::AccountId,
+        call: <T as Config>::Proposal,
+    ) -> Result<ProposalId, DispatchError> {
+        let bag_info = Bags::<T>::get(bag).ok_or(Error::<T>::InvalidBag)?;
+
+        let shares =
+            sugarfunge_asset::Pallet::<T>::balance_of(who, bag_info.class_id, bag_info.asset_id);
+        ensure!(shares > 0, Error::<T>::InsufficientShares);
+        // Share holders cannot act for an escrow bag before it is released
+        Self::ensure_released(bag)?;
+
+        // Votes count the shares held at the snapshot, which must have been recorded
+        let now = frame_system::Pallet::<T>::block_number();
+        let snapshot = now.saturating_sub(One::one());
+        ensure!(
+            sugarfunge_asset::Pallet::<T>::checkpointed_classes(bag_info.class_id)
+                .map_or(false, |enabled_at| snapshot >= enabled_at),
+            Error::<T>::SharesNotRecorded
+        );
+
+        ensure!(
+            T::ProposalFilter::contains(&call),
+            Error::<T>::ProposalNotAllowed
+        );
+        let call: BoundedVec<u8, T::MaxProposalLength> = call
+            .encode()
+            .try_into()
+            .map_err(|_| Error::<T>::ProposalTooLong)?;
+
+        let proposal_id =
+            NextProposalId::<T>::try_mutate(bag, |id| -> Result<ProposalId, DispatchError> {
+                let current_id = *id;
+                *id = id.checked_add(1).ok_or(Error::<T>::NumOverflow)?;
+                Ok(current_id)
+            })?;
+
+        let end = now.saturating_add(Self::settings(bag).voting_period);
+
+        Proposals::<T>::insert(
+            bag,
+            proposal_id,
+            BagProposal {
+                proposer: who.clone(),
+                call,
+                ayes: 0,
+                nays: 0,
+                snapshot,
+                end,
+                status: ProposalStatus::Active,
+            },
+        );
+
+        Self::deposit_event(Event::Proposed {
+            bag: bag.clone(),
+            proposal_id,
+            who: who.clone(),
+        });
+
+        Ok(proposal_id)
+    }
+
+    pub fn do_vote(
+        who: &T::AccountId,
+        bag: &T::AccountId,
+        proposal_id: ProposalId,
+        aye: bool,
+    ) -> DispatchResult {
+        let bag_info = Bags::<T>::get(bag).ok_or(Error::<T>::InvalidBag)?;
+        let mut proposal =
+            Proposals::<T>::get(bag, proposal_id).ok_or(Error::<T>::InvalidProposal)?;
+
+        ensure!(
+            proposal.status == ProposalStatus::Active,
+            Error::<T>::ProposalClosed
+        );
+        ensure!(
+            frame_system::Pallet::<T>::block_number() <= proposal.end,
+            Error::<T>::VotingEnded
+        );
+        ensure!(
+            !Votes::<T>::contains_key((bag, proposal_id, who)),
+            Error::<T>::AlreadyVoted
+        );
+
+        let shares = Self::shares_at(who, bag_info.class_id, bag_info.asset_id, proposal.snapshot);
+        ensure!(shares > 0, Error::<T>::InsufficientShares);
+
+        if aye {
+            proposal.ayes = proposal
+                .ayes
+                .checked_add(shares)
+                .ok_or(Error::<T>::NumOverflow)?;
+        } else {
+            proposal.nays = proposal
+                .nays
+                .checked_add(shares)
+                .ok_or(Error::<T>::NumOverflow)?;
         }
 
-        Self::deposit_event(Event::Sweep {
+        Proposals::<T>::insert(bag, proposal_id, &proposal);
+        Votes::<T>::insert((bag, proposal_id, who), Vote { aye, shares });
+
+        Self::deposit_event(Event::Voted {
             bag: bag.clone(),
+            proposal_id,
             who: who.clone(),
-            to: to.clone(),
+            aye,
+            shares,
         });
 
-        Ok(balances)
+        Ok(())
+    }
+
+    /// Shares held at the end of `block`, which proposals only take once checkpoints of the
+    /// bag class were recorded
+    fn shares_at(
+        who: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+        block: BlockNumberFor<T>,
+    ) -> Balance {
+        sugarfunge_asset::Pallet::<T>::balance_at(who, class_id, asset_id, block)
+            .unwrap_or_default()
+    }
+
+    pub fn do_close(bag: &T::AccountId, proposal_id: ProposalId) -> DispatchResult {
+        let bag_info = Bags::<T>::get(bag).ok_or(Error::<T>::InvalidBag)?;
+        let mut proposal =
+            Proposals::<T>::get(bag, proposal_id).ok_or(Error::<T>::InvalidProposal)?;
+
+        ensure!(
+            proposal.status == ProposalStatus::Active,
+            Error::<T>::ProposalClosed
+        );
+
+        let settings = Self::settings(bag);
+        let total_shares = bag_info.total_shares;
+        let turnout = proposal.ayes.saturating_add(proposal.nays);
+
+        let approved = proposal.ayes > 0
+            && turnout >= settings.quorum.mul_ceil(total_shares)
+            && proposal.ayes >= settings.threshold.mul_ceil(turnout);
+
+        // Remaining votes cannot change the outcome once ayes alone meet quorum and
+        // threshold over all shares
+        let decided = proposal.ayes > 0
+            && proposal.ayes >= settings.quorum.mul_ceil(total_shares)
+            && proposal.ayes >= settings.threshold.mul_ceil(total_shares);
+
+        ensure!(
+            decided || frame_system::Pallet::<T>::block_number() > proposal.end,
+            Error::<T>::VotingNotEnded
+        );
+        // An approved proposal stays active until the escrow is released again
+        if approved {
+            Self::ensure_released(bag)?;
+        }
+
+        proposal.status = if approved {
+            ProposalStatus::Approved
+        } else {
+            ProposalStatus::Rejected
+        };
+        Proposals::<T>::insert(bag, proposal_id, &proposal);
+
+        Self::deposit_event(Event::Closed {
+            bag: bag.clone(),
+            proposal_id,
+            ayes: proposal.ayes,
+            nays: proposal.nays,
+            approved,
+        });
+
+        if approved {
+            let call = <T as Config>::Proposal::decode(&mut &proposal.call[..])
+                .map_err(|_| Error::<T>::InvalidProposal)?;
+            let result = call
+                .dispatch(frame_system::RawOrigin::Signed(bag.clone()).into())
+                .map(|_| ())
+                .map_err(|e| e.error);
+
+            Self::deposit_event(Event::Executed {
+                bag: bag.clone(),
+                proposal_id,
+                result,
+            });
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,127 @@
+//! Storage migrations of `sugarfunge_bag`
+
+use super::*;
+use frame_support::{
+    traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+    weights::Weight,
+};
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+use sp_std::{collections::btree_map::BTreeMap, marker::PhantomData};
+
+/// Version 1 records the creator of each bag, counts the live bags of each class and
+/// records share checkpoints of bag classes registered before them
+pub mod v1 {
+    use super::*;
+
+    #[derive(Decode)]
+    struct OldBag<AccountId, ClassId, AssetId> {
+        operator: AccountId,
+        class_id: ClassId,
+        asset_id: AssetId,
+        total_shares: Balance,
+    }
+
+    /// Bags created before version 1 did not record who paid the creation deposit, so the
+    /// operator of their bag class becomes the creator refunded on dissolution
+    ///
+    /// Runs in a single block, so only while there are at most `Limit` bags and bag classes.
+    /// Above that the upgrade is skipped and the storage version stays at 0.
+    pub struct MigrateToV1<T, Limit>(PhantomData<(T, Limit)>);
+
+    /// Bags and bag classes to migrate, counting no further than past `limit`
+    fn count<T: Config>(limit: usize) -> usize {
+        let bags = Bags::<T>::iter_keys().take(limit + 1).count();
+        bags + BagClasses::<T>::iter_keys()
+            .take((limit + 1).saturating_sub(bags))
+            .count()
+    }
+
+    impl<T: Config, Limit: Get<u32>> OnRuntimeUpgrade for MigrateToV1<T, Limit> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 0 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let counted = count::<T>(Limit::get() as usize);
+            if counted > Limit::get() as usize {
+                return T::DbWeight::get().reads(counted as u64 + 1);
+            }
+
+            let mut translated = 0u64;
+            let mut live: BTreeMap<T::ClassId, u32> = BTreeMap::new();
+            Bags::<T>::translate_values::<OldBag<T::AccountId, T::ClassId, T::AssetId>, _>(|old| {
+                translated += 1;
+                *live.entry(old.class_id).or_default() += 1;
+                let creator = BagClasses::<T>::get(old.class_id)
+                    .map_or(old.operator.clone(), |bag_class| bag_class.operator);
+                Some(Bag {
+                    operator: old.operator,
+                    creator,
+                    class_id: old.class_id,
+                    asset_id: old.asset_id,
+                    total_shares: old.total_shares,
+                })
+            });
+
+            let classes = live.len() as u64;
+            for (class_id, count) in live {
+                LiveBags::<T>::insert(class_id, count);
+            }
+
+            // Proposals of these classes open once a snapshot follows this block
+            let owner: T::AccountId = <T as Config>::PalletId::get().into_account_truncating();
+            let (mut bag_classes, mut enabled) = (0u64, 0u64);
+            for class_id in BagClasses::<T>::iter_keys() {
+                bag_classes += 1;
+                if !sugarfunge_asset::Pallet::<T>::checkpoints_enabled(class_id)
+                    && sugarfunge_asset::Pallet::<T>::do_enable_checkpoints(&owner, class_id)
+                        .is_ok()
+                {
+                    enabled += 1;
+                }
+            }
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(
+                counted as u64 + 2 * translated + 3 * bag_classes + 1,
+                translated + classes + enabled + 1,
+            )
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+            if Pallet::<T>::on_chain_storage_version() != 0 {
+                return Ok(Vec::new());
+            }
+            ensure!(
+                count::<T>(Limit::get() as usize) <= Limit::get() as usize,
+                "too many bags to migrate in one block"
+            );
+            Ok((Bags::<T>::iter_keys().count() as u32).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            ensure!(
+                Pallet::<T>::on_chain_storage_version() == 1,
+                "bag migration did not run"
+            );
+            if state.is_empty() {
+                return Ok(());
+            }
+            let bags = u32::decode(&mut &state[..])
+                .map_err(|_| TryRuntimeError::Other("invalid bag count"))?;
+            ensure!(
+                Bags::<T>::iter_values().count() as u32 == bags,
+                "bags failed to decode after the migration"
+            );
+            ensure!(
+                LiveBags::<T>::iter_values().sum::<u32>() == bags,
+                "live bags do not add up to the migrated bags"
+            );
+            Ok(())
+        }
+    }
+}
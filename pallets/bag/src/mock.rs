@@ -7,8 +7,9 @@ use frame_support::{
 };
 use sp_core::H256;
 use sp_runtime::{
+    testing::{TestSignature, UintAuthorityId},
     traits::{BlakeTwo256, IdentityLookup},
-    BuildStorage,
+    BuildStorage, Perbill,
 };
 use sugarfunge_primitives::Balance;
 
@@ -18,9 +19,11 @@ pub const DOLLARS: Balance = 100 * CENTS;
 
 parameter_types! {
     pub const CreateAssetClassDeposit: Balance = 500 * MILLICENTS;
-    pub const CreateBagDeposit: Balance = 1;
+    pub const CreateBagDeposit: Balance = DOLLARS;
     pub const CurrencyModuleId: PalletId = PalletId(*b"sug/curr");
     pub const BagModuleId: PalletId = PalletId(*b"sug/crow");
+    pub const DefaultQuorum: Perbill = Perbill::from_percent(50);
+    pub const DefaultThreshold: Perbill = Perbill::from_percent(50);
 }
 
 impl frame_system::Config for Test {
@@ -75,6 +78,10 @@ impl sugarfunge_asset::Config for Test {
     type ClassId = u64;
     type MaxClassMetadata = ConstU32<1>;
     type MaxAssetMetadata = ConstU32<1>;
+    type MaxVestingSchedules = ConstU32<2>;
+    type MaxMultiItems = ConstU32<4>;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
 }
 
 impl sugarfunge_bag::Config for Test {
@@ -83,6 +90,12 @@ impl sugarfunge_bag::Config for Test {
     type CreateBagDeposit = CreateBagDeposit;
     type Currency = Balances;
     type MaxOwners = ConstU32<20>;
+    type Proposal = RuntimeCall;
+    type ProposalFilter = Everything;
+    type MaxProposalLength = ConstU32<1024>;
+    type DefaultQuorum = DefaultQuorum;
+    type DefaultThreshold = DefaultThreshold;
+    type DefaultVotingPeriod = ConstU64<10>;
 }
 
 frame_support::construct_runtime!(
@@ -1,6 +1,16 @@
-use crate::mock::*;
-use frame_support::assert_ok;
+use crate::{
+    migrations, mock::*, Bags, Error, LiveBags, ProposalStatus, Proposals, ReleaseConditions,
+};
+use codec::Encode;
+use frame_support::{
+    assert_noop, assert_ok,
+    storage::{storage_prefix, unhashed},
+    traits::{ConstU32, GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+    Blake2_128Concat, StorageHasher,
+};
 use sp_core::bounded_vec;
+use sp_runtime::Perbill;
+use sugarfunge_asset::{GiftPolicy, Royalty};
 
 fn last_event() -> RuntimeEvent {
     frame_system::Pallet::<Test>::events()
@@ -115,6 +125,7 @@ fn deposit_assets() {
                 vec![2, 3, 4],
                 vec![asset_ids.clone(), asset_ids.clone(), asset_ids.clone()],
                 vec![amounts.clone(), amounts.clone(), amounts.clone()],
+                0,
             ));
 
             let mut balances = Asset::balances_of_owner(&bag).unwrap();
@@ -249,6 +260,7 @@ fn sweep_assets() {
                 vec![2, 3, 4],
                 vec![asset_ids.clone(), asset_ids.clone(), asset_ids.clone()],
                 vec![amounts.clone(), amounts.clone(), amounts.clone()],
+                0,
             ));
 
             let mut balances = Asset::balances_of_owner(&bag).unwrap();
@@ -322,9 +334,730 @@ fn sweep_assets() {
     })
 }
 
+#[test]
+fn withdraw_pro_rata() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        assert_ok!(Asset::create_class(
+            RuntimeOrigin::signed(1),
+            1,
+            2,
+            bounded_vec![]
+        ));
+        assert_ok!(Asset::do_batch_mint(&1, &2, 2, vec![0, 1], vec![10, 11]));
+
+        let bag = Bag::do_create(&1, 1000, &vec![2, 3], &vec![3, 7]).unwrap();
+
+        assert_ok!(Bag::deposit(
+            RuntimeOrigin::signed(2),
+            bag,
+            vec![2],
+            vec![vec![0, 1]],
+            vec![vec![10, 11]],
+            0,
+        ));
+
+        assert_noop!(
+            Bag::withdraw(RuntimeOrigin::signed(2), bag, 0),
+            Error::<Test>::InvalidShares
+        );
+        assert_noop!(
+            Bag::withdraw(RuntimeOrigin::signed(2), bag, 4),
+            Error::<Test>::InsufficientShares
+        );
+
+        // 30% of shares, rounded down
+        assert_ok!(Bag::withdraw(RuntimeOrigin::signed(2), bag, 3));
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Bag(crate::Event::Withdraw {
+                bag,
+                who: 2,
+                shares: 3,
+                class_ids: vec![2],
+                asset_ids: vec![vec![0, 1]],
+                amounts: vec![vec![3, 3]],
+                native: 0,
+            })
+        );
+        assert_eq!(Asset::balance_of(&2, 1000, 0), 0);
+        assert_eq!(Asset::balance_of(&2, 2, 0), 3);
+        assert_eq!(Asset::balance_of(&2, 2, 1), 3);
+        assert_eq!(Bags::<Test>::get(bag).unwrap().total_shares, 7);
+
+        // Remaining holder gets the rest, dust included
+        assert_ok!(Bag::withdraw(RuntimeOrigin::signed(3), bag, 7));
+        assert_eq!(Asset::balance_of(&3, 2, 0), 7);
+        assert_eq!(Asset::balance_of(&3, 2, 1), 8);
+        assert_eq!(Asset::balance_of(&bag, 2, 0), 0);
+        assert_eq!(Asset::balance_of(&bag, 2, 1), 0);
+        assert_eq!(Bags::<Test>::get(bag).unwrap().total_shares, 0);
+    })
+}
+
+#[test]
+fn share_holders_govern_bag() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        assert_ok!(Asset::create_class(
+            RuntimeOrigin::signed(1),
+            1,
+            2,
+            bounded_vec![]
+        ));
+        assert_ok!(Asset::do_mint(&1, &2, 2, 0, 10));
+
+        let bag = Bag::do_create(&1, 1000, &vec![2, 3], &vec![4, 6]).unwrap();
+        assert_ok!(Bag::deposit(
+            RuntimeOrigin::signed(2),
+            bag,
+            vec![2],
+            vec![vec![0]],
+            vec![vec![10]],
+            0,
+        ));
+        // Shares are counted as held at the end of the block before a proposal
+        run_to_block(11);
+
+        // Only the bag account itself can move bag assets
+        assert_noop!(
+            Bag::transfer(RuntimeOrigin::signed(2), 4, 2, vec![0], vec![10]),
+            Error::<Test>::InvalidBag
+        );
+
+        let call = RuntimeCall::Bag(crate::Call::transfer {
+            to: 4,
+            class_id: 2,
+            asset_ids: vec![0],
+            amounts: vec![10],
+        });
+        assert_noop!(
+            Bag::propose(RuntimeOrigin::signed(4), bag, Box::new(call.clone())),
+            Error::<Test>::InsufficientShares
+        );
+        assert_ok!(Bag::propose(RuntimeOrigin::signed(2), bag, Box::new(call)));
+
+        assert_ok!(Bag::vote(RuntimeOrigin::signed(2), bag, 0, true));
+        assert_noop!(
+            Bag::vote(RuntimeOrigin::signed(2), bag, 0, true),
+            Error::<Test>::AlreadyVoted
+        );
+        assert_noop!(
+            Bag::close(RuntimeOrigin::signed(4), bag, 0),
+            Error::<Test>::VotingNotEnded
+        );
+
+        // Ayes reach quorum and threshold over all shares, closable early
+        assert_ok!(Bag::vote(RuntimeOrigin::signed(3), bag, 0, true));
+        assert_ok!(Bag::close(RuntimeOrigin::signed(4), bag, 0));
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Bag(crate::Event::Executed {
+                bag,
+                proposal_id: 0,
+                result: Ok(()),
+            })
+        );
+        assert_eq!(
+            Proposals::<Test>::get(bag, 0).unwrap().status,
+            ProposalStatus::Approved
+        );
+        assert_eq!(Asset::balance_of(&bag, 2, 0), 0);
+        assert_eq!(Asset::balance_of(&4, 2, 0), 10);
+
+        assert_noop!(
+            Bag::close(RuntimeOrigin::signed(4), bag, 0),
+            Error::<Test>::ProposalClosed
+        );
+    })
+}
+
+#[test]
+fn rejected_proposal_after_voting_period() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        let bag = Bag::do_create(&1, 1000, &vec![2, 3], &vec![4, 6]).unwrap();
+        run_to_block(11);
+
+        let call = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+        assert_ok!(Bag::propose(RuntimeOrigin::signed(2), bag, Box::new(call)));
+        assert_ok!(Bag::vote(RuntimeOrigin::signed(2), bag, 0, true));
+        assert_ok!(Bag::vote(RuntimeOrigin::signed(3), bag, 0, false));
+
+        assert_noop!(
+            Bag::close(RuntimeOrigin::signed(2), bag, 0),
+            Error::<Test>::VotingNotEnded
+        );
+
+        run_to_block(22);
+        assert_ok!(Bag::close(RuntimeOrigin::signed(2), bag, 0));
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Bag(crate::Event::Closed {
+                bag,
+                proposal_id: 0,
+                ayes: 4,
+                nays: 6,
+                approved: false,
+            })
+        );
+    })
+}
+
+#[test]
+fn shares_counted_at_proposal_snapshot() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        let bag = Bag::do_create(&1, 1000, &vec![2, 3], &vec![4, 6]).unwrap();
+        let bag_info = Bags::<Test>::get(&bag).unwrap();
+
+        // Snapshots taken before the bag class recorded checkpoints are refused
+        let call = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+        assert_noop!(
+            Bag::propose(RuntimeOrigin::signed(2), bag, Box::new(call.clone())),
+            Error::<Test>::SharesNotRecorded
+        );
+
+        // Shares created in the proposal block do not count
+        run_to_block(11);
+        let later = Bag::do_create(&1, 1000, &vec![2], &vec![10]).unwrap();
+        assert_ok!(Bag::propose(
+            RuntimeOrigin::signed(2),
+            later,
+            Box::new(call.clone())
+        ));
+        assert_noop!(
+            Bag::vote(RuntimeOrigin::signed(2), later, 0, true),
+            Error::<Test>::InsufficientShares
+        );
+
+        assert_ok!(Bag::propose(RuntimeOrigin::signed(2), bag, Box::new(call)));
+        assert_ok!(Bag::vote(RuntimeOrigin::signed(2), bag, 0, true));
+
+        // Moving shares after the snapshot does not grant the receiver a second vote
+        assert_ok!(Asset::do_transfer_from(
+            &2,
+            &2,
+            &4,
+            bag_info.class_id,
+            bag_info.asset_id,
+            4
+        ));
+        assert_noop!(
+            Bag::vote(RuntimeOrigin::signed(4), bag, 0, true),
+            Error::<Test>::InsufficientShares
+        );
+        assert_eq!(Proposals::<Test>::get(bag, 0).unwrap().ayes, 4);
+    })
+}
+
+#[test]
+fn dissolve_refunds_deposit() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        assert_ok!(Asset::create_class(
+            RuntimeOrigin::signed(1),
+            1,
+            2,
+            bounded_vec![]
+        ));
+        assert_ok!(Asset::do_mint(&1, &2, 2, 0, 10));
+
+        let bag = Bag::do_create(&1, 1000, &vec![2], &vec![10]).unwrap();
+        assert_ok!(Bag::deposit(
+            RuntimeOrigin::signed(2),
+            bag,
+            vec![2],
+            vec![vec![0]],
+            vec![vec![10]],
+            0,
+        ));
+
+        assert_noop!(
+            Bag::dissolve(RuntimeOrigin::signed(3), bag),
+            Error::<Test>::InvalidShares
+        );
+        assert_noop!(
+            Bag::unregister(RuntimeOrigin::signed(1), 1000),
+            Error::<Test>::BagClassInUse
+        );
+
+        assert_ok!(Bag::sweep(RuntimeOrigin::signed(2), 2, bag));
+
+        let creator_balance = Balances::free_balance(&1);
+        assert_ok!(Bag::dissolve(RuntimeOrigin::signed(3), bag));
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Bag(crate::Event::Dissolved {
+                bag,
+                creator: 1,
+                refund: CreateBagDeposit::get(),
+            })
+        );
+        assert_eq!(
+            Balances::free_balance(&1),
+            creator_balance + CreateBagDeposit::get()
+        );
+        assert!(Bags::<Test>::get(bag).is_none());
+
+        assert_noop!(
+            Bag::unregister(RuntimeOrigin::signed(2), 1000),
+            Error::<Test>::InvalidBagOperator
+        );
+        assert_ok!(Bag::unregister(RuntimeOrigin::signed(1), 1000));
+        assert_noop!(
+            Bag::create(RuntimeOrigin::signed(1), 1000, vec![2], vec![10]),
+            Error::<Test>::InvalidBagClass
+        );
+    })
+}
+
+#[test]
+fn dissolve_requires_empty_bag() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        assert_ok!(Asset::create_class(
+            RuntimeOrigin::signed(1),
+            1,
+            2,
+            bounded_vec![]
+        ));
+        assert_ok!(Asset::do_mint(&1, &2, 2, 0, 10));
+
+        let bag = Bag::do_create(&1, 1000, &vec![2], &vec![10]).unwrap();
+        assert_ok!(Bag::withdraw(RuntimeOrigin::signed(2), bag, 10));
+
+        // Assets sent after all shares were burned keep the bag alive
+        assert_ok!(Asset::do_transfer_from(&2, &2, &bag, 2, 0, 1));
+        assert_noop!(
+            Bag::dissolve(RuntimeOrigin::signed(2), bag),
+            Error::<Test>::BagNotEmpty
+        );
+    })
+}
+
+#[test]
+fn native_balance_pro_rata() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        let bag = Bag::do_create(&1, 1000, &vec![2, 3], &vec![3, 7]).unwrap();
+        assert_eq!(Bag::native_balance(&bag), 0);
+
+        assert_ok!(Bag::deposit(
+            RuntimeOrigin::signed(1),
+            bag,
+            vec![],
+            vec![],
+            vec![],
+            1001 * DOLLARS,
+        ));
+        assert_eq!(Bag::native_balance(&bag), 1001 * DOLLARS);
+
+        let balance = Balances::free_balance(&2);
+        assert_ok!(Bag::withdraw(RuntimeOrigin::signed(2), bag, 3));
+        assert_eq!(Balances::free_balance(&2), balance + 3003 * DOLLARS / 10);
+
+        // The last holder takes the remaining native balance, the creation deposit stays
+        assert_ok!(Bag::sweep(RuntimeOrigin::signed(3), 3, bag));
+        assert_eq!(Balances::free_balance(&3), 7007 * DOLLARS / 10);
+        assert_eq!(Bag::native_balance(&bag), 0);
+        assert_eq!(Balances::free_balance(&bag), CreateBagDeposit::get());
+    })
+}
+
+#[test]
+fn escrow_timelock() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        assert_ok!(Bag::create_escrow(
+            RuntimeOrigin::signed(1),
+            1000,
+            vec![2],
+            vec![10],
+            ReleaseConditions {
+                release_after: Some(20),
+                arbiter: None,
+                dispute_window: 0,
+            },
+        ));
+        let bag = match last_event() {
+            RuntimeEvent::Bag(crate::Event::EscrowCreated { bag, .. }) => bag,
+            _ => panic!("Event expected"),
+        };
+
+        assert_noop!(
+            Bag::sweep(RuntimeOrigin::signed(2), 2, bag),
+            Error::<Test>::EscrowLocked
+        );
+        assert_noop!(
+            Bag::withdraw(RuntimeOrigin::signed(2), bag, 5),
+            Error::<Test>::EscrowLocked
+        );
+        assert_noop!(
+            Bag::dispute(RuntimeOrigin::signed(2), bag),
+            Error::<Test>::DisputeWindowClosed
+        );
+
+        run_to_block(20);
+        assert_ok!(Bag::sweep(RuntimeOrigin::signed(2), 2, bag));
+    })
+}
+
+#[test]
+fn escrow_arbiter_split() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        assert_ok!(Asset::create_class(
+            RuntimeOrigin::signed(1),
+            1,
+            2,
+            bounded_vec![]
+        ));
+        assert_ok!(Asset::do_mint(&1, &1, 2, 0, 101));
+
+        let bag = Bag::do_create_escrow(
+            &1,
+            1000,
+            &vec![2],
+            &vec![10],
+            ReleaseConditions {
+                release_after: Some(20),
+                arbiter: Some(5),
+                dispute_window: 5,
+            },
+        )
+        .unwrap();
+        assert_ok!(Bag::deposit(
+            RuntimeOrigin::signed(1),
+            bag,
+            vec![2],
+            vec![vec![0]],
+            vec![vec![101]],
+            0,
+        ));
+
+        assert_noop!(
+            Bag::release(RuntimeOrigin::signed(2), bag),
+            Error::<Test>::InvalidArbiter
+        );
+        assert_ok!(Bag::release(RuntimeOrigin::signed(5), bag));
+
+        // Approved but still within the dispute window
+        run_to_block(21);
+        assert_noop!(
+            Bag::sweep(RuntimeOrigin::signed(2), 2, bag),
+            Error::<Test>::EscrowLocked
+        );
+        assert_noop!(
+            Bag::dispute(RuntimeOrigin::signed(3), bag),
+            Error::<Test>::InsufficientShares
+        );
+        assert_ok!(Bag::dispute(RuntimeOrigin::signed(1), bag));
+
+        run_to_block(30);
+        assert_noop!(
+            Bag::sweep(RuntimeOrigin::signed(2), 2, bag),
+            Error::<Test>::EscrowLocked
+        );
+
+        assert_noop!(
+            Bag::split(
+                RuntimeOrigin::signed(5),
+                bag,
+                vec![2, 1],
+                vec![Perbill::from_percent(70), Perbill::from_percent(20)],
+            ),
+            Error::<Test>::InvalidPercents
+        );
+        assert_ok!(Bag::split(
+            RuntimeOrigin::signed(5),
+            bag,
+            vec![2, 1],
+            vec![Perbill::from_percent(70), Perbill::from_percent(30)],
+        ));
+        assert_eq!(Asset::balance_of(&2, 2, 0), 70);
+        assert_eq!(Asset::balance_of(&1, 2, 0), 31);
+        assert_eq!(Asset::balance_of(&bag, 2, 0), 0);
+
+        assert_noop!(
+            Bag::refund(RuntimeOrigin::signed(5), bag),
+            Error::<Test>::EscrowSettled
+        );
+    })
+}
+
+#[test]
+fn escrow_blocks_share_holder_proposals() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        assert_ok!(Asset::create_class(
+            RuntimeOrigin::signed(1),
+            1,
+            2,
+            bounded_vec![]
+        ));
+        assert_ok!(Asset::do_mint(&1, &1, 2, 0, 101));
+
+        let bag = Bag::do_create_escrow(
+            &1,
+            1000,
+            &vec![2],
+            &vec![10],
+            ReleaseConditions {
+                release_after: None,
+                arbiter: Some(5),
+                dispute_window: 0,
+            },
+        )
+        .unwrap();
+        assert_ok!(Bag::deposit(
+            RuntimeOrigin::signed(1),
+            bag,
+            vec![2],
+            vec![vec![0]],
+            vec![vec![101]],
+            0,
+        ));
+        let other = Bag::do_create(&1, 1000, &vec![2], &vec![10]).unwrap();
+        run_to_block(11);
+
+        // Moving escrowed assets into a bag the share holders control is not allowed
+        let call = RuntimeCall::Bag(crate::Call::deposit {
+            bag: other,
+            class_ids: vec![2],
+            asset_ids: vec![vec![0]],
+            amounts: vec![vec![101]],
+            native: 0,
+        });
+        assert_noop!(
+            Bag::propose(RuntimeOrigin::signed(2), bag, Box::new(call.clone())),
+            Error::<Test>::EscrowLocked
+        );
+
+        assert_ok!(Bag::release(RuntimeOrigin::signed(5), bag));
+        assert_ok!(Bag::propose(RuntimeOrigin::signed(2), bag, Box::new(call)));
+        assert_ok!(Bag::vote(RuntimeOrigin::signed(2), bag, 0, true));
+        assert_ok!(Bag::close(RuntimeOrigin::signed(2), bag, 0));
+        assert_eq!(Asset::balance_of(&other, 2, 0), 101);
+    })
+}
+
+#[test]
+fn escrow_arbiter_refund() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+        assert_ok!(Asset::do_mint(&1, &3, 1, 1, 100));
+        assert_ok!(Asset::do_mint(&1, &4, 1, 1, 100));
+        assert_ok!(Balances::transfer_keep_alive(
+            RuntimeOrigin::signed(1),
+            3,
+            100 * DOLLARS
+        ));
+
+        let bag = Bag::do_create_escrow(
+            &1,
+            1000,
+            &vec![2],
+            &vec![10],
+            ReleaseConditions {
+                release_after: None,
+                arbiter: Some(5),
+                dispute_window: 0,
+            },
+        )
+        .unwrap();
+        // The first depositor pays for the escrow, and is the only one who can fund it
+        assert_ok!(Bag::deposit(
+            RuntimeOrigin::signed(3),
+            bag,
+            vec![1],
+            vec![vec![1]],
+            vec![vec![60]],
+            10 * DOLLARS,
+        ));
+        assert_noop!(
+            Bag::deposit(
+                RuntimeOrigin::signed(4),
+                bag,
+                vec![1],
+                vec![vec![1]],
+                vec![vec![100]],
+                0,
+            ),
+            Error::<Test>::NotEscrowPayer
+        );
+        assert_ok!(Bag::deposit(
+            RuntimeOrigin::signed(3),
+            bag,
+            vec![1],
+            vec![vec![1]],
+            vec![vec![40]],
+            0,
+        ));
+
+        assert_noop!(
+            Bag::sweep(RuntimeOrigin::signed(2), 2, bag),
+            Error::<Test>::EscrowLocked
+        );
+
+        let balance = Balances::free_balance(&3);
+        assert_ok!(Bag::refund(RuntimeOrigin::signed(5), bag));
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Bag(crate::Event::Refunded {
+                bag,
+                arbiter: 5,
+                to: 3,
+            })
+        );
+        assert_eq!(Asset::balance_of(&bag, 1, 1), 0);
+        assert_eq!(Asset::balance_of(&3, 1, 1), 100);
+        assert_eq!(Balances::free_balance(&3), balance + 10 * DOLLARS);
+    })
+}
+
+#[test]
+fn gift_forbidden_assets_leave_bags() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        assert_ok!(Asset::create_class(
+            RuntimeOrigin::signed(1),
+            1,
+            2,
+            bounded_vec![]
+        ));
+        assert_ok!(Asset::do_mint(&1, &1, 2, 0, 202));
+        assert_ok!(Asset::do_mint(&1, &3, 2, 0, 10));
+        assert_ok!(Asset::set_royalty(
+            RuntimeOrigin::signed(1),
+            2,
+            None,
+            Some(Royalty {
+                beneficiary: 1,
+                bps: 250,
+                gifts: GiftPolicy::Forbidden,
+            })
+        ));
+
+        let bag = Bag::do_create(&1, 1000, &vec![2, 3], &vec![3, 7]).unwrap();
+        // Holders cannot gift the asset to the share holders of a bag
+        assert_noop!(
+            Bag::deposit(
+                RuntimeOrigin::signed(3),
+                bag,
+                vec![2],
+                vec![vec![0]],
+                vec![vec![10]],
+                0,
+            ),
+            sugarfunge_asset::Error::<Test>::GiftsForbidden
+        );
+        assert_ok!(Bag::deposit(
+            RuntimeOrigin::signed(1),
+            bag,
+            vec![2],
+            vec![vec![0]],
+            vec![vec![101]],
+            0,
+        ));
+
+        // But the bag pays it out to its share holders
+        assert_ok!(Bag::withdraw(RuntimeOrigin::signed(2), bag, 3));
+        assert_eq!(Asset::balance_of(&2, 2, 0), 30);
+        assert_ok!(Bag::sweep(RuntimeOrigin::signed(3), 3, bag));
+        assert_eq!(Asset::balance_of(&3, 2, 0), 81);
+        assert_eq!(Asset::balance_of(&bag, 2, 0), 0);
+
+        let escrow = Bag::do_create_escrow(
+            &1,
+            1000,
+            &vec![2],
+            &vec![10],
+            ReleaseConditions {
+                release_after: None,
+                arbiter: Some(5),
+                dispute_window: 0,
+            },
+        )
+        .unwrap();
+        assert_ok!(Bag::deposit(
+            RuntimeOrigin::signed(1),
+            escrow,
+            vec![2],
+            vec![vec![0]],
+            vec![vec![101]],
+            0,
+        ));
+        assert_ok!(Bag::split(
+            RuntimeOrigin::signed(5),
+            escrow,
+            vec![2, 1],
+            vec![Perbill::from_percent(70), Perbill::from_percent(30)],
+        ));
+        assert_eq!(Asset::balance_of(&2, 2, 0), 100);
+        assert_eq!(Asset::balance_of(&escrow, 2, 0), 0);
+    })
+}
+
 #[test]
 fn before_bag_works() {
     new_test_ext().execute_with(|| {
         before_bag();
     })
 }
+
+#[test]
+fn migration_backfills_creator_and_live_bags() {
+    new_test_ext().execute_with(|| {
+        before_bag();
+
+        let bag = Bag::do_create(&2, 1000, &vec![2], &vec![10]).unwrap();
+        let info = Bags::<Test>::get(bag).unwrap();
+
+        // Store the bag in its version 0 layout
+        StorageVersion::new(0).put::<Bag>();
+        unhashed::put_raw(
+            &Bags::<Test>::hashed_key_for(bag),
+            &(
+                info.operator,
+                info.class_id,
+                info.asset_id,
+                info.total_shares,
+            )
+                .encode(),
+        );
+        LiveBags::<Test>::remove(1000);
+        // Bag classes registered before version 1 did not record checkpoints
+        let mut checkpointed = storage_prefix(b"Asset", b"CheckpointedClasses").to_vec();
+        checkpointed.extend(1000u64.using_encoded(Blake2_128Concat::hash));
+        unhashed::kill(&checkpointed);
+
+        run_to_block(12);
+        // One bag and its class are too many for a limit of one
+        migrations::v1::MigrateToV1::<Test, ConstU32<1>>::on_runtime_upgrade();
+        assert_eq!(Bag::on_chain_storage_version(), 0);
+
+        migrations::v1::MigrateToV1::<Test, ConstU32<2>>::on_runtime_upgrade();
+
+        assert_eq!(Bag::on_chain_storage_version(), 1);
+        // The bag class operator stands in for the unknown depositor
+        assert_eq!(Bags::<Test>::get(bag).unwrap().creator, 1);
+        assert_eq!(LiveBags::<Test>::get(1000), 1);
+        assert_eq!(Asset::checkpointed_classes(1000), Some(12));
+        assert_noop!(
+            Bag::unregister(RuntimeOrigin::signed(1), 1000),
+            Error::<Test>::BagClassInUse
+        );
+    })
+}
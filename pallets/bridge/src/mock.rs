@@ -7,6 +7,7 @@ use frame_support::{
 };
 use sp_core::H256;
 use sp_runtime::{
+    testing::{TestSignature, UintAuthorityId},
     traits::{AccountIdConversion, BlakeTwo256, IdentityLookup},
     BuildStorage,
 };
@@ -74,6 +75,10 @@ impl sugarfunge_asset::Config for Test {
     type ClassId = u64;
     type MaxClassMetadata = ConstU32<1>;
     type MaxAssetMetadata = ConstU32<1>;
+    type MaxVestingSchedules = ConstU32<2>;
+    type MaxMultiItems = ConstU32<4>;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
 }
 
 impl sugarfunge_bridge::Config for Test {
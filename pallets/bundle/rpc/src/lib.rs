@@ -0,0 +1,120 @@
+//! RPC interface for the bundle runtime API.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpsee::{
+    core::{Error as JsonRpseeError, RpcResult},
+    proc_macros::rpc,
+    types::error::{CallError, ErrorObject},
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Block as BlockT;
+use sugarfunge_primitives::Balance;
+
+pub use sugarfunge_bundle_runtime_api::{BundleApi as BundleRuntimeApi, BundleId, BundleInfo};
+
+#[rpc(client, server)]
+pub trait BundleApi<BlockHash, AccountId, ClassId, AssetId, NativeBalance> {
+    #[method(name = "bundle_bundleInfo")]
+    fn bundle_info(
+        &self,
+        bundle_id: BundleId,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<BundleInfo<AccountId, ClassId, AssetId, NativeBalance>>>;
+
+    #[method(name = "bundle_bundlesContaining")]
+    fn bundles_containing(
+        &self,
+        class_id: ClassId,
+        asset_id: AssetId,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<BundleId>>;
+
+    #[method(name = "bundle_maxMintable")]
+    fn max_mintable(
+        &self,
+        account: AccountId,
+        bundle_id: BundleId,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Balance>;
+}
+
+/// Provides RPC methods to query bundles.
+pub struct Bundle<C, P> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<P>,
+}
+
+impl<C, P> Bundle<C, P> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+/// Error code for runtime API call failures.
+const RUNTIME_ERROR: i32 = 1;
+
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> JsonRpseeError {
+    CallError::Custom(ErrorObject::owned(
+        RUNTIME_ERROR,
+        "Runtime error",
+        Some(format!("{:?}", err)),
+    ))
+    .into()
+}
+
+impl<C, Block, AccountId, ClassId, AssetId, NativeBalance>
+    BundleApiServer<<Block as BlockT>::Hash, AccountId, ClassId, AssetId, NativeBalance>
+    for Bundle<C, Block>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: BundleRuntimeApi<Block, AccountId, ClassId, AssetId, NativeBalance>,
+    AccountId: Codec + Send + Sync + 'static,
+    ClassId: Codec + Send + Sync + 'static,
+    AssetId: Codec + Send + Sync + 'static,
+    NativeBalance: Codec + Send + Sync + 'static,
+{
+    fn bundle_info(
+        &self,
+        bundle_id: BundleId,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Option<BundleInfo<AccountId, ClassId, AssetId, NativeBalance>>> {
+        let api = self.client.runtime_api();
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+        api.bundle_info(at, bundle_id)
+            .map_err(runtime_error_into_rpc_err)
+    }
+
+    fn bundles_containing(
+        &self,
+        class_id: ClassId,
+        asset_id: AssetId,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Vec<BundleId>> {
+        let api = self.client.runtime_api();
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+        api.bundles_containing(at, class_id, asset_id)
+            .map_err(runtime_error_into_rpc_err)
+    }
+
+    fn max_mintable(
+        &self,
+        account: AccountId,
+        bundle_id: BundleId,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Balance> {
+        let api = self.client.runtime_api();
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+        api.max_mintable(at, account, bundle_id)
+            .map_err(runtime_error_into_rpc_err)
+    }
+}
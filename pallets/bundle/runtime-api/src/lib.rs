@@ -0,0 +1,61 @@
+//! Runtime API for querying registered bundles.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+use sugarfunge_primitives::Balance;
+
+pub type BundleId = sugarfunge_primitives::Hash;
+
+/// A registered bundle with its IOU asset and schema
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct BundleInfo<AccountId, ClassId, AssetId, NativeBalance> {
+    /// Creator
+    pub creator: AccountId,
+    /// IOU asset class
+    pub class_id: ClassId,
+    /// IOU asset id
+    pub asset_id: AssetId,
+    /// Bundle metadata
+    pub metadata: Vec<u8>,
+    /// Vault holding the components of minted bundles
+    pub vault: AccountId,
+    /// Component asset classes
+    pub class_ids: Vec<ClassId>,
+    /// Component asset ids per class
+    pub asset_ids: Vec<Vec<AssetId>>,
+    /// Component amounts per bundle per class
+    pub amounts: Vec<Vec<Balance>>,
+    /// Components that can be left in the vault on partial redemption
+    pub optional: Vec<(ClassId, AssetId)>,
+    /// Native currency amount per bundle
+    pub native: NativeBalance,
+    /// Number of nested bundle levels
+    pub depth: u32,
+}
+
+sp_api::decl_runtime_apis! {
+    pub trait BundleApi<AccountId, ClassId, AssetId, NativeBalance>
+    where
+        AccountId: Codec,
+        ClassId: Codec,
+        AssetId: Codec,
+        NativeBalance: Codec,
+    {
+        /// Bundle registered under `bundle_id`
+        fn bundle_info(bundle_id: BundleId) -> Option<BundleInfo<AccountId, ClassId, AssetId, NativeBalance>>;
+
+        /// Bundles containing the asset as a component
+        fn bundles_containing(class_id: ClassId, asset_id: AssetId) -> Vec<BundleId>;
+
+        /// Number of bundles `account` can mint with its current balances
+        fn max_mintable(account: AccountId, bundle_id: BundleId) -> Balance;
+    }
+}
@@ -4,19 +4,26 @@ use codec::{Decode, Encode, MaxEncodedLen};
 use frame_support::{
     dispatch::DispatchResult,
     ensure,
-    traits::{Currency, Get, ReservableCurrency},
-    BoundedVec, PalletId,
+    traits::{
+        Currency,
+        ExistenceRequirement::{AllowDeath, KeepAlive},
+        Get, ReservableCurrency,
+    },
+    BoundedVec, CloneNoBound, EqNoBound, PalletId, PartialEqNoBound, RuntimeDebugNoBound,
 };
 use scale_info::TypeInfo;
 use sp_runtime::{
-    traits::{AccountIdConversion, BlakeTwo256, Hash},
-    RuntimeDebug,
+    traits::{AccountIdConversion, BlakeTwo256, Hash, Saturating, UniqueSaturatedInto, Zero},
+    DispatchError, RuntimeDebug,
 };
 use sp_std::prelude::*;
 use sugarfunge_primitives::Balance;
 
 pub use pallet::*;
 
+type BalanceOf<T> =
+    <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
 #[cfg(test)]
 mod mock;
 
@@ -26,14 +33,42 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
-pub type BundleSchema<T> = (
-    BoundedVec<<T as sugarfunge_asset::Config>::ClassId, <T as Config>::MaxAssets>,
-    BoundedVec<
+pub mod migrations;
+
+#[derive(
+    Encode,
+    Decode,
+    CloneNoBound,
+    PartialEqNoBound,
+    EqNoBound,
+    RuntimeDebugNoBound,
+    TypeInfo,
+    MaxEncodedLen,
+)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct BundleSchema<T: Config> {
+    /// Component asset classes
+    pub class_ids: BoundedVec<<T as sugarfunge_asset::Config>::ClassId, <T as Config>::MaxAssets>,
+    /// Component asset ids per class
+    pub asset_ids: BoundedVec<
         BoundedVec<<T as sugarfunge_asset::Config>::AssetId, <T as Config>::MaxAssets>,
         <T as Config>::MaxAssets,
     >,
-    BoundedVec<BoundedVec<Balance, <T as Config>::MaxAssets>, <T as Config>::MaxAssets>,
-);
+    /// Component amounts per bundle per class
+    pub amounts:
+        BoundedVec<BoundedVec<Balance, <T as Config>::MaxAssets>, <T as Config>::MaxAssets>,
+    /// Components a holder may leave in the vault with `redeem_partial`
+    pub optional: BoundedVec<
+        (
+            <T as sugarfunge_asset::Config>::ClassId,
+            <T as sugarfunge_asset::Config>::AssetId,
+        ),
+        <T as Config>::MaxAssets,
+    >,
+    /// Native currency amount per bundle
+    pub native: BalanceOf<T>,
+}
 
 pub type BundleId = sugarfunge_primitives::Hash;
 
@@ -63,6 +98,10 @@ pub mod pallet {
         type MaxAssets: Get<u32>;
 
         type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+
+        /// Max nesting of bundles containing other bundle IOUs
+        #[pallet::constant]
+        type MaxBundleDepth: Get<u32>;
     }
 
     pub type BundleMetadataOf<T> =
@@ -75,7 +114,10 @@ pub mod pallet {
         BundleMetadataOf<T>,
     >;
 
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     #[pallet::storage]
@@ -93,6 +135,25 @@ pub mod pallet {
         BundleId,
     >;
 
+    /// Bundles registered before schemas were normalized, by their canonical id
+    #[pallet::storage]
+    #[pallet::getter(fn legacy_bundle_ids)]
+    pub(super) type LegacyBundleIds<T: Config> =
+        StorageMap<_, Blake2_128Concat, BundleId, BundleId>;
+
+    /// Reverse index from component asset to the bundles containing it
+    #[pallet::storage]
+    pub(super) type ComponentBundles<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+            NMapKey<Blake2_128Concat, BundleId>,
+        ),
+        (),
+        OptionQuery,
+    >;
+
     // Pallets use events to inform users when important changes are made.
     // https://docs.substrate.io/v3/runtime/events-and-errors
     #[pallet::event]
@@ -118,6 +179,14 @@ pub mod pallet {
             to: T::AccountId,
             amount: Balance,
         },
+        PartialBurn {
+            bundle_id: BundleId,
+            who: T::AccountId,
+            from: T::AccountId,
+            to: T::AccountId,
+            amount: Balance,
+            components: Vec<(T::ClassId, T::AssetId)>,
+        },
     }
 
     // Errors inform users that something went wrong.
@@ -135,6 +204,16 @@ pub mod pallet {
         InvalidArrayLength,
         /// Insufficient asset balance
         InsufficientBalance,
+        /// Schema amounts must be greater than zero
+        InvalidAmount,
+        /// Component is not part of the bundle schema
+        InvalidComponent,
+        /// Component is not marked optional in the bundle schema
+        ComponentNotOptional,
+        /// Bundle nests other bundles deeper than allowed
+        BundleTooDeep,
+        /// No permission to act for the account
+        NoPermission,
     }
 
     // Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -190,26 +269,211 @@ pub mod pallet {
 
             Ok(().into())
         }
+
+        /// Burns bundle IOUs releasing the required components and only the chosen
+        /// optional `components`. Optional components not chosen stay in the vault.
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn redeem_partial(
+            origin: OriginFor<T>,
+            from: T::AccountId,
+            to: T::AccountId,
+            bundle_id: BundleId,
+            amount: Balance,
+            components: Vec<(T::ClassId, T::AssetId)>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_redeem_partial(&who, &from, &to, bundle_id, amount, components)?;
+
+            Ok(().into())
+        }
     }
 }
 
 #[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
 pub struct Bundle<ClassId, AssetId, BundleSchema, AccountId, BundleMetadataOf> {
     /// Creator
-    creator: AccountId,
+    pub creator: AccountId,
     /// IOU asset class
-    class_id: ClassId,
+    pub class_id: ClassId,
     /// IOU asset id
-    asset_id: AssetId,
+    pub asset_id: AssetId,
     /// Bundle metadata
-    metadata: BundleMetadataOf,
+    pub metadata: BundleMetadataOf,
     /// Schema
-    schema: BundleSchema,
+    pub schema: BundleSchema,
     /// Vault
-    vault: AccountId,
+    pub vault: AccountId,
+    /// Number of nested bundle levels in the schema, zero without bundle components
+    pub depth: u32,
 }
 
 impl<T: Config> Pallet<T> {
+    /// Canonical `BundleId` of a schema, the hash of its normalized form
+    pub fn bundle_id_for(schema: &BundleSchema<T>) -> Result<BundleId, DispatchError> {
+        Ok(BlakeTwo256::hash_of(&Self::normalize_schema(schema)?))
+    }
+
+    /// Sorts schema entries by class and asset, merging duplicates.
+    /// Zero amounts are rejected.
+    pub fn normalize_schema(schema: &BundleSchema<T>) -> Result<BundleSchema<T>, DispatchError> {
+        let BundleSchema {
+            class_ids,
+            asset_ids,
+            amounts,
+            optional,
+            native,
+        } = schema;
+        ensure!(
+            class_ids.len() == asset_ids.len(),
+            Error::<T>::InvalidArrayLength
+        );
+        ensure!(
+            class_ids.len() == amounts.len(),
+            Error::<T>::InvalidArrayLength
+        );
+
+        let mut entries = Vec::<((T::ClassId, T::AssetId), Balance)>::new();
+        for (class_idx, class_id) in class_ids.iter().enumerate() {
+            ensure!(
+                asset_ids[class_idx].len() == amounts[class_idx].len(),
+                Error::<T>::InvalidArrayLength
+            );
+            for (asset_idx, asset_id) in asset_ids[class_idx].iter().enumerate() {
+                let amount = amounts[class_idx][asset_idx];
+                ensure!(amount > 0, Error::<T>::InvalidAmount);
+                entries.push(((*class_id, *asset_id), amount));
+            }
+        }
+
+        let keys = entries.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+        let mut merged = Vec::<((T::ClassId, T::AssetId), Balance)>::new();
+        for idx in argsort(&keys) {
+            let (key, amount) = entries[idx];
+            match merged.last_mut() {
+                Some((last_key, last_amount)) if *last_key == key => {
+                    *last_amount = last_amount
+                        .checked_add(amount)
+                        .ok_or(Error::<T>::NumOverflow)?;
+                }
+                _ => merged.push((key, amount)),
+            }
+        }
+
+        let mut optional = optional.to_vec();
+        optional.sort();
+        optional.dedup();
+        for component in optional.iter() {
+            ensure!(
+                merged
+                    .binary_search_by_key(component, |(key, _)| *key)
+                    .is_ok(),
+                Error::<T>::InvalidComponent
+            );
+        }
+
+        let mut class_ids = Vec::<T::ClassId>::new();
+        let mut asset_ids = Vec::<Vec<T::AssetId>>::new();
+        let mut amounts = Vec::<Vec<Balance>>::new();
+        for ((class_id, asset_id), amount) in merged {
+            if class_ids.last() != Some(&class_id) {
+                class_ids.push(class_id);
+                asset_ids.push(vec![]);
+                amounts.push(vec![]);
+            }
+            if let Some(class_asset_ids) = asset_ids.last_mut() {
+                class_asset_ids.push(asset_id);
+            }
+            if let Some(class_amounts) = amounts.last_mut() {
+                class_amounts.push(amount);
+            }
+        }
+
+        let asset_ids = asset_ids
+            .into_iter()
+            .map(|ids| ids.try_into())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Error::<T>::InvalidArrayLength)?;
+        let amounts = amounts
+            .into_iter()
+            .map(|amounts| amounts.try_into())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Error::<T>::InvalidArrayLength)?;
+
+        Ok(BundleSchema {
+            class_ids: class_ids
+                .try_into()
+                .map_err(|_| Error::<T>::InvalidArrayLength)?,
+            asset_ids: asset_ids
+                .try_into()
+                .map_err(|_| Error::<T>::InvalidArrayLength)?,
+            amounts: amounts
+                .try_into()
+                .map_err(|_| Error::<T>::InvalidArrayLength)?,
+            optional: optional
+                .try_into()
+                .map_err(|_| Error::<T>::InvalidArrayLength)?,
+            native: *native,
+        })
+    }
+
+    /// Bundles containing the asset as a component
+    pub fn bundles_containing(class_id: T::ClassId, asset_id: T::AssetId) -> Vec<BundleId> {
+        ComponentBundles::<T>::iter_key_prefix((class_id, asset_id)).collect()
+    }
+
+    /// Number of bundles `who` can mint with its current balances
+    pub fn max_mintable(who: &T::AccountId, bundle_id: BundleId) -> Result<Balance, DispatchError> {
+        let bundle = Bundles::<T>::get(bundle_id).ok_or(Error::<T>::BundleNotFound)?;
+        let BundleSchema {
+            class_ids,
+            asset_ids,
+            amounts,
+            native,
+            ..
+        } = bundle.schema;
+
+        let mut mintable = Balance::MAX;
+        for (class_idx, class_id) in class_ids.iter().enumerate() {
+            let balances = sugarfunge_asset::Pallet::<T>::balance_of_single_owner_batch(
+                who,
+                *class_id,
+                asset_ids[class_idx].to_vec(),
+            )?;
+            for (balance_idx, balance) in balances.iter().enumerate() {
+                let amount = amounts[class_idx][balance_idx];
+                if amount > 0 {
+                    mintable = mintable.min(balance / amount);
+                }
+            }
+        }
+
+        if !native.is_zero() {
+            // Minting keeps the account alive
+            let free = <T as Config>::Currency::free_balance(who)
+                .saturating_sub(<T as Config>::Currency::minimum_balance());
+            mintable = mintable.min((free / native).unique_saturated_into());
+        }
+
+        Ok(mintable)
+    }
+
+    /// Nesting depth of a schema, rejecting schemas deeper than `MaxBundleDepth`
+    pub fn schema_depth(schema: &BundleSchema<T>) -> Result<u32, DispatchError> {
+        let mut depth = 0;
+        for (class_idx, class_id) in schema.class_ids.iter().enumerate() {
+            for asset_id in schema.asset_ids[class_idx].iter() {
+                if let Some(child_id) = AssetBundles::<T>::get((class_id, asset_id)) {
+                    let child = Bundles::<T>::get(child_id).ok_or(Error::<T>::BundleNotFound)?;
+                    depth = depth.max(child.depth.saturating_add(1));
+                }
+            }
+        }
+        ensure!(depth <= T::MaxBundleDepth::get(), Error::<T>::BundleTooDeep);
+        Ok(depth)
+    }
+
     pub fn do_register_bundle(
         who: &T::AccountId,
         class_id: T::ClassId,
@@ -218,18 +482,21 @@ impl<T: Config> Pallet<T> {
         schema: &BundleSchema<T>,
         metadata: BundleMetadataOf<T>,
     ) -> DispatchResult {
+        let schema = Self::normalize_schema(schema)?;
+
         ensure!(
             BlakeTwo256::hash_of(&schema) == bundle_id,
             Error::<T>::InvalidBundleIdForBundle
         );
 
-        let bundle_id: BundleId = BlakeTwo256::hash_of(&schema);
-
         ensure!(
-            !Bundles::<T>::contains_key(bundle_id),
+            !Bundles::<T>::contains_key(bundle_id)
+                && !LegacyBundleIds::<T>::contains_key(bundle_id),
             Error::<T>::BundleExists
         );
 
+        let depth = Self::schema_depth(&schema)?;
+
         let operator = <T as Config>::PalletId::get().into_account_truncating();
 
         sugarfunge_asset::Pallet::<T>::do_create_class(
@@ -251,10 +518,19 @@ impl<T: Config> Pallet<T> {
                 schema: schema.clone(),
                 metadata,
                 vault,
+                depth,
             },
         );
 
         AssetBundles::<T>::insert((class_id, asset_id), bundle_id);
+        for (class_idx, component_class_id) in schema.class_ids.iter().enumerate() {
+            for component_asset_id in schema.asset_ids[class_idx].iter() {
+                ComponentBundles::<T>::insert(
+                    (component_class_id, component_asset_id, bundle_id),
+                    (),
+                );
+            }
+        }
 
         Self::deposit_event(Event::Register {
             bundle_id,
@@ -273,9 +549,18 @@ impl<T: Config> Pallet<T> {
         bundle_id: BundleId,
         amount: Balance,
     ) -> DispatchResult {
+        // Assets and native funds only leave the account of the caller
+        ensure!(who == from, Error::<T>::NoPermission);
+
         let bundle = Bundles::<T>::get(bundle_id).ok_or(Error::<T>::BundleNotFound)?;
 
-        let (class_ids, asset_ids, amounts) = bundle.schema;
+        let BundleSchema {
+            class_ids,
+            asset_ids,
+            amounts,
+            native,
+            ..
+        } = bundle.schema;
         ensure!(
             class_ids.len() == asset_ids.len(),
             Error::<T>::InvalidArrayLength
@@ -319,6 +604,12 @@ impl<T: Config> Pallet<T> {
             )?;
         }
 
+        // Transfer native currency to bundle vault
+        let native = native.saturating_mul(amount.unique_saturated_into());
+        if !native.is_zero() {
+            <T as Config>::Currency::transfer(from, &bundle.vault, native, KeepAlive)?;
+        }
+
         let operator: T::AccountId = <T as Config>::PalletId::get().into_account_truncating();
 
         // Mint IOU assets for each bundle created
@@ -348,75 +639,170 @@ impl<T: Config> Pallet<T> {
         bundle_id: BundleId,
         amount: Balance,
     ) -> DispatchResult {
+        ensure!(who == from, Error::<T>::NoPermission);
+
+        Self::do_redeem(from, to, bundle_id, amount, &[])?;
+
+        Self::deposit_event(Event::Burn {
+            bundle_id,
+            who: who.clone(),
+            from: from.clone(),
+            to: to.clone(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_redeem_partial(
+        who: &T::AccountId,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        bundle_id: BundleId,
+        amount: Balance,
+        components: Vec<(T::ClassId, T::AssetId)>,
+    ) -> DispatchResult {
+        ensure!(who == from, Error::<T>::NoPermission);
+
         let bundle = Bundles::<T>::get(bundle_id).ok_or(Error::<T>::BundleNotFound)?;
 
-        let (class_ids, asset_ids, amounts) = bundle.schema;
-        ensure!(
-            class_ids.len() == asset_ids.len(),
-            Error::<T>::InvalidArrayLength
-        );
-        ensure!(
-            class_ids.len() == amounts.len(),
-            Error::<T>::InvalidArrayLength
-        );
+        for component in components.iter() {
+            ensure!(
+                bundle.schema.optional.contains(component),
+                Error::<T>::ComponentNotOptional
+            );
+        }
+
+        let skipped = bundle
+            .schema
+            .optional
+            .iter()
+            .filter(|component| !components.contains(component))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Self::do_redeem(from, to, bundle_id, amount, &skipped)?;
+
+        Self::deposit_event(Event::PartialBurn {
+            bundle_id,
+            who: who.clone(),
+            from: from.clone(),
+            to: to.clone(),
+            amount,
+            components,
+        });
+
+        Ok(())
+    }
+
+    /// Burns `amount` IOUs held by `from` and releases the bundle components from the
+    /// bundle vault to `to`, except for `skipped` components which stay in the vault.
+    /// Components that are bundle IOUs themselves are unwrapped recursively.
+    fn do_redeem(
+        from: &T::AccountId,
+        to: &T::AccountId,
+        bundle_id: BundleId,
+        amount: Balance,
+        skipped: &[(T::ClassId, T::AssetId)],
+    ) -> DispatchResult {
+        let bundle = Bundles::<T>::get(bundle_id).ok_or(Error::<T>::BundleNotFound)?;
 
         // Ensure enough IOU assets to recover bundle assets
         let iou_balance =
-            sugarfunge_asset::Pallet::<T>::balances((from, bundle.class_id, bundle.asset_id));
+            sugarfunge_asset::Pallet::<T>::balance_of(from, bundle.class_id, bundle.asset_id);
         ensure!(iou_balance >= amount, Error::<T>::InsufficientBalance);
 
-        // Ensure enough bundle assets in vault to cover IOU
+        let BundleSchema {
+            class_ids,
+            asset_ids,
+            amounts,
+            native,
+            ..
+        } = bundle.schema;
+
+        // Components released per class
+        let mut releases = Vec::<(T::ClassId, Vec<T::AssetId>, Vec<Balance>)>::new();
         for (class_idx, class_id) in class_ids.iter().enumerate() {
+            let mut release_ids = Vec::new();
+            let mut release_amounts = Vec::new();
+            for (asset_idx, asset_id) in asset_ids[class_idx].iter().enumerate() {
+                if skipped.contains(&(*class_id, *asset_id)) {
+                    continue;
+                }
+                release_ids.push(*asset_id);
+                release_amounts.push(
+                    amounts[class_idx][asset_idx]
+                        .checked_mul(amount)
+                        .ok_or(Error::<T>::NumOverflow)?,
+                );
+            }
+            if !release_ids.is_empty() {
+                releases.push((*class_id, release_ids, release_amounts));
+            }
+        }
+
+        // Ensure enough bundle assets in vault to cover IOU
+        for (class_id, release_ids, release_amounts) in releases.iter() {
             let balances = sugarfunge_asset::Pallet::<T>::balance_of_single_owner_batch(
                 &bundle.vault,
                 *class_id,
-                asset_ids[class_idx].to_vec(),
+                release_ids.clone(),
             )?;
-            let amounts = amounts[class_idx]
-                .iter()
-                .map(|balance| balance.saturating_mul(amount))
-                .collect::<Vec<Balance>>();
             for (balance_idx, balance) in balances.iter().enumerate() {
                 ensure!(
-                    *balance >= amounts[balance_idx],
+                    *balance >= release_amounts[balance_idx],
                     Error::<T>::InsufficientBalance
                 );
             }
         }
 
-        let operator: T::AccountId = <T as Config>::PalletId::get().into_account_truncating();
+        let native = native.saturating_mul(amount.unique_saturated_into());
+        ensure!(
+            <T as Config>::Currency::free_balance(&bundle.vault) >= native,
+            Error::<T>::InsufficientBalance
+        );
 
-        // Transfer bundle assets from reserves to IOU owner
-        for (idx, class_id) in class_ids.iter().enumerate() {
-            sugarfunge_asset::Pallet::<T>::do_batch_transfer_from(
-                &operator,
-                &operator,
-                &to,
-                *class_id,
-                asset_ids[idx].to_vec(),
-                amounts[idx]
-                    .iter()
-                    .map(|balance| balance.saturating_mul(amount))
-                    .collect(),
-            )?;
-        }
+        let operator: T::AccountId = <T as Config>::PalletId::get().into_account_truncating();
 
         // Burn IOU assets
         sugarfunge_asset::Pallet::<T>::do_burn(
             &operator,
-            who,
+            from,
             bundle.class_id,
             bundle.asset_id,
             amount,
         )?;
 
-        Self::deposit_event(Event::Burn {
-            bundle_id,
-            who: who.clone(),
-            from: from.clone(),
-            to: to.clone(),
-            amount,
-        });
+        // Release bundle assets from the vault, unwrapping nested bundles
+        for (class_id, release_ids, release_amounts) in releases {
+            let mut plain_ids = Vec::new();
+            let mut plain_amounts = Vec::new();
+            for (asset_id, release_amount) in release_ids.into_iter().zip(release_amounts) {
+                match AssetBundles::<T>::get((class_id, asset_id)) {
+                    Some(child_id) => {
+                        Self::do_redeem(&bundle.vault, to, child_id, release_amount, &[])?
+                    }
+                    None => {
+                        plain_ids.push(asset_id);
+                        plain_amounts.push(release_amount);
+                    }
+                }
+            }
+            if !plain_ids.is_empty() {
+                sugarfunge_asset::Pallet::<T>::do_batch_custody_transfer_from(
+                    &bundle.vault,
+                    &bundle.vault,
+                    to,
+                    class_id,
+                    plain_ids,
+                    plain_amounts,
+                )?;
+            }
+        }
+
+        if !native.is_zero() {
+            <T as Config>::Currency::transfer(&bundle.vault, to, native, AllowDeath)?;
+        }
 
         Ok(())
     }
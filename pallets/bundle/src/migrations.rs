@@ -0,0 +1,186 @@
+//! Storage migrations of `sugarfunge_bundle`
+
+use super::*;
+use frame_support::{
+    traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+    weights::Weight,
+};
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+use sp_std::{collections::btree_map::BTreeMap, marker::PhantomData};
+
+/// Version 1 stores bundle schemas as `BundleSchema` structs, without native currency for
+/// existing bundles, records the nesting depth of each bundle and indexes the bundles
+/// containing each component asset
+pub mod v1 {
+    use super::*;
+
+    type OldSchema<T> = (
+        BoundedVec<<T as sugarfunge_asset::Config>::ClassId, <T as Config>::MaxAssets>,
+        BoundedVec<
+            BoundedVec<<T as sugarfunge_asset::Config>::AssetId, <T as Config>::MaxAssets>,
+            <T as Config>::MaxAssets,
+        >,
+        BoundedVec<BoundedVec<Balance, <T as Config>::MaxAssets>, <T as Config>::MaxAssets>,
+    );
+
+    #[derive(Decode)]
+    struct OldBundle<ClassId, AssetId, OldSchema, AccountId, BundleMetadataOf> {
+        creator: AccountId,
+        class_id: ClassId,
+        asset_id: AssetId,
+        metadata: BundleMetadataOf,
+        schema: OldSchema,
+        vault: AccountId,
+    }
+
+    /// Bundles registered before version 1 keep their id, since their vault account is
+    /// derived from it. Those whose id is not the hash of the normalized schema are
+    /// recorded in `LegacyBundleIds` under their canonical id.
+    ///
+    /// Runs in a single block, so only while there are at most `Limit` bundles. Above that
+    /// the upgrade is skipped and the storage version stays at 0.
+    pub struct MigrateToV1<T, Limit>(PhantomData<(T, Limit)>);
+
+    impl<T: Config, Limit: Get<u32>> OnRuntimeUpgrade for MigrateToV1<T, Limit> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 0 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let limit = Limit::get() as usize;
+            let counted = Bundles::<T>::iter_keys().take(limit + 1).count() as u64;
+            if counted > limit as u64 {
+                return T::DbWeight::get().reads(counted + 1);
+            }
+
+            let mut translated = 0u64;
+            let mut legacy = Vec::<(BundleId, BundleId)>::new();
+            let mut children = Vec::<(BundleId, Vec<BundleId>)>::new();
+            let mut lookups = 0u64;
+            Bundles::<T>::translate::<
+                OldBundle<T::ClassId, T::AssetId, OldSchema<T>, T::AccountId, BundleMetadataOf<T>>,
+                _,
+            >(|bundle_id, old| {
+                translated += 1;
+                let (class_ids, asset_ids, amounts) = old.schema;
+                let mut child_ids = Vec::new();
+                for (class_idx, class_id) in class_ids.iter().enumerate() {
+                    for asset_id in asset_ids.get(class_idx).into_iter().flatten() {
+                        lookups += 1;
+                        ComponentBundles::<T>::insert((class_id, asset_id, bundle_id), ());
+                        if let Some(child_id) = AssetBundles::<T>::get((class_id, asset_id)) {
+                            child_ids.push(child_id);
+                        }
+                    }
+                }
+                children.push((bundle_id, child_ids));
+                let schema = BundleSchema::<T> {
+                    class_ids,
+                    asset_ids,
+                    amounts,
+                    optional: BoundedVec::default(),
+                    native: Zero::zero(),
+                };
+                if let Ok(canonical_id) = Pallet::<T>::bundle_id_for(&schema) {
+                    if canonical_id != bundle_id {
+                        legacy.push((canonical_id, bundle_id));
+                    }
+                }
+                Some(BundleOf::<T> {
+                    creator: old.creator,
+                    class_id: old.class_id,
+                    asset_id: old.asset_id,
+                    metadata: old.metadata,
+                    schema,
+                    vault: old.vault,
+                    depth: 0,
+                })
+            });
+
+            // Each pass settles one more nesting level. Bundles nested deeper than
+            // `MaxBundleDepth`, or in a cycle, stop one level past it.
+            let mut depths = BTreeMap::<BundleId, u32>::new();
+            for _ in 0..=T::MaxBundleDepth::get() {
+                let mut changed = false;
+                for (bundle_id, child_ids) in children.iter() {
+                    let depth = child_ids
+                        .iter()
+                        .map(|child_id| {
+                            depths
+                                .get(child_id)
+                                .copied()
+                                .unwrap_or_default()
+                                .saturating_add(1)
+                        })
+                        .max()
+                        .unwrap_or_default();
+                    if depth > depths.get(bundle_id).copied().unwrap_or_default() {
+                        depths.insert(*bundle_id, depth);
+                        changed = true;
+                    }
+                }
+                if !changed {
+                    break;
+                }
+            }
+
+            let nested = depths.len() as u64;
+            for (bundle_id, depth) in depths {
+                Bundles::<T>::mutate(bundle_id, |bundle| {
+                    if let Some(bundle) = bundle {
+                        bundle.depth = depth;
+                    }
+                });
+            }
+
+            let aliased = legacy.len() as u64;
+            for (canonical_id, bundle_id) in legacy {
+                LegacyBundleIds::<T>::insert(canonical_id, bundle_id);
+            }
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(
+                counted + translated + lookups + nested + 1,
+                translated + lookups + nested + aliased + 1,
+            )
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+            if Pallet::<T>::on_chain_storage_version() != 0 {
+                return Ok(Vec::new());
+            }
+            let bundles = Bundles::<T>::iter_keys().count() as u32;
+            ensure!(
+                bundles <= Limit::get(),
+                "too many bundles to migrate in one block"
+            );
+            Ok(bundles.encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            ensure!(
+                Pallet::<T>::on_chain_storage_version() == 1,
+                "bundle migration did not run"
+            );
+            if state.is_empty() {
+                return Ok(());
+            }
+            let bundles = u32::decode(&mut &state[..])
+                .map_err(|_| TryRuntimeError::Other("invalid bundle count"))?;
+            ensure!(
+                Bundles::<T>::iter_values().count() as u32 == bundles,
+                "bundles failed to decode after the migration"
+            );
+            ensure!(
+                LegacyBundleIds::<T>::iter_values()
+                    .all(|bundle_id| Bundles::<T>::contains_key(bundle_id)),
+                "legacy bundle ids point to missing bundles"
+            );
+            Ok(())
+        }
+    }
+}
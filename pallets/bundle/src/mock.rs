@@ -7,6 +7,7 @@ use frame_support::{
 };
 use sp_core::H256;
 use sp_runtime::{
+    testing::{TestSignature, UintAuthorityId},
     traits::{BlakeTwo256, IdentityLookup},
     BuildStorage,
 };
@@ -75,6 +76,10 @@ impl sugarfunge_asset::Config for Test {
     type ClassId = u64;
     type MaxClassMetadata = ConstU32<1>;
     type MaxAssetMetadata = ConstU32<1>;
+    type MaxVestingSchedules = ConstU32<2>;
+    type MaxMultiItems = ConstU32<4>;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
 }
 
 impl sugarfunge_bundle::Config for Test {
@@ -82,6 +87,7 @@ impl sugarfunge_bundle::Config for Test {
     type PalletId = BundleModuleId;
     type Currency = Balances;
     type MaxAssets = MaxAssets;
+    type MaxBundleDepth = ConstU32<1>;
 }
 
 frame_support::construct_runtime!(
@@ -1,7 +1,14 @@
-use crate::{mock::*, BundleSchema, Error};
-use frame_support::{assert_err, assert_ok, BoundedVec};
+use crate::{migrations, mock::*, AssetBundles, BundleId, BundleSchema, Bundles, Error};
+use codec::Encode;
+use frame_support::{
+    assert_err, assert_ok,
+    storage::unhashed,
+    traits::{ConstU32, GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+    BoundedVec,
+};
 use sp_core::bounded_vec;
-use sp_runtime::traits::{BlakeTwo256, Hash};
+use sp_runtime::traits::{AccountIdConversion, BlakeTwo256, Hash};
+use sugarfunge_asset::{GiftPolicy, Royalty};
 
 fn last_event() -> RuntimeEvent {
     frame_system::Pallet::<Test>::events()
@@ -55,15 +62,17 @@ fn mint_bundle_works() {
         let basset_ids: BoundedVec<u64, MaxAssets> = asset_ids.clone();
         let bamounts: BoundedVec<u128, MaxAssets> = bounded_vec![1, 2, 3, 4, 5];
 
-        let schema: BundleSchema<Test> = (
-            vec![2000, 3000, 4000].try_into().unwrap(),
-            vec![basset_ids.clone(), basset_ids.clone(), basset_ids.clone()]
+        let schema: BundleSchema<Test> = BundleSchema {
+            class_ids: vec![2000, 3000, 4000].try_into().unwrap(),
+            asset_ids: vec![basset_ids.clone(), basset_ids.clone(), basset_ids.clone()]
                 .try_into()
                 .unwrap(),
-            vec![bamounts.clone(), bamounts.clone(), bamounts.clone()]
+            amounts: vec![bamounts.clone(), bamounts.clone(), bamounts.clone()]
                 .try_into()
                 .unwrap(),
-        );
+            optional: bounded_vec![],
+            native: 0,
+        };
 
         let bundle_id = BlakeTwo256::hash_of(&schema);
 
@@ -119,11 +128,13 @@ fn mint_bundle_fails() {
         let basset_ids: BoundedVec<u64, MaxAssets> = asset_ids.clone();
         let bamounts: BoundedVec<u128, MaxAssets> = bounded_vec![1, 2, 3, 4, 5];
 
-        let schema: BundleSchema<Test> = (
-            bounded_vec![2000, 3000, 4000],
-            bounded_vec![basset_ids.clone(), basset_ids.clone(), basset_ids.clone()],
-            bounded_vec![bamounts.clone(), bamounts.clone(), bamounts.clone()],
-        );
+        let schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000, 3000, 4000],
+            asset_ids: bounded_vec![basset_ids.clone(), basset_ids.clone(), basset_ids.clone()],
+            amounts: bounded_vec![bamounts.clone(), bamounts.clone(), bamounts.clone()],
+            optional: bounded_vec![],
+            native: 0,
+        };
 
         let bundle_id = BlakeTwo256::hash_of(&schema);
 
@@ -158,11 +169,13 @@ fn add_assets_to_existing_bundle() {
         let basset_ids: BoundedVec<u64, MaxAssets> = asset_ids.clone();
         let bamounts: BoundedVec<u128, MaxAssets> = bounded_vec![1, 2, 3, 4, 5];
 
-        let schema: BundleSchema<Test> = (
-            bounded_vec![2000, 3000, 4000],
-            bounded_vec![basset_ids.clone(), basset_ids.clone(), basset_ids.clone()],
-            bounded_vec![bamounts.clone(), bamounts.clone(), bamounts.clone()],
-        );
+        let schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000, 3000, 4000],
+            asset_ids: bounded_vec![basset_ids.clone(), basset_ids.clone(), basset_ids.clone()],
+            amounts: bounded_vec![bamounts.clone(), bamounts.clone(), bamounts.clone()],
+            optional: bounded_vec![],
+            native: 0,
+        };
 
         let bundle_id = BlakeTwo256::hash_of(&schema);
 
@@ -205,11 +218,13 @@ fn burn_bundle_works() {
         let basset_ids: BoundedVec<u64, MaxAssets> = asset_ids.clone();
         let bamounts: BoundedVec<u128, MaxAssets> = bounded_vec![1, 2, 3, 4, 5];
 
-        let schema: BundleSchema<Test> = (
-            bounded_vec![2000, 3000, 4000],
-            bounded_vec![basset_ids.clone(), basset_ids.clone(), basset_ids.clone()],
-            bounded_vec![bamounts.clone(), bamounts.clone(), bamounts.clone()],
-        );
+        let schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000, 3000, 4000],
+            asset_ids: bounded_vec![basset_ids.clone(), basset_ids.clone(), basset_ids.clone()],
+            amounts: bounded_vec![bamounts.clone(), bamounts.clone(), bamounts.clone()],
+            optional: bounded_vec![],
+            native: 0,
+        };
 
         let bundle_id = BlakeTwo256::hash_of(&schema);
 
@@ -263,6 +278,528 @@ fn burn_bundle_works() {
     })
 }
 
+#[test]
+fn equivalent_schemas_share_bundle_id() {
+    new_test_ext().execute_with(|| {
+        before_bundle();
+
+        let schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000, 3000],
+            asset_ids: bounded_vec![bounded_vec![1, 2], bounded_vec![1]],
+            amounts: bounded_vec![bounded_vec![1, 2], bounded_vec![3]],
+            optional: bounded_vec![],
+            native: 0,
+        };
+        let bundle_id = BlakeTwo256::hash_of(&schema);
+        assert_eq!(Bundle::bundle_id_for(&schema), Ok(bundle_id));
+
+        // Same contents in a different order, with a duplicate entry
+        let shuffled: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![3000, 2000, 3000],
+            asset_ids: bounded_vec![bounded_vec![1], bounded_vec![2, 1], bounded_vec![1]],
+            amounts: bounded_vec![bounded_vec![1], bounded_vec![2, 1], bounded_vec![2]],
+            optional: bounded_vec![],
+            native: 0,
+        };
+        assert_eq!(Bundle::bundle_id_for(&shuffled), Ok(bundle_id));
+
+        let zero_amount: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000],
+            asset_ids: bounded_vec![bounded_vec![1, 2]],
+            amounts: bounded_vec![bounded_vec![1, 0]],
+            optional: bounded_vec![],
+            native: 0,
+        };
+        assert_err!(
+            Bundle::bundle_id_for(&zero_amount),
+            Error::<Test>::InvalidAmount
+        );
+
+        assert_ok!(Bundle::do_register_bundle(
+            &1,
+            9000,
+            0,
+            bundle_id,
+            &shuffled,
+            bounded_vec![]
+        ));
+        assert_eq!(Bundle::bundles(bundle_id).unwrap().schema, schema);
+        assert_err!(
+            Bundle::do_register_bundle(&1, 9001, 0, bundle_id, &schema, bounded_vec![]),
+            Error::<Test>::BundleExists
+        );
+    })
+}
+
+#[test]
+fn burn_bundle_releases_from_vault() {
+    new_test_ext().execute_with(|| {
+        before_bundle();
+
+        let schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000, 3000, 4000],
+            asset_ids: bounded_vec![bounded_vec![1, 2], bounded_vec![3], bounded_vec![4, 5]],
+            amounts: bounded_vec![bounded_vec![1, 2], bounded_vec![3], bounded_vec![4, 5]],
+            optional: bounded_vec![],
+            native: 0,
+        };
+        let bundle_id = BlakeTwo256::hash_of(&schema);
+
+        assert_ok!(Bundle::do_register_bundle(
+            &1,
+            9000,
+            0,
+            bundle_id,
+            &schema,
+            bounded_vec![]
+        ));
+        let bundle = Bundle::bundles(bundle_id).unwrap();
+
+        assert_err!(
+            Bundle::do_mint_bundles(&3, &2, &3, bundle_id, 10),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(Bundle::do_mint_bundles(&2, &2, &3, bundle_id, 10));
+        assert_eq!(Asset::balance_of(&3, 9000, 0), 10);
+
+        assert_err!(
+            Bundle::do_burn_bundles(&2, &2, &4, bundle_id, 1),
+            Error::<Test>::InsufficientBalance
+        );
+
+        // Only the IOU holder can redeem its bundles
+        assert_err!(
+            Bundle::do_burn_bundles(&4, &3, &4, bundle_id, 4),
+            Error::<Test>::NoPermission
+        );
+        // IOU is burned from its holder and components leave the bundle vault
+        assert_ok!(Bundle::do_burn_bundles(&3, &3, &4, bundle_id, 4));
+        assert_eq!(Asset::balance_of(&3, 9000, 0), 6);
+        assert_eq!(
+            Asset::balance_of_single_owner_batch(&4, 2000, vec![1, 2]).unwrap(),
+            vec![4, 8]
+        );
+        assert_eq!(
+            Asset::balance_of_single_owner_batch(&4, 3000, vec![3]).unwrap(),
+            vec![12]
+        );
+        assert_eq!(
+            Asset::balance_of_single_owner_batch(&4, 4000, vec![4, 5]).unwrap(),
+            vec![16, 20]
+        );
+        assert_eq!(
+            Asset::balance_of_single_owner_batch(&bundle.vault, 2000, vec![1, 2]).unwrap(),
+            vec![6, 12]
+        );
+        assert_eq!(
+            Asset::balance_of_single_owner_batch(&bundle.vault, 3000, vec![3]).unwrap(),
+            vec![18]
+        );
+        assert_eq!(
+            Asset::balance_of_single_owner_batch(&bundle.vault, 4000, vec![4, 5]).unwrap(),
+            vec![24, 30]
+        );
+    })
+}
+
+#[test]
+fn redeem_partial_bundle() {
+    new_test_ext().execute_with(|| {
+        before_bundle();
+
+        let schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000, 3000],
+            asset_ids: bounded_vec![bounded_vec![1, 2], bounded_vec![1]],
+            amounts: bounded_vec![bounded_vec![1, 1], bounded_vec![2]],
+            optional: bounded_vec![(2000, 2), (3000, 1)],
+            native: 0,
+        };
+        let bundle_id = BlakeTwo256::hash_of(&schema);
+
+        assert_ok!(Bundle::do_register_bundle(
+            &1,
+            9000,
+            0,
+            bundle_id,
+            &schema,
+            bounded_vec![]
+        ));
+        let bundle = Bundle::bundles(bundle_id).unwrap();
+
+        assert_ok!(Bundle::do_mint_bundles(&2, &2, &3, bundle_id, 2));
+
+        assert_err!(
+            Bundle::do_redeem_partial(&3, &3, &3, bundle_id, 1, vec![(2000, 1)]),
+            Error::<Test>::ComponentNotOptional
+        );
+        assert_err!(
+            Bundle::do_redeem_partial(&4, &3, &4, bundle_id, 1, vec![(3000, 1)]),
+            Error::<Test>::NoPermission
+        );
+
+        assert_ok!(Bundle::do_redeem_partial(
+            &3,
+            &3,
+            &3,
+            bundle_id,
+            1,
+            vec![(3000, 1)]
+        ));
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Bundle(crate::Event::PartialBurn {
+                bundle_id,
+                who: 3,
+                from: 3,
+                to: 3,
+                amount: 1,
+                components: vec![(3000, 1)],
+            }),
+        );
+        assert_eq!(Asset::balance_of(&3, 9000, 0), 1);
+        assert_eq!(
+            Asset::balance_of_single_owner_batch(&3, 2000, vec![1, 2]).unwrap(),
+            vec![1, 0]
+        );
+        assert_eq!(Asset::balance_of(&3, 3000, 1), 2);
+
+        // Unclaimed optional component stays in the vault
+        assert_eq!(
+            Asset::balance_of_single_owner_batch(&bundle.vault, 2000, vec![1, 2]).unwrap(),
+            vec![1, 2]
+        );
+        assert_eq!(Asset::balance_of(&bundle.vault, 3000, 1), 2);
+    })
+}
+
+#[test]
+fn bundle_with_native_currency() {
+    new_test_ext().execute_with(|| {
+        before_bundle();
+
+        let schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000],
+            asset_ids: bounded_vec![bounded_vec![1]],
+            amounts: bounded_vec![bounded_vec![1]],
+            optional: bounded_vec![],
+            native: DOLLARS,
+        };
+        let bundle_id = BlakeTwo256::hash_of(&schema);
+
+        assert_ok!(Bundle::do_register_bundle(
+            &1,
+            9000,
+            0,
+            bundle_id,
+            &schema,
+            bounded_vec![]
+        ));
+        let bundle = Bundle::bundles(bundle_id).unwrap();
+
+        let balance = Balances::free_balance(&2);
+        assert_ok!(Bundle::do_mint_bundles(&2, &2, &2, bundle_id, 3));
+        assert_eq!(Balances::free_balance(&2), balance - 3 * DOLLARS);
+        assert_eq!(Balances::free_balance(&bundle.vault), 3 * DOLLARS);
+
+        assert_ok!(Bundle::do_burn_bundles(&2, &2, &3, bundle_id, 3));
+        assert_eq!(Balances::free_balance(&3), 3 * DOLLARS);
+        assert_eq!(Asset::balance_of(&3, 2000, 1), 3);
+        assert_eq!(Balances::free_balance(&bundle.vault), 0);
+    })
+}
+
+#[test]
+fn nested_bundles_unwrap_on_redeem() {
+    new_test_ext().execute_with(|| {
+        before_bundle();
+
+        let child_schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000],
+            asset_ids: bounded_vec![bounded_vec![1]],
+            amounts: bounded_vec![bounded_vec![1]],
+            optional: bounded_vec![],
+            native: 0,
+        };
+        let child_id = BlakeTwo256::hash_of(&child_schema);
+        assert_ok!(Bundle::do_register_bundle(
+            &1,
+            9000,
+            0,
+            child_id,
+            &child_schema,
+            bounded_vec![]
+        ));
+
+        let parent_schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![3000, 9000],
+            asset_ids: bounded_vec![bounded_vec![1], bounded_vec![0]],
+            amounts: bounded_vec![bounded_vec![1], bounded_vec![2]],
+            optional: bounded_vec![],
+            native: 0,
+        };
+        let parent_id = BlakeTwo256::hash_of(&parent_schema);
+        assert_ok!(Bundle::do_register_bundle(
+            &1,
+            9001,
+            0,
+            parent_id,
+            &parent_schema,
+            bounded_vec![]
+        ));
+
+        // Nesting beyond MaxBundleDepth is rejected
+        let grandparent_schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![9001],
+            asset_ids: bounded_vec![bounded_vec![0]],
+            amounts: bounded_vec![bounded_vec![1]],
+            optional: bounded_vec![],
+            native: 0,
+        };
+        assert_err!(
+            Bundle::do_register_bundle(
+                &1,
+                9002,
+                0,
+                BlakeTwo256::hash_of(&grandparent_schema),
+                &grandparent_schema,
+                bounded_vec![]
+            ),
+            Error::<Test>::BundleTooDeep
+        );
+
+        let child = Bundle::bundles(child_id).unwrap();
+        let parent = Bundle::bundles(parent_id).unwrap();
+
+        // Minting the parent locks child IOUs in the parent vault
+        assert_ok!(Bundle::do_mint_bundles(&2, &2, &2, child_id, 4));
+        assert_ok!(Bundle::do_mint_bundles(&2, &2, &2, parent_id, 2));
+        assert_eq!(Asset::balance_of(&2, 9000, 0), 0);
+        assert_eq!(Asset::balance_of(&parent.vault, 9000, 0), 4);
+
+        assert_ok!(Bundle::do_burn_bundles(&2, &2, &3, parent_id, 1));
+        assert_eq!(Asset::balance_of(&2, 9001, 0), 1);
+        assert_eq!(Asset::balance_of(&3, 9000, 0), 0);
+        assert_eq!(Asset::balance_of(&3, 2000, 1), 2);
+        assert_eq!(Asset::balance_of(&3, 3000, 1), 1);
+        assert_eq!(Asset::balance_of(&parent.vault, 9000, 0), 2);
+        assert_eq!(Asset::balance_of(&child.vault, 2000, 1), 2);
+    })
+}
+
+#[test]
+fn bundle_queries() {
+    new_test_ext().execute_with(|| {
+        before_bundle();
+
+        let schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000, 3000],
+            asset_ids: bounded_vec![bounded_vec![1, 2], bounded_vec![1]],
+            amounts: bounded_vec![bounded_vec![1, 7], bounded_vec![3]],
+            optional: bounded_vec![],
+            native: 0,
+        };
+        let bundle_id = BlakeTwo256::hash_of(&schema);
+        assert_ok!(Bundle::do_register_bundle(
+            &1,
+            9000,
+            0,
+            bundle_id,
+            &schema,
+            bounded_vec![]
+        ));
+
+        let other_schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000],
+            asset_ids: bounded_vec![bounded_vec![1]],
+            amounts: bounded_vec![bounded_vec![5]],
+            optional: bounded_vec![],
+            native: 0,
+        };
+        let other_id = BlakeTwo256::hash_of(&other_schema);
+        assert_ok!(Bundle::do_register_bundle(
+            &1,
+            9001,
+            0,
+            other_id,
+            &other_schema,
+            bounded_vec![]
+        ));
+
+        let mut containing = Bundle::bundles_containing(2000, 1);
+        containing.sort();
+        let mut expected = vec![bundle_id, other_id];
+        expected.sort();
+        assert_eq!(containing, expected);
+        assert_eq!(Bundle::bundles_containing(3000, 1), vec![bundle_id]);
+        assert_eq!(Bundle::bundles_containing(4000, 1), vec![]);
+
+        // Limited by class 2000 asset 2: 200 / 7
+        assert_eq!(Bundle::max_mintable(&2, bundle_id), Ok(28));
+        assert_eq!(Bundle::max_mintable(&2, other_id), Ok(20));
+        assert_eq!(Bundle::max_mintable(&3, bundle_id), Ok(0));
+
+        assert_ok!(Bundle::do_mint_bundles(&2, &2, &2, bundle_id, 28));
+        assert_eq!(Bundle::max_mintable(&2, bundle_id), Ok(0));
+    })
+}
+
+/// Stores a bundle in its version 0 layout, its id the hash of the schema as given
+fn put_legacy_bundle(
+    class_ids: BoundedVec<u64, MaxAssets>,
+    asset_ids: BoundedVec<BoundedVec<u64, MaxAssets>, MaxAssets>,
+    amounts: BoundedVec<BoundedVec<u128, MaxAssets>, MaxAssets>,
+    iou_class_id: u64,
+) -> BundleId {
+    let old_schema = (class_ids, asset_ids, amounts);
+    let bundle_id = BlakeTwo256::hash_of(&old_schema);
+
+    let operator: u64 = BundleModuleId::get().into_account_truncating();
+    let vault: u64 = BundleModuleId::get().into_sub_account_truncating(bundle_id);
+    assert_ok!(Asset::do_create_class(
+        &1,
+        &operator,
+        iou_class_id,
+        bounded_vec![]
+    ));
+
+    unhashed::put_raw(
+        &Bundles::<Test>::hashed_key_for(bundle_id),
+        &(
+            1u64,
+            iou_class_id,
+            0u64,
+            Vec::<u8>::new(),
+            old_schema,
+            vault,
+        )
+            .encode(),
+    );
+    AssetBundles::<Test>::insert((iou_class_id, 0), bundle_id);
+
+    bundle_id
+}
+
+#[test]
+fn migration_keeps_legacy_bundle_ids() {
+    new_test_ext().execute_with(|| {
+        before_bundle();
+
+        StorageVersion::new(0).put::<Bundle>();
+        let legacy_id = put_legacy_bundle(
+            bounded_vec![3000, 2000],
+            bounded_vec![bounded_vec![3], bounded_vec![1]],
+            bounded_vec![bounded_vec![2], bounded_vec![1]],
+            9000,
+        );
+
+        // Too many bundles for one block
+        migrations::v1::MigrateToV1::<Test, ConstU32<0>>::on_runtime_upgrade();
+        assert_eq!(Bundle::on_chain_storage_version(), 0);
+
+        migrations::v1::MigrateToV1::<Test, ConstU32<1>>::on_runtime_upgrade();
+
+        assert_eq!(Bundle::on_chain_storage_version(), 1);
+        let bundle = Bundle::bundles(legacy_id).unwrap();
+        assert_eq!(bundle.schema.class_ids.to_vec(), vec![3000, 2000]);
+        assert!(bundle.schema.optional.is_empty());
+        assert_eq!(bundle.schema.native, 0);
+        assert_eq!(bundle.depth, 0);
+
+        // The canonical id of the same schema resolves to the legacy bundle
+        let canonical_id = Bundle::bundle_id_for(&bundle.schema).unwrap();
+        assert_ne!(canonical_id, legacy_id);
+        assert_eq!(Bundle::legacy_bundle_ids(canonical_id), Some(legacy_id));
+        assert_err!(
+            Bundle::do_register_bundle(&1, 9001, 0, canonical_id, &bundle.schema, bounded_vec![]),
+            Error::<Test>::BundleExists
+        );
+
+        assert_ok!(Bundle::do_mint_bundles(&2, &2, &3, legacy_id, 1));
+        assert_eq!(Asset::balance_of(&3, 9000, 0), 1);
+        assert_eq!(
+            Asset::balance_of_single_owner_batch(&bundle.vault, 3000, vec![3]).unwrap(),
+            vec![2]
+        );
+    })
+}
+
+#[test]
+fn migration_records_nested_bundle_depth() {
+    new_test_ext().execute_with(|| {
+        before_bundle();
+
+        StorageVersion::new(0).put::<Bundle>();
+        // The parent holds the IOU of the child bundle
+        let parent_id = put_legacy_bundle(
+            bounded_vec![9000],
+            bounded_vec![bounded_vec![0]],
+            bounded_vec![bounded_vec![1]],
+            9100,
+        );
+        let child_id = put_legacy_bundle(
+            bounded_vec![2000],
+            bounded_vec![bounded_vec![1]],
+            bounded_vec![bounded_vec![1]],
+            9000,
+        );
+
+        migrations::v1::MigrateToV1::<Test, ConstU32<100>>::on_runtime_upgrade();
+
+        assert_eq!(Bundle::bundles(child_id).unwrap().depth, 0);
+        assert_eq!(Bundle::bundles(parent_id).unwrap().depth, 1);
+        assert_eq!(Bundle::bundles_containing(2000, 1), vec![child_id]);
+        assert_eq!(Bundle::bundles_containing(9000, 0), vec![parent_id]);
+    })
+}
+
+#[test]
+fn gift_forbidden_assets_leave_vault() {
+    new_test_ext().execute_with(|| {
+        before_bundle();
+        assert_ok!(Asset::do_mint(&1, &1, 2000, 1, 20));
+        assert_ok!(Asset::set_royalty(
+            RuntimeOrigin::signed(1),
+            2000,
+            Some(1),
+            Some(Royalty {
+                beneficiary: 1,
+                bps: 250,
+                gifts: GiftPolicy::Forbidden,
+            })
+        ));
+
+        let schema: BundleSchema<Test> = BundleSchema {
+            class_ids: bounded_vec![2000],
+            asset_ids: bounded_vec![bounded_vec![1]],
+            amounts: bounded_vec![bounded_vec![1]],
+            optional: bounded_vec![],
+            native: 0,
+        };
+        let bundle_id = BlakeTwo256::hash_of(&schema);
+        assert_ok!(Bundle::do_register_bundle(
+            &1,
+            9000,
+            0,
+            bundle_id,
+            &schema,
+            bounded_vec![]
+        ));
+        let bundle = Bundle::bundles(bundle_id).unwrap();
+
+        // Holders cannot wrap the asset in transferable bundles
+        assert_err!(
+            Bundle::do_mint_bundles(&2, &2, &2, bundle_id, 10),
+            sugarfunge_asset::Error::<Test>::GiftsForbidden
+        );
+        assert_ok!(Bundle::do_mint_bundles(&1, &1, &1, bundle_id, 10));
+
+        // But bundles redeem it out of the vault
+        assert_ok!(Bundle::do_burn_bundles(&1, &1, &1, bundle_id, 4));
+        assert_eq!(Asset::balance_of(&1, 2000, 1), 14);
+        assert_eq!(Asset::balance_of(&bundle.vault, 2000, 1), 6);
+    })
+}
+
 #[test]
 fn before_bundle_works() {
     new_test_ext().execute_with(|| {
@@ -1,8 +1,22 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-/// Edit this file to define custom logic or remove it if it is not needed.
-/// Learn more about FRAME and the core library of Substrate FRAME pallets:
-/// <https://docs.substrate.io/v3/runtime/frame>
+use codec::{Decode, Encode, EncodeLike, MaxEncodedLen};
+use frame_support::pallet_prelude::DispatchError;
+use frame_support::{
+    dispatch::DispatchResult,
+    ensure,
+    traits::{Contains, Get},
+    BoundedVec, PalletId,
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{AccountIdConversion, Dispatchable, One, Saturating, Zero},
+    Perbill, RuntimeDebug,
+};
+use sp_std::prelude::*;
+use sugarfunge_primitives::Balance;
+
 pub use pallet::*;
 
 #[cfg(test)]
@@ -14,90 +28,726 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+pub type DaoId = u32;
+pub type ProposalId = u32;
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct DaoSettings<BlockNumber> {
+    /// Minimum voting power that must take part for a proposal to pass
+    pub quorum: Balance,
+    /// Share of votes in favor required for a proposal to pass
+    pub threshold: Perbill,
+    /// Number of blocks a proposal is open for voting
+    pub voting_period: BlockNumber,
+    /// Base lock period for conviction votes, conviction is disabled when not set
+    pub conviction_period: Option<BlockNumber>,
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Dao<AccountId, ClassId, AssetId, BlockNumber, Metadata> {
+    /// Account that created the dao
+    pub creator: AccountId,
+    /// Class of the asset granting voting power
+    pub class_id: ClassId,
+    /// Asset granting voting power
+    pub asset_id: AssetId,
+    /// Governance settings
+    pub settings: DaoSettings<BlockNumber>,
+    /// Dao metadata
+    pub metadata: Metadata,
+}
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum ProposalStatus {
+    Active,
+    Approved,
+    Rejected,
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct DaoProposal<AccountId, EncodedCall, BlockNumber> {
+    /// The holder who made the proposal
+    pub proposer: AccountId,
+    /// The encoded call dispatched as the dao account
+    pub call: EncodedCall,
+    /// Votes in favor, weighted by conviction
+    pub ayes: Balance,
+    /// Votes against, weighted by conviction
+    pub nays: Balance,
+    /// Assets that took part in the vote
+    pub turnout: Balance,
+    /// Block whose closing balances are counted when the class records checkpoints
+    pub snapshot: BlockNumber,
+    /// Last block open for voting
+    pub end: BlockNumber,
+    /// Proposal status
+    pub status: ProposalStatus,
+}
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum Conviction {
+    /// Assets are not locked and count once
+    None,
+    /// Assets are locked for one conviction period and count twice
+    Locked2x,
+    /// Assets are locked for two conviction periods and count three times
+    Locked3x,
+    /// Assets are locked for four conviction periods and count four times
+    Locked4x,
+}
+
+impl Conviction {
+    pub fn multiplier(&self) -> Balance {
+        match self {
+            Conviction::None => 1,
+            Conviction::Locked2x => 2,
+            Conviction::Locked3x => 3,
+            Conviction::Locked4x => 4,
+        }
+    }
+
+    pub fn lock_periods(&self) -> u32 {
+        match self {
+            Conviction::None => 0,
+            Conviction::Locked2x => 1,
+            Conviction::Locked3x => 2,
+            Conviction::Locked4x => 4,
+        }
+    }
+}
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Vote {
+    /// Whether the vote is in favor
+    pub aye: bool,
+    /// Assets backing the vote
+    pub shares: Balance,
+    /// Conviction of the vote
+    pub conviction: Conviction,
+}
+
+#[derive(
+    Encode, Decode, Clone, Copy, Default, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen,
+)]
+pub struct VoteLock<BlockNumber> {
+    /// Assets held by the pallet on behalf of the voter
+    pub amount: Balance,
+    /// First block the assets can be unlocked
+    pub until: BlockNumber,
+    /// Block the locked amount last changed
+    pub updated: BlockNumber,
+}
+
 #[frame_support::pallet]
 pub mod pallet {
-	use frame_support::pallet_prelude::*;
-	use frame_system::pallet_prelude::*;
+    use super::*;
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
 
     /// Configure the pallet by specifying the parameters and types on which it depends.
     #[pallet::config]
-    pub trait Config: frame_system::Config {
+    pub trait Config: frame_system::Config + sugarfunge_asset::Config {
         /// Because this pallet emits events, it depends on the runtime's definition of an event.
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        type PalletId: Get<PalletId>;
+
+        /// Call proposed by holders and dispatched as the dao account
+        type Proposal: Parameter
+            + Dispatchable<RuntimeOrigin = <Self as frame_system::Config>::RuntimeOrigin>
+            + EncodeLike;
+
+        /// Calls a dao is allowed to propose
+        type ProposalFilter: Contains<<Self as Config>::Proposal>;
+
+        /// Max size of an encoded proposal
+        #[pallet::constant]
+        type MaxProposalLength: Get<u32>;
+
+        /// Max metadata size
+        #[pallet::constant]
+        type MaxMetadata: Get<u32>;
     }
 
+    pub type DaoSettingsOf<T> = DaoSettings<BlockNumberFor<T>>;
+    pub type DaoOf<T> = Dao<
+        <T as frame_system::Config>::AccountId,
+        <T as sugarfunge_asset::Config>::ClassId,
+        <T as sugarfunge_asset::Config>::AssetId,
+        BlockNumberFor<T>,
+        BoundedVec<u8, <T as Config>::MaxMetadata>,
+    >;
+    pub type DaoProposalOf<T> = DaoProposal<
+        <T as frame_system::Config>::AccountId,
+        BoundedVec<u8, <T as Config>::MaxProposalLength>,
+        BlockNumberFor<T>,
+    >;
+
     #[pallet::pallet]
     pub struct Pallet<T>(_);
 
-    // The pallet's runtime storage items.
-    // https://docs.substrate.io/v3/runtime/storage
     #[pallet::storage]
-    #[pallet::getter(fn something)]
-    // Learn more about declaring storage items:
-    // https://docs.substrate.io/v3/runtime/storage#declaring-storage-items
-    pub type Something<T> = StorageValue<_, u32>;
+    #[pallet::getter(fn next_dao_id)]
+    pub(super) type NextDaoId<T: Config> = StorageValue<_, DaoId, ValueQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn daos)]
+    pub(super) type Daos<T: Config> = StorageMap<_, Blake2_128Concat, DaoId, DaoOf<T>>;
+
+    #[pallet::storage]
+    pub(super) type NextProposalId<T: Config> =
+        StorageMap<_, Blake2_128Concat, DaoId, ProposalId, ValueQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn proposals)]
+    pub(super) type Proposals<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        DaoId,
+        Blake2_128Concat,
+        ProposalId,
+        DaoProposalOf<T>,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn votes)]
+    pub(super) type Votes<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, DaoId>,
+            NMapKey<Blake2_128Concat, ProposalId>,
+            NMapKey<Blake2_128Concat, T::AccountId>,
+        ),
+        Vote,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn locks)]
+    pub(super) type Locks<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        DaoId,
+        Blake2_128Concat,
+        T::AccountId,
+        VoteLock<BlockNumberFor<T>>,
+        ValueQuery,
+    >;
 
-    // Pallets use events to inform users when important changes are made.
-    // https://docs.substrate.io/v3/runtime/events-and-errors
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
-        /// Event documentation should end with an array that provides descriptive names for event
-        /// parameters. [something, who]
-        SomethingStored(u32, T::AccountId),
+        Created {
+            dao_id: DaoId,
+            creator: T::AccountId,
+            account: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        },
+        SettingsUpdated {
+            dao_id: DaoId,
+            settings: DaoSettingsOf<T>,
+        },
+        Proposed {
+            dao_id: DaoId,
+            proposal_id: ProposalId,
+            who: T::AccountId,
+        },
+        Voted {
+            dao_id: DaoId,
+            proposal_id: ProposalId,
+            who: T::AccountId,
+            aye: bool,
+            shares: Balance,
+            conviction: Conviction,
+        },
+        Closed {
+            dao_id: DaoId,
+            proposal_id: ProposalId,
+            ayes: Balance,
+            nays: Balance,
+            turnout: Balance,
+            approved: bool,
+        },
+        Executed {
+            dao_id: DaoId,
+            proposal_id: ProposalId,
+            result: DispatchResult,
+        },
+        Unlocked {
+            dao_id: DaoId,
+            who: T::AccountId,
+            amount: Balance,
+        },
     }
 
-    // Errors inform users that something went wrong.
     #[pallet::error]
     pub enum Error<T> {
-        /// Error names should be descriptive.
-        NoneValue,
-        /// Errors should have helpful documentation associated with them.
-        StorageOverflow,
+        InvalidDao,
+        InvalidAsset,
+        InvalidSettings,
+        InvalidProposal,
+        ProposalNotAllowed,
+        ProposalTooLong,
+        ProposalClosed,
+        VotingEnded,
+        VotingNotEnded,
+        AlreadyVoted,
+        InsufficientShares,
+        ConvictionDisabled,
+        NoLock,
+        StillLocked,
+        NoPermission,
+        NumOverflow,
     }
 
-    // Dispatchable functions allows users to interact with the pallet and invoke state changes.
-    // These functions materialize as "extrinsics", which are often compared to transactions.
-    // Dispatchable functions must be annotated with a weight and must return a DispatchResult.
     #[pallet::call]
     impl<T: Config> Pallet<T> {
-        /// An example dispatchable that takes a singles value as a parameter, writes the value to
-        /// storage and emits an event. This function must be dispatched by a signed extrinsic.
         #[pallet::call_index(0)]
-        #[pallet::weight(Weight::from_parts(10_000 as u64, 0) + T::DbWeight::get().writes(1_u64))]
-        pub fn do_something(origin: OriginFor<T>, something: u32) -> DispatchResult {
-            // Check that the extrinsic was signed and get the signer.
-            // This function will return an error if the extrinsic is not signed.
-            // https://docs.substrate.io/v3/runtime/origins
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn create(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            settings: DaoSettingsOf<T>,
+            metadata: BoundedVec<u8, <T as Config>::MaxMetadata>,
+        ) -> DispatchResultWithPostInfo {
             let who = ensure_signed(origin)?;
 
-            // Update storage.
-            <Something<T>>::put(something);
+            Self::do_create(&who, class_id, asset_id, settings, metadata)?;
 
-            // Emit an event.
-            Self::deposit_event(Event::SomethingStored(something, who));
-            // Return a successful DispatchResultWithPostInfo
-            Ok(())
+            Ok(().into())
         }
 
-        /// An example dispatchable that may throw a custom error.
+        /// Update the settings of a dao, dispatched by the dao account through a proposal
         #[pallet::call_index(1)]
-        #[pallet::weight(Weight::from_parts(10_000 as u64, 0) + T::DbWeight::get().reads_writes(1_u64,1_u64))]
-        pub fn cause_error(origin: OriginFor<T>) -> DispatchResult {
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn set_settings(
+            origin: OriginFor<T>,
+            dao_id: DaoId,
+            settings: DaoSettingsOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            ensure!(who == Self::dao_account(dao_id), Error::<T>::NoPermission);
+
+            Self::do_set_settings(dao_id, settings)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn propose(
+            origin: OriginFor<T>,
+            dao_id: DaoId,
+            call: Box<<T as Config>::Proposal>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_propose(&who, dao_id, *call)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn vote(
+            origin: OriginFor<T>,
+            dao_id: DaoId,
+            proposal_id: ProposalId,
+            aye: bool,
+            conviction: Conviction,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_vote(&who, dao_id, proposal_id, aye, conviction)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn close(
+            origin: OriginFor<T>,
+            dao_id: DaoId,
+            proposal_id: ProposalId,
+        ) -> DispatchResultWithPostInfo {
             let _who = ensure_signed(origin)?;
 
-            // Read a value from storage.
-            match <Something<T>>::get() {
-                // Return an error if the value has not been set.
-                None => Err(Error::<T>::NoneValue)?,
-                Some(old) => {
-                    // Increment the value read from storage; will error in the event of overflow.
-                    let new = old.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
-                    // Update the value in storage with the incremented result.
-                    <Something<T>>::put(new);
-                    Ok(())
-                }
+            Self::do_close(dao_id, proposal_id)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(5)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn unlock(origin: OriginFor<T>, dao_id: DaoId) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_unlock(&who, dao_id)?;
+
+            Ok(().into())
+        }
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Account holding the treasury of a dao and dispatching its proposals
+    pub fn dao_account(dao_id: DaoId) -> T::AccountId {
+        <T as Config>::PalletId::get().into_sub_account_truncating(dao_id)
+    }
+
+    /// Account holding assets locked by votes
+    pub fn lock_account() -> T::AccountId {
+        <T as Config>::PalletId::get().into_account_truncating()
+    }
+
+    fn ensure_valid_settings(settings: &DaoSettingsOf<T>) -> DispatchResult {
+        ensure!(
+            settings.threshold.deconstruct() > 0 && !settings.voting_period.is_zero(),
+            Error::<T>::InvalidSettings
+        );
+        if let Some(period) = settings.conviction_period {
+            ensure!(!period.is_zero(), Error::<T>::InvalidSettings);
+        }
+        Ok(())
+    }
+
+    pub fn do_create(
+        who: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+        settings: DaoSettingsOf<T>,
+        metadata: BoundedVec<u8, <T as Config>::MaxMetadata>,
+    ) -> Result<DaoId, DispatchError> {
+        ensure!(
+            sugarfunge_asset::Pallet::<T>::class_exists(class_id),
+            Error::<T>::InvalidAsset
+        );
+        Self::ensure_valid_settings(&settings)?;
+
+        let dao_id = NextDaoId::<T>::try_mutate(|id| -> Result<DaoId, DispatchError> {
+            let current_id = *id;
+            *id = id.checked_add(1).ok_or(Error::<T>::NumOverflow)?;
+            Ok(current_id)
+        })?;
+
+        Daos::<T>::insert(
+            dao_id,
+            Dao {
+                creator: who.clone(),
+                class_id,
+                asset_id,
+                settings,
+                metadata,
+            },
+        );
+
+        Self::deposit_event(Event::Created {
+            dao_id,
+            creator: who.clone(),
+            account: Self::dao_account(dao_id),
+            class_id,
+            asset_id,
+        });
+
+        Ok(dao_id)
+    }
+
+    pub fn do_set_settings(dao_id: DaoId, settings: DaoSettingsOf<T>) -> DispatchResult {
+        Self::ensure_valid_settings(&settings)?;
+
+        Daos::<T>::try_mutate(dao_id, |dao| -> DispatchResult {
+            let dao = dao.as_mut().ok_or(Error::<T>::InvalidDao)?;
+            dao.settings = settings.clone();
+            Ok(())
+        })?;
+
+        Self::deposit_event(Event::SettingsUpdated { dao_id, settings });
+
+        Ok(())
+    }
+
+    /// Voting power of an account: assets it can move plus assets locked in the dao
+    pub fn voting_power(dao_id: DaoId, who: &T::AccountId) -> Balance {
+        match Daos::<T>::get(dao_id) {
+            Some(dao) => {
+                Self::free_shares(&dao, who).saturating_add(Locks::<T>::get(dao_id, who).amount)
+            }
+            None => 0,
+        }
+    }
+
+    /// Voting power at the end of `block`, or `None` when the dao class did not record
+    /// checkpoints by then and votes fall back to locking current shares
+    ///
+    /// Assets locked after `block` are already part of the checkpointed balance, so a
+    /// lock only adds to it when it has not changed since.
+    fn shares_at(
+        dao: &DaoOf<T>,
+        who: &T::AccountId,
+        lock: &VoteLock<BlockNumberFor<T>>,
+        block: BlockNumberFor<T>,
+    ) -> Option<Balance> {
+        let balance =
+            sugarfunge_asset::Pallet::<T>::balance_at(who, dao.class_id, dao.asset_id, block)?;
+        let locked = if lock.updated <= block {
+            lock.amount
+        } else {
+            0
+        };
+        Some(balance.saturating_add(locked))
+    }
+
+    fn free_shares(dao: &DaoOf<T>, who: &T::AccountId) -> Balance {
+        sugarfunge_asset::Pallet::<T>::balance_of(who, dao.class_id, dao.asset_id).saturating_sub(
+            sugarfunge_asset::Pallet::<T>::locked_balance_of(who, dao.class_id, dao.asset_id),
+        )
+    }
+
+    pub fn do_propose(
+        who: &T::AccountId,
+        dao_id: DaoId,
+        call: <T as Config>::Proposal,
+    ) -> Result<ProposalId, DispatchError> {
+        let dao = Daos::<T>::get(dao_id).ok_or(Error::<T>::InvalidDao)?;
+
+        ensure!(
+            Self::voting_power(dao_id, who) > 0,
+            Error::<T>::InsufficientShares
+        );
+
+        ensure!(
+            T::ProposalFilter::contains(&call),
+            Error::<T>::ProposalNotAllowed
+        );
+        let call: BoundedVec<u8, T::MaxProposalLength> = call
+            .encode()
+            .try_into()
+            .map_err(|_| Error::<T>::ProposalTooLong)?;
+
+        let proposal_id =
+            NextProposalId::<T>::try_mutate(dao_id, |id| -> Result<ProposalId, DispatchError> {
+                let current_id = *id;
+                *id = id.checked_add(1).ok_or(Error::<T>::NumOverflow)?;
+                Ok(current_id)
+            })?;
+
+        let now = frame_system::Pallet::<T>::block_number();
+        let end = now.saturating_add(dao.settings.voting_period);
+
+        Proposals::<T>::insert(
+            dao_id,
+            proposal_id,
+            DaoProposal {
+                proposer: who.clone(),
+                call,
+                ayes: 0,
+                nays: 0,
+                turnout: 0,
+                snapshot: now.saturating_sub(One::one()),
+                end,
+                status: ProposalStatus::Active,
+            },
+        );
+
+        Self::deposit_event(Event::Proposed {
+            dao_id,
+            proposal_id,
+            who: who.clone(),
+        });
+
+        Ok(proposal_id)
+    }
+
+    pub fn do_vote(
+        who: &T::AccountId,
+        dao_id: DaoId,
+        proposal_id: ProposalId,
+        aye: bool,
+        conviction: Conviction,
+    ) -> DispatchResult {
+        let dao = Daos::<T>::get(dao_id).ok_or(Error::<T>::InvalidDao)?;
+        let mut proposal =
+            Proposals::<T>::get(dao_id, proposal_id).ok_or(Error::<T>::InvalidProposal)?;
+
+        ensure!(
+            proposal.status == ProposalStatus::Active,
+            Error::<T>::ProposalClosed
+        );
+        ensure!(
+            frame_system::Pallet::<T>::block_number() <= proposal.end,
+            Error::<T>::VotingEnded
+        );
+        ensure!(
+            !Votes::<T>::contains_key((dao_id, proposal_id, who)),
+            Error::<T>::AlreadyVoted
+        );
+
+        let free = Self::free_shares(&dao, who);
+        let mut lock = Locks::<T>::get(dao_id, who);
+        let snapshot_shares = Self::shares_at(&dao, who, &lock, proposal.snapshot);
+        let shares = snapshot_shares.unwrap_or_else(|| free.saturating_add(lock.amount));
+        ensure!(shares > 0, Error::<T>::InsufficientShares);
+
+        let until = if conviction != Conviction::None {
+            let period = dao
+                .settings
+                .conviction_period
+                .ok_or(Error::<T>::ConvictionDisabled)?;
+            Some(
+                proposal
+                    .end
+                    .saturating_add(period.saturating_mul(conviction.lock_periods().into())),
+            )
+        } else if snapshot_shares.is_none() {
+            // Shares counted at their current balance stay locked until voting ends, so they
+            // cannot be moved to another account and vote again
+            Some(proposal.end.saturating_add(One::one()))
+        } else {
+            None
+        };
+
+        if let Some(until) = until {
+            // Move the voter's assets into the pallet so they cannot vote again elsewhere
+            sugarfunge_asset::Pallet::<T>::do_custody_transfer_from(
+                who,
+                who,
+                &Self::lock_account(),
+                dao.class_id,
+                dao.asset_id,
+                free,
+            )?;
+
+            if !free.is_zero() {
+                lock.amount = lock.amount.saturating_add(free);
+                lock.updated = frame_system::Pallet::<T>::block_number();
             }
+            lock.until = lock.until.max(until);
+            Locks::<T>::insert(dao_id, who, lock);
+        }
+
+        let votes = shares
+            .checked_mul(conviction.multiplier())
+            .ok_or(Error::<T>::NumOverflow)?;
+
+        if aye {
+            proposal.ayes = proposal
+                .ayes
+                .checked_add(votes)
+                .ok_or(Error::<T>::NumOverflow)?;
+        } else {
+            proposal.nays = proposal
+                .nays
+                .checked_add(votes)
+                .ok_or(Error::<T>::NumOverflow)?;
+        }
+        proposal.turnout = proposal
+            .turnout
+            .checked_add(shares)
+            .ok_or(Error::<T>::NumOverflow)?;
+
+        Proposals::<T>::insert(dao_id, proposal_id, &proposal);
+        Votes::<T>::insert(
+            (dao_id, proposal_id, who),
+            Vote {
+                aye,
+                shares,
+                conviction,
+            },
+        );
+
+        Self::deposit_event(Event::Voted {
+            dao_id,
+            proposal_id,
+            who: who.clone(),
+            aye,
+            shares,
+            conviction,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_close(dao_id: DaoId, proposal_id: ProposalId) -> DispatchResult {
+        let dao = Daos::<T>::get(dao_id).ok_or(Error::<T>::InvalidDao)?;
+        let mut proposal =
+            Proposals::<T>::get(dao_id, proposal_id).ok_or(Error::<T>::InvalidProposal)?;
+
+        ensure!(
+            proposal.status == ProposalStatus::Active,
+            Error::<T>::ProposalClosed
+        );
+        ensure!(
+            frame_system::Pallet::<T>::block_number() > proposal.end,
+            Error::<T>::VotingNotEnded
+        );
+
+        let votes = proposal.ayes.saturating_add(proposal.nays);
+        let approved = proposal.ayes > 0
+            && proposal.turnout >= dao.settings.quorum
+            && proposal.ayes >= dao.settings.threshold.mul_ceil(votes);
+
+        proposal.status = if approved {
+            ProposalStatus::Approved
+        } else {
+            ProposalStatus::Rejected
+        };
+        Proposals::<T>::insert(dao_id, proposal_id, &proposal);
+
+        Self::deposit_event(Event::Closed {
+            dao_id,
+            proposal_id,
+            ayes: proposal.ayes,
+            nays: proposal.nays,
+            turnout: proposal.turnout,
+            approved,
+        });
+
+        if approved {
+            let call = <T as Config>::Proposal::decode(&mut &proposal.call[..])
+                .map_err(|_| Error::<T>::InvalidProposal)?;
+            let result = call
+                .dispatch(frame_system::RawOrigin::Signed(Self::dao_account(dao_id)).into())
+                .map(|_| ())
+                .map_err(|e| e.error);
+
+            Self::deposit_event(Event::Executed {
+                dao_id,
+                proposal_id,
+                result,
+            });
         }
+
+        Ok(())
+    }
+
+    pub fn do_unlock(who: &T::AccountId, dao_id: DaoId) -> DispatchResult {
+        let dao = Daos::<T>::get(dao_id).ok_or(Error::<T>::InvalidDao)?;
+        let lock = Locks::<T>::get(dao_id, who);
+
+        ensure!(lock.amount > 0, Error::<T>::NoLock);
+        ensure!(
+            frame_system::Pallet::<T>::block_number() >= lock.until,
+            Error::<T>::StillLocked
+        );
+
+        sugarfunge_asset::Pallet::<T>::do_custody_transfer_from(
+            &Self::lock_account(),
+            &Self::lock_account(),
+            who,
+            dao.class_id,
+            dao.asset_id,
+            lock.amount,
+        )?;
+        Locks::<T>::remove(dao_id, who);
+
+        Self::deposit_event(Event::Unlocked {
+            dao_id,
+            who: who.clone(),
+            amount: lock.amount,
+        });
+
+        Ok(())
     }
 }
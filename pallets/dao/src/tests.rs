@@ -1,23 +1,464 @@
-use crate::{mock::*, Error};
-use frame_support::{assert_noop, assert_ok};
+use crate::{mock::*, Conviction, DaoSettings, Error, ProposalStatus, Proposals};
+use frame_support::{assert_noop, assert_ok, bounded_vec};
+use sp_runtime::Perbill;
+use sugarfunge_asset::{GiftPolicy, Royalty};
+
+fn last_event() -> RuntimeEvent {
+    frame_system::Pallet::<Test>::events()
+        .pop()
+        .expect("Event expected")
+        .event
+}
+
+fn settings() -> DaoSettings<u64> {
+    DaoSettings {
+        quorum: 50,
+        threshold: Perbill::from_percent(50),
+        voting_period: 10,
+        conviction_period: Some(5),
+    }
+}
+
+fn spend_call(value: u128) -> Box<RuntimeCall> {
+    Box::new(RuntimeCall::Balances(
+        pallet_balances::Call::transfer_keep_alive { dest: 4, value },
+    ))
+}
+
+pub fn before_dao() {
+    System::set_block_number(1);
+
+    // Holders of 1000/1 govern the dao, 2 holds 60 and 3 holds 40
+    assert_ok!(Asset::do_create_class(&1, &1, 1000, bounded_vec![0]));
+    assert_ok!(Asset::do_mint(&1, &2, 1000, 1, 60));
+    assert_ok!(Asset::do_mint(&1, &3, 1000, 1, 40));
+
+    assert_ok!(Dao::create(
+        RuntimeOrigin::signed(1),
+        1000,
+        1,
+        settings(),
+        bounded_vec![]
+    ));
+
+    assert_ok!(Balances::transfer_keep_alive(
+        RuntimeOrigin::signed(1),
+        Dao::dao_account(0),
+        100 * DOLLARS
+    ));
+}
+
+#[test]
+fn before_dao_works() {
+    new_test_ext().execute_with(|| {
+        before_dao();
+
+        assert_eq!(Dao::next_dao_id(), 1);
+        assert_eq!(Balances::free_balance(Dao::dao_account(0)), 100 * DOLLARS);
+    })
+}
+
+#[test]
+fn create_dao_fails() {
+    new_test_ext().execute_with(|| {
+        before_dao();
+
+        assert_noop!(
+            Dao::create(
+                RuntimeOrigin::signed(1),
+                2000,
+                1,
+                settings(),
+                bounded_vec![]
+            ),
+            Error::<Test>::InvalidAsset
+        );
+        assert_noop!(
+            Dao::create(
+                RuntimeOrigin::signed(1),
+                1000,
+                1,
+                DaoSettings {
+                    voting_period: 0,
+                    ..settings()
+                },
+                bounded_vec![]
+            ),
+            Error::<Test>::InvalidSettings
+        );
+    })
+}
+
+#[test]
+fn holders_spend_treasury() {
+    new_test_ext().execute_with(|| {
+        before_dao();
+
+        assert_noop!(
+            Dao::propose(RuntimeOrigin::signed(4), 0, spend_call(10 * DOLLARS)),
+            Error::<Test>::InsufficientShares
+        );
+        assert_ok!(Dao::propose(
+            RuntimeOrigin::signed(3),
+            0,
+            spend_call(10 * DOLLARS)
+        ));
+
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(2),
+            0,
+            0,
+            true,
+            Conviction::None
+        ));
+        assert_noop!(
+            Dao::vote(RuntimeOrigin::signed(2), 0, 0, true, Conviction::None),
+            Error::<Test>::AlreadyVoted
+        );
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(3),
+            0,
+            0,
+            false,
+            Conviction::None
+        ));
+
+        assert_noop!(
+            Dao::close(RuntimeOrigin::signed(4), 0, 0),
+            Error::<Test>::VotingNotEnded
+        );
+
+        System::set_block_number(12);
+        assert_noop!(
+            Dao::vote(RuntimeOrigin::signed(1), 0, 0, true, Conviction::None),
+            Error::<Test>::VotingEnded
+        );
+        assert_ok!(Dao::close(RuntimeOrigin::signed(4), 0, 0));
+
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Dao(crate::Event::Executed {
+                dao_id: 0,
+                proposal_id: 0,
+                result: Ok(()),
+            })
+        );
+        assert_eq!(
+            Proposals::<Test>::get(0, 0).unwrap().status,
+            ProposalStatus::Approved
+        );
+        assert_eq!(Balances::free_balance(Dao::dao_account(0)), 90 * DOLLARS);
+        assert_eq!(Balances::free_balance(4), 10 * DOLLARS);
+
+        assert_noop!(
+            Dao::close(RuntimeOrigin::signed(4), 0, 0),
+            Error::<Test>::ProposalClosed
+        );
+    })
+}
+
+#[test]
+fn proposal_rejected_without_quorum() {
+    new_test_ext().execute_with(|| {
+        before_dao();
+
+        assert_ok!(Dao::propose(
+            RuntimeOrigin::signed(3),
+            0,
+            spend_call(10 * DOLLARS)
+        ));
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(3),
+            0,
+            0,
+            true,
+            Conviction::None
+        ));
+
+        System::set_block_number(12);
+        assert_ok!(Dao::close(RuntimeOrigin::signed(4), 0, 0));
+
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Dao(crate::Event::Closed {
+                dao_id: 0,
+                proposal_id: 0,
+                ayes: 40,
+                nays: 0,
+                turnout: 40,
+                approved: false,
+            })
+        );
+        assert_eq!(Balances::free_balance(Dao::dao_account(0)), 100 * DOLLARS);
+    })
+}
+
+#[test]
+fn conviction_locks_voting_assets() {
+    new_test_ext().execute_with(|| {
+        before_dao();
+
+        assert_ok!(Dao::propose(
+            RuntimeOrigin::signed(3),
+            0,
+            spend_call(10 * DOLLARS)
+        ));
+
+        // 40 locked at 3x outweighs 60 unlocked
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(3),
+            0,
+            0,
+            true,
+            Conviction::Locked3x
+        ));
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(2),
+            0,
+            0,
+            false,
+            Conviction::None
+        ));
+
+        assert_eq!(Asset::balance_of(&3, 1000, 1), 0);
+        assert_eq!(Asset::balance_of(&Dao::lock_account(), 1000, 1), 40);
+        assert_eq!(Dao::voting_power(0, &3), 40);
+        assert_eq!(Dao::locks(0, 3).until, 21);
+
+        System::set_block_number(12);
+        assert_ok!(Dao::close(RuntimeOrigin::signed(4), 0, 0));
+        assert_eq!(
+            Proposals::<Test>::get(0, 0).unwrap().status,
+            ProposalStatus::Approved
+        );
+
+        assert_noop!(
+            Dao::unlock(RuntimeOrigin::signed(3), 0),
+            Error::<Test>::StillLocked
+        );
+        // Votes without conviction are locked until voting ends
+        assert_eq!(Dao::locks(0, 2).until, 12);
+        assert_ok!(Dao::unlock(RuntimeOrigin::signed(2), 0));
+        assert_eq!(Asset::balance_of(&2, 1000, 1), 60);
+        assert_noop!(
+            Dao::unlock(RuntimeOrigin::signed(2), 0),
+            Error::<Test>::NoLock
+        );
+
+        System::set_block_number(21);
+        assert_ok!(Dao::unlock(RuntimeOrigin::signed(3), 0));
+        assert_eq!(Asset::balance_of(&3, 1000, 1), 40);
+        assert_eq!(Asset::balance_of(&Dao::lock_account(), 1000, 1), 0);
+    })
+}
+
+#[test]
+fn gift_forbidden_shares_lock_and_unlock() {
+    new_test_ext().execute_with(|| {
+        before_dao();
+        assert_ok!(Asset::set_royalty(
+            RuntimeOrigin::signed(1),
+            1000,
+            None,
+            Some(Royalty {
+                beneficiary: 1,
+                bps: 250,
+                gifts: GiftPolicy::Forbidden,
+            })
+        ));
+
+        assert_ok!(Dao::propose(
+            RuntimeOrigin::signed(3),
+            0,
+            spend_call(10 * DOLLARS)
+        ));
+        // Locking shares to vote is not gifting them
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(3),
+            0,
+            0,
+            true,
+            Conviction::Locked2x
+        ));
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(2),
+            0,
+            0,
+            false,
+            Conviction::None
+        ));
+        assert_eq!(Asset::balance_of(&Dao::lock_account(), 1000, 1), 100);
+
+        System::set_block_number(12);
+        assert_ok!(Dao::unlock(RuntimeOrigin::signed(2), 0));
+        assert_eq!(Asset::balance_of(&2, 1000, 1), 60);
+
+        System::set_block_number(16);
+        assert_ok!(Dao::unlock(RuntimeOrigin::signed(3), 0));
+        assert_eq!(Asset::balance_of(&3, 1000, 1), 40);
+    })
+}
+
+#[test]
+fn votes_lock_shares_without_checkpoints() {
+    new_test_ext().execute_with(|| {
+        before_dao();
+
+        assert_ok!(Dao::propose(
+            RuntimeOrigin::signed(3),
+            0,
+            spend_call(10 * DOLLARS)
+        ));
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(2),
+            0,
+            0,
+            true,
+            Conviction::None
+        ));
+
+        // Voted shares cannot be passed on to vote again
+        assert_eq!(Asset::balance_of(&Dao::lock_account(), 1000, 1), 60);
+        assert!(Asset::do_transfer_from(&2, &2, &4, 1000, 1, 60).is_err());
+        assert_noop!(
+            Dao::vote(RuntimeOrigin::signed(4), 0, 0, true, Conviction::None),
+            Error::<Test>::InsufficientShares
+        );
+        assert_noop!(
+            Dao::unlock(RuntimeOrigin::signed(2), 0),
+            Error::<Test>::StillLocked
+        );
+
+        System::set_block_number(12);
+        assert_ok!(Dao::unlock(RuntimeOrigin::signed(2), 0));
+        assert_eq!(Asset::balance_of(&2, 1000, 1), 60);
+    })
+}
 
 #[test]
-fn it_works_for_default_value() {
+fn snapshots_before_checkpoints_lock_shares() {
     new_test_ext().execute_with(|| {
-        // Dispatch a signed extrinsic.
-        assert_ok!(Dao::do_something(RuntimeOrigin::signed(1), 42));
-        // Read pallet storage and assert an expected result.
-        assert_eq!(Dao::something(), Some(42));
-    });
+        before_dao();
+
+        assert_ok!(Dao::propose(
+            RuntimeOrigin::signed(3),
+            0,
+            spend_call(10 * DOLLARS)
+        ));
+        assert_ok!(Asset::enable_checkpoints(RuntimeOrigin::signed(1), 1000));
+
+        // The snapshot predates the checkpoints, so current shares are counted and locked
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(2),
+            0,
+            0,
+            true,
+            Conviction::None
+        ));
+        assert_eq!(Proposals::<Test>::get(0, 0).unwrap().ayes, 60);
+        assert_eq!(Asset::balance_of(&Dao::lock_account(), 1000, 1), 60);
+        assert!(Asset::do_transfer_from(&2, &2, &4, 1000, 1, 60).is_err());
+    })
 }
 
 #[test]
-fn correct_error_for_none_value() {
+fn settings_updated_through_proposal() {
     new_test_ext().execute_with(|| {
-        // Ensure the expected error is thrown when no value is present.
+        before_dao();
+
+        let new_settings = DaoSettings {
+            conviction_period: None,
+            ..settings()
+        };
+
         assert_noop!(
-            Dao::cause_error(RuntimeOrigin::signed(1)),
-            Error::<Test>::NoneValue
+            Dao::set_settings(RuntimeOrigin::signed(1), 0, new_settings.clone()),
+            Error::<Test>::NoPermission
         );
-    });
+
+        let call = RuntimeCall::Dao(crate::Call::set_settings {
+            dao_id: 0,
+            settings: new_settings.clone(),
+        });
+        assert_ok!(Dao::propose(RuntimeOrigin::signed(2), 0, Box::new(call)));
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(2),
+            0,
+            0,
+            true,
+            Conviction::None
+        ));
+
+        System::set_block_number(12);
+        assert_ok!(Dao::close(RuntimeOrigin::signed(4), 0, 0));
+        assert_eq!(Dao::daos(0).unwrap().settings, new_settings);
+
+        assert_ok!(Dao::propose(
+            RuntimeOrigin::signed(2),
+            0,
+            spend_call(10 * DOLLARS)
+        ));
+        assert_noop!(
+            Dao::vote(RuntimeOrigin::signed(2), 0, 1, true, Conviction::Locked2x),
+            Error::<Test>::ConvictionDisabled
+        );
+    })
+}
+
+#[test]
+fn checkpointed_votes_count_snapshot_balances() {
+    new_test_ext().execute_with(|| {
+        before_dao();
+        assert_ok!(Asset::enable_checkpoints(RuntimeOrigin::signed(1), 1000));
+
+        System::set_block_number(2);
+        assert_ok!(Dao::propose(
+            RuntimeOrigin::signed(3),
+            0,
+            spend_call(10 * DOLLARS)
+        ));
+
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(2),
+            0,
+            0,
+            true,
+            Conviction::Locked2x
+        ));
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(3),
+            0,
+            0,
+            false,
+            Conviction::None
+        ));
+
+        // Assets moved after the snapshot carry no voting power
+        assert_ok!(Asset::do_transfer_from(&3, &3, &4, 1000, 1, 40));
+        assert_noop!(
+            Dao::vote(RuntimeOrigin::signed(4), 0, 0, false, Conviction::None),
+            Error::<Test>::InsufficientShares
+        );
+
+        let proposal = Proposals::<Test>::get(0, 0).unwrap();
+        assert_eq!(proposal.ayes, 120);
+        assert_eq!(proposal.nays, 40);
+        assert_eq!(proposal.turnout, 100);
+
+        // Locked assets keep counting on later proposals
+        System::set_block_number(3);
+        assert_ok!(Dao::propose(
+            RuntimeOrigin::signed(2),
+            0,
+            spend_call(10 * DOLLARS)
+        ));
+        assert_ok!(Dao::vote(
+            RuntimeOrigin::signed(2),
+            0,
+            1,
+            true,
+            Conviction::None
+        ));
+        assert_eq!(Proposals::<Test>::get(0, 1).unwrap().ayes, 60);
+    })
 }
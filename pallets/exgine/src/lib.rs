@@ -1,8 +1,16 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-/// Edit this file to define custom logic or remove it if it is not needed.
-/// Learn more about FRAME and the core library of Substrate FRAME pallets:
-/// <https://docs.substrate.io/v3/runtime/frame>
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{dispatch::DispatchResult, ensure, traits::Randomness, BoundedVec};
+use frame_system::pallet_prelude::BlockNumberFor;
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{Hash, One, Saturating, Zero},
+    DispatchError, RuntimeDebug,
+};
+use sp_std::{collections::btree_map::BTreeMap, prelude::*};
+use sugarfunge_primitives::Balance;
+
 pub use pallet::*;
 
 #[cfg(test)]
@@ -14,46 +22,265 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+pub type RecipeId = u64;
+pub type LootTableId = u64;
+
+const LOOT_SUBJECT: &[u8] = b"sugarfunge/exgine/loot";
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct RecipeAsset<ClassId, AssetId> {
+    pub class_id: ClassId,
+    pub asset_id: AssetId,
+    pub amount: Balance,
+}
+
+pub type RecipeAssets<T> = BoundedVec<
+    RecipeAsset<<T as sugarfunge_asset::Config>::ClassId, <T as sugarfunge_asset::Config>::AssetId>,
+    <T as Config>::MaxRecipeAssets,
+>;
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Recipe<AccountId, Assets, BlockNumber> {
+    pub owner: AccountId,
+    /// Assets burned from the crafter on every craft
+    pub inputs: Assets,
+    /// Assets the crafter must hold but which are not consumed
+    pub catalysts: Assets,
+    /// Assets minted to the crafter on every craft
+    pub outputs: Assets,
+    /// Blocks an account must wait between crafts
+    pub cooldown: BlockNumber,
+    /// Max number of crafts per account
+    pub max_crafts: Option<u32>,
+    /// Loot table rolled once on every craft
+    pub loot: Option<LootTableId>,
+}
+
+pub type RecipeOf<T> =
+    Recipe<<T as frame_system::Config>::AccountId, RecipeAssets<T>, BlockNumberFor<T>>;
+
+#[derive(
+    Encode, Decode, Clone, Copy, Default, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen,
+)]
+pub struct CraftInfo<BlockNumber> {
+    pub crafts: u32,
+    pub last_block: BlockNumber,
+}
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct LootEntry<ClassId, AssetId> {
+    pub class_id: ClassId,
+    pub asset_id: AssetId,
+    pub amount: Balance,
+    /// Relative chance of this entry being picked on a roll
+    pub weight: u32,
+}
+
+pub type LootEntries<T> = BoundedVec<
+    LootEntry<<T as sugarfunge_asset::Config>::ClassId, <T as sugarfunge_asset::Config>::AssetId>,
+    <T as Config>::MaxLootEntries,
+>;
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct LootTable<AccountId, Entries> {
+    pub owner: AccountId,
+    pub entries: Entries,
+}
+
+pub type LootTableOf<T> = LootTable<<T as frame_system::Config>::AccountId, LootEntries<T>>;
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Pack {
+    pub table_id: LootTableId,
+    /// Number of rolls for every pack opened
+    pub rolls: u32,
+}
+
+/// Loot rolled once the randomness of a later block is known
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct PendingRoll<BlockNumber> {
+    pub table_id: LootTableId,
+    pub rolls: u32,
+    /// Block whose randomness seeds the rolls
+    pub reveal_at: BlockNumber,
+}
+
+pub type LootDrops<T> = Vec<(
+    <T as sugarfunge_asset::Config>::ClassId,
+    <T as sugarfunge_asset::Config>::AssetId,
+    Balance,
+)>;
+
 #[frame_support::pallet]
 pub mod pallet {
-    use frame_support::{dispatch::DispatchResult, pallet_prelude::*};
+    use super::*;
+    use frame_support::pallet_prelude::*;
     use frame_system::pallet_prelude::*;
 
     /// Configure the pallet by specifying the parameters and types on which it depends.
     #[pallet::config]
-    pub trait Config: frame_system::Config {
+    pub trait Config: frame_system::Config + sugarfunge_asset::Config {
         /// Because this pallet emits events, it depends on the runtime's definition of an event.
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Max number of inputs, catalysts or outputs in a recipe
+        #[pallet::constant]
+        type MaxRecipeAssets: Get<u32>;
+
+        /// Source of randomness for loot rolls
+        type Randomness: Randomness<Self::Hash, BlockNumberFor<Self>>;
+
+        /// Max number of entries in a loot table
+        #[pallet::constant]
+        type MaxLootEntries: Get<u32>;
+
+        /// Max number of loot rolls in a single call
+        #[pallet::constant]
+        type MaxLootRolls: Get<u32>;
     }
 
     #[pallet::pallet]
     pub struct Pallet<T>(_);
 
-    // The pallet's runtime storage items.
-    // https://docs.substrate.io/v3/runtime/storage
     #[pallet::storage]
-    #[pallet::getter(fn something)]
-    // Learn more about declaring storage items:
-    // https://docs.substrate.io/v3/runtime/storage#declaring-storage-items
-    pub type Something<T> = StorageValue<_, u32>;
+    #[pallet::getter(fn next_recipe_id)]
+    pub(super) type NextRecipeId<T: Config> = StorageValue<_, RecipeId, ValueQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn recipes)]
+    pub(super) type Recipes<T: Config> = StorageMap<_, Blake2_128Concat, RecipeId, RecipeOf<T>>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn crafts)]
+    pub(super) type Crafts<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        RecipeId,
+        Blake2_128Concat,
+        T::AccountId,
+        CraftInfo<BlockNumberFor<T>>,
+        ValueQuery,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn next_loot_table_id)]
+    pub(super) type NextLootTableId<T: Config> = StorageValue<_, LootTableId, ValueQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn loot_tables)]
+    pub(super) type LootTables<T: Config> =
+        StorageMap<_, Blake2_128Concat, LootTableId, LootTableOf<T>>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn packs)]
+    pub(super) type Packs<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, T::ClassId, Blake2_128Concat, T::AssetId, Pack>;
+
+    /// Id of the next committed roll, mixed into its seed so rolls of a block differ
+    #[pallet::storage]
+    pub(super) type LootNonce<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+    /// Loot rolls committed by an account and not revealed yet
+    #[pallet::storage]
+    #[pallet::getter(fn pending_rolls)]
+    pub(super) type PendingRolls<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Twox64Concat,
+        u64,
+        PendingRoll<BlockNumberFor<T>>,
+    >;
+
+    /// Number of unrevealed rolls seeded by the randomness of a block
+    #[pallet::storage]
+    pub(super) type DueRolls<T: Config> =
+        StorageMap<_, Twox64Concat, BlockNumberFor<T>, u32, ValueQuery>;
+
+    /// Randomness recorded at the start of a block for the rolls it seeds
+    #[pallet::storage]
+    #[pallet::getter(fn roll_seeds)]
+    pub(super) type RollSeeds<T: Config> = StorageMap<_, Twox64Concat, BlockNumberFor<T>, T::Hash>;
 
-    // Pallets use events to inform users when important changes are made.
-    // https://docs.substrate.io/v3/runtime/events-and-errors
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
-        /// Event documentation should end with an array that provides descriptive names for event
-        /// parameters. [something, who]
-        SomethingStored(u32, T::AccountId),
+        RecipeCreated {
+            recipe_id: RecipeId,
+            owner: T::AccountId,
+        },
+        RecipeRemoved {
+            recipe_id: RecipeId,
+            owner: T::AccountId,
+        },
+        Crafted {
+            recipe_id: RecipeId,
+            who: T::AccountId,
+            times: u32,
+        },
+        LootTableCreated {
+            table_id: LootTableId,
+            owner: T::AccountId,
+        },
+        PackSet {
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            table_id: LootTableId,
+            rolls: u32,
+        },
+        PackOpened {
+            who: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        },
+        LootCommitted {
+            roll_id: u64,
+            table_id: LootTableId,
+            who: T::AccountId,
+            rolls: u32,
+            reveal_at: BlockNumberFor<T>,
+        },
+        LootDropped {
+            roll_id: u64,
+            table_id: LootTableId,
+            who: T::AccountId,
+            drops: LootDrops<T>,
+        },
     }
 
-    // Errors inform users that something went wrong.
     #[pallet::error]
     pub enum Error<T> {
-        /// Error names should be descriptive.
-        NoneValue,
-        /// Errors should have helpful documentation associated with them.
-        StorageOverflow,
+        InvalidRecipe,
+        InvalidRecipeOwner,
+        InvalidAmount,
+        InvalidTimes,
+        NoPermission,
+        InsufficientInput,
+        MissingCatalyst,
+        CooldownActive,
+        CraftLimitReached,
+        NumOverflow,
+        InvalidLootTable,
+        InvalidLootTableOwner,
+        InvalidWeight,
+        InvalidPack,
+        TooManyRolls,
+        InvalidRoll,
+        RollNotReady,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+            if !DueRolls::<T>::contains_key(now) {
+                return T::DbWeight::get().reads(1);
+            }
+            // Rolls committed in earlier blocks could not know this randomness
+            let (seed, _) = T::Randomness::random(LOOT_SUBJECT);
+            RollSeeds::<T>::insert(now, seed);
+            T::DbWeight::get().reads_writes(2, 1)
+        }
     }
 
     // Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -61,43 +288,486 @@ pub mod pallet {
     // Dispatchable functions must be annotated with a weight and must return a DispatchResult.
     #[pallet::call]
     impl<T: Config> Pallet<T> {
-        /// An example dispatchable that takes a singles value as a parameter, writes the value to
-        /// storage and emits an event. This function must be dispatched by a signed extrinsic.
         #[pallet::call_index(0)]
-        #[pallet::weight(Weight::from_parts(10_000 as u64, 0) + T::DbWeight::get().writes(1_u64))]
-        pub fn do_something(origin: OriginFor<T>, something: u32) -> DispatchResult {
-            // Check that the extrinsic was signed and get the signer.
-            // This function will return an error if the extrinsic is not signed.
-            // https://docs.substrate.io/v3/runtime/origins
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn create_recipe(
+            origin: OriginFor<T>,
+            inputs: RecipeAssets<T>,
+            catalysts: RecipeAssets<T>,
+            outputs: RecipeAssets<T>,
+            cooldown: BlockNumberFor<T>,
+            max_crafts: Option<u32>,
+            loot: Option<LootTableId>,
+        ) -> DispatchResultWithPostInfo {
             let who = ensure_signed(origin)?;
 
-            // Update storage.
-            <Something<T>>::put(something);
+            Self::do_create_recipe(&who, inputs, catalysts, outputs, cooldown, max_crafts, loot)?;
 
-            // Emit an event.
-            Self::deposit_event(Event::SomethingStored(something, who));
-            // Return a successful DispatchResultWithPostInfo
-            Ok(())
+            Ok(().into())
         }
 
-        /// An example dispatchable that may throw a custom error.
         #[pallet::call_index(1)]
-        #[pallet::weight(Weight::from_parts(10_000 as u64, 0) + T::DbWeight::get().reads_writes(1_u64,1_u64))]
-        pub fn cause_error(origin: OriginFor<T>) -> DispatchResult {
-            let _who = ensure_signed(origin)?;
-
-            // Read a value from storage.
-            match <Something<T>>::get() {
-                // Return an error if the value has not been set.
-                None => Err(Error::<T>::NoneValue)?,
-                Some(old) => {
-                    // Increment the value read from storage; will error in the event of overflow.
-                    let new = old.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
-                    // Update the value in storage with the incremented result.
-                    <Something<T>>::put(new);
-                    Ok(())
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn remove_recipe(
+            origin: OriginFor<T>,
+            recipe_id: RecipeId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_remove_recipe(&who, recipe_id)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn craft(
+            origin: OriginFor<T>,
+            recipe_id: RecipeId,
+            times: u32,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_craft(&who, recipe_id, times)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn create_loot_table(
+            origin: OriginFor<T>,
+            entries: LootEntries<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_create_loot_table(&who, entries)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn set_pack(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            table_id: LootTableId,
+            rolls: u32,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_set_pack(&who, class_id, asset_id, table_id, rolls)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(5)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn open_pack(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_open_pack(&who, class_id, asset_id, amount)?;
+
+            Ok(().into())
+        }
+
+        /// Mint the loot of a roll committed in an earlier block
+        #[pallet::call_index(6)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn reveal_loot(origin: OriginFor<T>, roll_id: u64) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_reveal_loot(&who, roll_id)?;
+
+            Ok(().into())
+        }
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    pub fn do_create_recipe(
+        who: &T::AccountId,
+        inputs: RecipeAssets<T>,
+        catalysts: RecipeAssets<T>,
+        outputs: RecipeAssets<T>,
+        cooldown: BlockNumberFor<T>,
+        max_crafts: Option<u32>,
+        loot: Option<LootTableId>,
+    ) -> Result<RecipeId, DispatchError> {
+        ensure!(
+            !outputs.is_empty() || loot.is_some(),
+            Error::<T>::InvalidRecipe
+        );
+
+        if let Some(table_id) = loot {
+            let table = LootTables::<T>::get(table_id).ok_or(Error::<T>::InvalidLootTable)?;
+            ensure!(table.owner == *who, Error::<T>::InvalidLootTableOwner);
+        }
+
+        for asset in inputs.iter().chain(catalysts.iter()).chain(outputs.iter()) {
+            ensure!(asset.amount > 0, Error::<T>::InvalidAmount);
+        }
+
+        for output in outputs.iter() {
+            ensure!(
+                sugarfunge_asset::Pallet::<T>::account_is_owner(who, output.class_id),
+                Error::<T>::NoPermission
+            );
+        }
+
+        let recipe_id = NextRecipeId::<T>::try_mutate(|id| -> Result<RecipeId, DispatchError> {
+            let current_id = *id;
+            *id = id.checked_add(1).ok_or(Error::<T>::NumOverflow)?;
+            Ok(current_id)
+        })?;
+
+        let recipe = Recipe {
+            owner: who.clone(),
+            inputs,
+            catalysts,
+            outputs,
+            cooldown,
+            max_crafts,
+            loot,
+        };
+
+        Recipes::<T>::insert(recipe_id, recipe);
+
+        Self::deposit_event(Event::RecipeCreated {
+            recipe_id,
+            owner: who.clone(),
+        });
+
+        Ok(recipe_id)
+    }
+
+    pub fn do_remove_recipe(who: &T::AccountId, recipe_id: RecipeId) -> DispatchResult {
+        let recipe = Recipes::<T>::get(recipe_id).ok_or(Error::<T>::InvalidRecipe)?;
+        ensure!(recipe.owner == *who, Error::<T>::InvalidRecipeOwner);
+
+        Recipes::<T>::remove(recipe_id);
+        let _ = Crafts::<T>::clear_prefix(recipe_id, u32::MAX, None);
+
+        Self::deposit_event(Event::RecipeRemoved {
+            recipe_id,
+            owner: who.clone(),
+        });
+
+        Ok(())
+    }
+
+    pub fn do_craft(who: &T::AccountId, recipe_id: RecipeId, times: u32) -> DispatchResult {
+        ensure!(times > 0, Error::<T>::InvalidTimes);
+
+        let recipe = Recipes::<T>::get(recipe_id).ok_or(Error::<T>::InvalidRecipe)?;
+
+        let now = frame_system::Pallet::<T>::block_number();
+        let info = Crafts::<T>::get(recipe_id, who);
+
+        if info.crafts > 0 && !recipe.cooldown.is_zero() {
+            ensure!(
+                now >= info.last_block.saturating_add(recipe.cooldown),
+                Error::<T>::CooldownActive
+            );
+        }
+
+        let crafts = info
+            .crafts
+            .checked_add(times)
+            .ok_or(Error::<T>::NumOverflow)?;
+        if let Some(max_crafts) = recipe.max_crafts {
+            ensure!(crafts <= max_crafts, Error::<T>::CraftLimitReached);
+        }
+        if recipe.loot.is_some() {
+            ensure!(times <= T::MaxLootRolls::get(), Error::<T>::TooManyRolls);
+        }
+
+        for catalyst in recipe.catalysts.iter() {
+            ensure!(
+                sugarfunge_asset::Pallet::<T>::usable_balance_of(
+                    who,
+                    catalyst.class_id,
+                    catalyst.asset_id
+                ) >= catalyst.amount,
+                Error::<T>::MissingCatalyst
+            );
+        }
+
+        Self::apply_recipe(who, &recipe, times)?;
+
+        Crafts::<T>::insert(
+            recipe_id,
+            who,
+            CraftInfo {
+                crafts,
+                last_block: now,
+            },
+        );
+
+        Self::deposit_event(Event::Crafted {
+            recipe_id,
+            who: who.clone(),
+            times,
+        });
+
+        Ok(())
+    }
+
+    fn apply_recipe(who: &T::AccountId, recipe: &RecipeOf<T>, times: u32) -> DispatchResult {
+        for input in recipe.inputs.iter() {
+            let amount = input
+                .amount
+                .checked_mul(times.into())
+                .ok_or(Error::<T>::NumOverflow)?;
+            ensure!(
+                sugarfunge_asset::Pallet::<T>::balance_of(who, input.class_id, input.asset_id)
+                    >= amount,
+                Error::<T>::InsufficientInput
+            );
+            sugarfunge_asset::Pallet::<T>::do_burn(
+                who,
+                who,
+                input.class_id,
+                input.asset_id,
+                amount,
+            )?;
+        }
+
+        for output in recipe.outputs.iter() {
+            let amount = output
+                .amount
+                .checked_mul(times.into())
+                .ok_or(Error::<T>::NumOverflow)?;
+            sugarfunge_asset::Pallet::<T>::do_mint(
+                &recipe.owner,
+                who,
+                output.class_id,
+                output.asset_id,
+                amount,
+            )?;
+        }
+
+        if let Some(table_id) = recipe.loot {
+            Self::commit_roll(who, table_id, times)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn do_create_loot_table(
+        who: &T::AccountId,
+        entries: LootEntries<T>,
+    ) -> Result<LootTableId, DispatchError> {
+        ensure!(!entries.is_empty(), Error::<T>::InvalidLootTable);
+
+        for entry in entries.iter() {
+            ensure!(entry.amount > 0, Error::<T>::InvalidAmount);
+            ensure!(entry.weight > 0, Error::<T>::InvalidWeight);
+            ensure!(
+                sugarfunge_asset::Pallet::<T>::account_is_owner(who, entry.class_id),
+                Error::<T>::NoPermission
+            );
+        }
+
+        let table_id =
+            NextLootTableId::<T>::try_mutate(|id| -> Result<LootTableId, DispatchError> {
+                let current_id = *id;
+                *id = id.checked_add(1).ok_or(Error::<T>::NumOverflow)?;
+                Ok(current_id)
+            })?;
+
+        LootTables::<T>::insert(
+            table_id,
+            LootTable {
+                owner: who.clone(),
+                entries,
+            },
+        );
+
+        Self::deposit_event(Event::LootTableCreated {
+            table_id,
+            owner: who.clone(),
+        });
+
+        Ok(table_id)
+    }
+
+    pub fn do_set_pack(
+        who: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+        table_id: LootTableId,
+        rolls: u32,
+    ) -> DispatchResult {
+        ensure!(
+            sugarfunge_asset::Pallet::<T>::account_is_owner(who, class_id),
+            Error::<T>::NoPermission
+        );
+        ensure!(
+            rolls > 0 && rolls <= T::MaxLootRolls::get(),
+            Error::<T>::InvalidPack
+        );
+
+        let table = LootTables::<T>::get(table_id).ok_or(Error::<T>::InvalidLootTable)?;
+        ensure!(table.owner == *who, Error::<T>::InvalidLootTableOwner);
+
+        Packs::<T>::insert(class_id, asset_id, Pack { table_id, rolls });
+
+        Self::deposit_event(Event::PackSet {
+            class_id,
+            asset_id,
+            table_id,
+            rolls,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_open_pack(
+        who: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+        amount: Balance,
+    ) -> DispatchResult {
+        ensure!(amount > 0, Error::<T>::InvalidAmount);
+
+        let pack = Packs::<T>::get(class_id, asset_id).ok_or(Error::<T>::InvalidPack)?;
+
+        let rolls = amount
+            .checked_mul(pack.rolls.into())
+            .ok_or(Error::<T>::NumOverflow)?;
+        ensure!(
+            rolls <= T::MaxLootRolls::get().into(),
+            Error::<T>::TooManyRolls
+        );
+
+        sugarfunge_asset::Pallet::<T>::do_burn(who, who, class_id, asset_id, amount)?;
+        Self::commit_roll(who, pack.table_id, rolls as u32)?;
+
+        Self::deposit_event(Event::PackOpened {
+            who: who.clone(),
+            class_id,
+            asset_id,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Commit `rolls` of a loot table for `who`, seeded by the randomness of the next block
+    fn commit_roll(
+        who: &T::AccountId,
+        table_id: LootTableId,
+        rolls: u32,
+    ) -> Result<u64, DispatchError> {
+        ensure!(
+            LootTables::<T>::contains_key(table_id),
+            Error::<T>::InvalidLootTable
+        );
+
+        let roll_id = LootNonce::<T>::mutate(|nonce| {
+            let current = *nonce;
+            *nonce = nonce.wrapping_add(1);
+            current
+        });
+        let reveal_at = frame_system::Pallet::<T>::block_number().saturating_add(One::one());
+
+        PendingRolls::<T>::insert(
+            who,
+            roll_id,
+            PendingRoll {
+                table_id,
+                rolls,
+                reveal_at,
+            },
+        );
+        DueRolls::<T>::mutate(reveal_at, |due| *due = due.saturating_add(1));
+
+        Self::deposit_event(Event::LootCommitted {
+            roll_id,
+            table_id,
+            who: who.clone(),
+            rolls,
+            reveal_at,
+        });
+
+        Ok(roll_id)
+    }
+
+    pub fn do_reveal_loot(who: &T::AccountId, roll_id: u64) -> DispatchResult {
+        let pending = PendingRolls::<T>::get(who, roll_id).ok_or(Error::<T>::InvalidRoll)?;
+        let block_seed = RollSeeds::<T>::get(pending.reveal_at).ok_or(Error::<T>::RollNotReady)?;
+
+        PendingRolls::<T>::remove(who, roll_id);
+        let due = DueRolls::<T>::get(pending.reveal_at).saturating_sub(1);
+        if due == 0 {
+            DueRolls::<T>::remove(pending.reveal_at);
+            RollSeeds::<T>::remove(pending.reveal_at);
+        } else {
+            DueRolls::<T>::insert(pending.reveal_at, due);
+        }
+
+        let seed = T::Hashing::hash_of(&(block_seed, roll_id));
+        Self::roll_loot(who, roll_id, pending.table_id, pending.rolls, seed)
+    }
+
+    /// Sample `rolls` entries of a loot table from `seed` and mint them to `who`
+    fn roll_loot(
+        who: &T::AccountId,
+        roll_id: u64,
+        table_id: LootTableId,
+        rolls: u32,
+        seed: T::Hash,
+    ) -> DispatchResult {
+        let table = LootTables::<T>::get(table_id).ok_or(Error::<T>::InvalidLootTable)?;
+
+        let total_weight: u64 = table.entries.iter().map(|e| e.weight as u64).sum();
+        ensure!(total_weight > 0, Error::<T>::InvalidLootTable);
+
+        let mut drops: BTreeMap<(T::ClassId, T::AssetId), Balance> = BTreeMap::new();
+        for roll in 0..rolls {
+            let hash = T::Hashing::hash_of(&(seed, roll));
+            let value = u64::decode(&mut hash.as_ref()).unwrap_or_default() % total_weight;
+
+            let mut cumulative = 0u64;
+            for entry in table.entries.iter() {
+                cumulative += entry.weight as u64;
+                if value < cumulative {
+                    let dropped = drops.entry((entry.class_id, entry.asset_id)).or_default();
+                    *dropped = dropped
+                        .checked_add(entry.amount)
+                        .ok_or(Error::<T>::NumOverflow)?;
+                    break;
                 }
             }
         }
+
+        for ((class_id, asset_id), amount) in drops.iter() {
+            sugarfunge_asset::Pallet::<T>::do_mint(
+                &table.owner,
+                who,
+                *class_id,
+                *asset_id,
+                *amount,
+            )?;
+        }
+
+        Self::deposit_event(Event::LootDropped {
+            roll_id,
+            table_id,
+            who: who.clone(),
+            drops: drops
+                .into_iter()
+                .map(|((class_id, asset_id), amount)| (class_id, asset_id, amount))
+                .collect(),
+        });
+
+        Ok(())
     }
 }
@@ -1,12 +1,24 @@
 use crate as sugarfunge_exgine;
-use frame_support::traits::{ConstU16, ConstU32, ConstU64, Everything};
+use frame_support::{
+    parameter_types,
+    traits::{ConstU128, ConstU16, ConstU32, ConstU64, Everything, Randomness},
+};
 use sp_core::H256;
 use sp_runtime::{
-    traits::{BlakeTwo256, IdentityLookup},
+    testing::{TestSignature, UintAuthorityId},
+    traits::{BlakeTwo256, Hash, IdentityLookup},
     BuildStorage,
 };
 use sugarfunge_primitives::Balance;
 
+pub const MILLICENTS: Balance = 10_000_000_000_000;
+pub const CENTS: Balance = 1_000 * MILLICENTS;
+pub const DOLLARS: Balance = 100 * CENTS;
+
+parameter_types! {
+    pub const CreateAssetClassDeposit: Balance = 500 * MILLICENTS;
+}
+
 impl frame_system::Config for Test {
     type BaseCallFilter = Everything;
     type BlockWeights = ();
@@ -34,22 +46,73 @@ impl frame_system::Config for Test {
     type RuntimeTask = ();
 }
 
+impl pallet_balances::Config for Test {
+    type Balance = Balance;
+    type RuntimeEvent = RuntimeEvent;
+    type DustRemoval = ();
+    type ExistentialDeposit = ConstU128<500>;
+    type AccountStore = System;
+    type WeightInfo = pallet_balances::weights::SubstrateWeight<Test>;
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type FreezeIdentifier = ();
+    type MaxHolds = ();
+    type MaxFreezes = ();
+    type RuntimeHoldReason = ();
+    type RuntimeFreezeReason = ();
+}
+
+impl sugarfunge_asset::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type CreateAssetClassDeposit = CreateAssetClassDeposit;
+    type Currency = Balances;
+    type AssetId = u64;
+    type ClassId = u64;
+    type MaxClassMetadata = ConstU32<1>;
+    type MaxAssetMetadata = ConstU32<1>;
+    type MaxVestingSchedules = ConstU32<2>;
+    type MaxMultiItems = ConstU32<4>;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
+}
+
+/// Deterministic randomness derived from the subject and current block
+pub struct MockRandomness;
+
+impl Randomness<H256, u64> for MockRandomness {
+    fn random(subject: &[u8]) -> (H256, u64) {
+        let block_number = System::block_number();
+        (BlakeTwo256::hash_of(&(subject, block_number)), block_number)
+    }
+}
+
 impl sugarfunge_exgine::Config for Test {
     type RuntimeEvent = RuntimeEvent;
+    type MaxRecipeAssets = ConstU32<4>;
+    type Randomness = MockRandomness;
+    type MaxLootEntries = ConstU32<4>;
+    type MaxLootRolls = ConstU32<100>;
 }
 
 frame_support::construct_runtime!(
     pub enum Test
     {
         System: frame_system,
+        Balances: pallet_balances,
+        Asset: sugarfunge_asset,
         Exgine: sugarfunge_exgine,
     }
 );
 
-// Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-    frame_system::GenesisConfig::<Test>::default()
+    let mut t = frame_system::GenesisConfig::<Test>::default()
         .build_storage()
-        .unwrap()
-        .into()
+        .unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(1, 1000000 * DOLLARS), (2, 1000000 * DOLLARS)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+    t.into()
 }
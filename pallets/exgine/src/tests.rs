@@ -1,24 +1,399 @@
-use crate::{mock::*, Error};
-use frame_support::{assert_noop, assert_ok};
+use crate::{mock::*, Error, LootEntry, RecipeAsset};
+use frame_support::{assert_noop, assert_ok, bounded_vec, traits::Hooks};
+
+fn last_event() -> RuntimeEvent {
+    frame_system::Pallet::<Test>::events()
+        .pop()
+        .expect("Event expected")
+        .event
+}
+
+pub fn before_crafting() {
+    System::set_block_number(1);
+
+    // Class 1000 holds materials, class 2000 holds crafted items owned by account 1
+    assert_ok!(Asset::do_create_class(&1, &1, 1000, bounded_vec![0]));
+    assert_ok!(Asset::do_create_class(&1, &1, 2000, bounded_vec![0]));
+
+    assert_ok!(Asset::do_mint(&1, &2, 1000, 1, 10));
+    assert_ok!(Asset::do_mint(&1, &2, 1000, 2, 1));
+
+    assert_ok!(Exgine::create_recipe(
+        RuntimeOrigin::signed(1),
+        bounded_vec![RecipeAsset {
+            class_id: 1000,
+            asset_id: 1,
+            amount: 2,
+        }],
+        bounded_vec![RecipeAsset {
+            class_id: 1000,
+            asset_id: 2,
+            amount: 1,
+        }],
+        bounded_vec![RecipeAsset {
+            class_id: 2000,
+            asset_id: 1,
+            amount: 1,
+        }],
+        5,
+        Some(3),
+        None,
+    ));
+}
+
+#[test]
+fn before_crafting_works() {
+    new_test_ext().execute_with(|| {
+        before_crafting();
+
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Exgine(crate::Event::RecipeCreated {
+                recipe_id: 0,
+                owner: 1,
+            }),
+        );
+    })
+}
+
+#[test]
+fn create_recipe_requires_output_class_owner() {
+    new_test_ext().execute_with(|| {
+        before_crafting();
+
+        assert_noop!(
+            Exgine::create_recipe(
+                RuntimeOrigin::signed(2),
+                bounded_vec![],
+                bounded_vec![],
+                bounded_vec![RecipeAsset {
+                    class_id: 2000,
+                    asset_id: 2,
+                    amount: 1,
+                }],
+                0,
+                None,
+                None,
+            ),
+            Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            Exgine::create_recipe(
+                RuntimeOrigin::signed(1),
+                bounded_vec![],
+                bounded_vec![],
+                bounded_vec![],
+                0,
+                None,
+                None,
+            ),
+            Error::<Test>::InvalidRecipe
+        );
+    })
+}
+
+#[test]
+fn craft_burns_inputs_and_mints_outputs() {
+    new_test_ext().execute_with(|| {
+        before_crafting();
+
+        assert_ok!(Exgine::craft(RuntimeOrigin::signed(2), 0, 2));
+
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Exgine(crate::Event::Crafted {
+                recipe_id: 0,
+                who: 2,
+                times: 2,
+            }),
+        );
+
+        assert_eq!(Asset::balance_of(&2, 1000, 1), 6);
+        // Catalyst is not consumed
+        assert_eq!(Asset::balance_of(&2, 1000, 2), 1);
+        assert_eq!(Asset::balance_of(&2, 2000, 1), 2);
+        assert_eq!(Exgine::crafts(0, 2).crafts, 2);
+    })
+}
+
+#[test]
+fn craft_enforces_cooldown_and_limit() {
+    new_test_ext().execute_with(|| {
+        before_crafting();
+
+        assert_ok!(Exgine::craft(RuntimeOrigin::signed(2), 0, 1));
+        assert_noop!(
+            Exgine::craft(RuntimeOrigin::signed(2), 0, 1),
+            Error::<Test>::CooldownActive
+        );
+
+        System::set_block_number(6);
+        assert_noop!(
+            Exgine::craft(RuntimeOrigin::signed(2), 0, 3),
+            Error::<Test>::CraftLimitReached
+        );
+        assert_ok!(Exgine::craft(RuntimeOrigin::signed(2), 0, 2));
+
+        System::set_block_number(11);
+        assert_noop!(
+            Exgine::craft(RuntimeOrigin::signed(2), 0, 1),
+            Error::<Test>::CraftLimitReached
+        );
+    })
+}
+
+#[test]
+fn craft_fails() {
+    new_test_ext().execute_with(|| {
+        before_crafting();
+
+        assert_noop!(
+            Exgine::craft(RuntimeOrigin::signed(2), 1, 1),
+            Error::<Test>::InvalidRecipe
+        );
+        assert_noop!(
+            Exgine::craft(RuntimeOrigin::signed(2), 0, 0),
+            Error::<Test>::InvalidTimes
+        );
+        // Account 1 has no catalyst
+        assert_noop!(
+            Exgine::craft(RuntimeOrigin::signed(1), 0, 1),
+            Error::<Test>::MissingCatalyst
+        );
+        // Not enough inputs for 3 crafts leaves every balance untouched
+        assert_ok!(Asset::do_burn(&2, &2, 1000, 1, 5));
+        assert_noop!(
+            Exgine::craft(RuntimeOrigin::signed(2), 0, 3),
+            Error::<Test>::InsufficientInput
+        );
+        assert_eq!(Asset::balance_of(&2, 1000, 1), 5);
+        assert_eq!(Asset::balance_of(&2, 2000, 1), 0);
+    })
+}
+
+#[test]
+fn remove_recipe_works() {
+    new_test_ext().execute_with(|| {
+        before_crafting();
+
+        assert_noop!(
+            Exgine::remove_recipe(RuntimeOrigin::signed(2), 0),
+            Error::<Test>::InvalidRecipeOwner
+        );
+        assert_ok!(Exgine::remove_recipe(RuntimeOrigin::signed(1), 0));
+        assert_noop!(
+            Exgine::craft(RuntimeOrigin::signed(2), 0, 1),
+            Error::<Test>::InvalidRecipe
+        );
+    })
+}
+
+pub fn before_loot() {
+    before_crafting();
+
+    // Common item 2000/10 or rare item 2000/11, opened from pack 2000/100
+    assert_ok!(Exgine::create_loot_table(
+        RuntimeOrigin::signed(1),
+        bounded_vec![
+            LootEntry {
+                class_id: 2000,
+                asset_id: 10,
+                amount: 1,
+                weight: 9,
+            },
+            LootEntry {
+                class_id: 2000,
+                asset_id: 11,
+                amount: 1,
+                weight: 1,
+            },
+        ],
+    ));
+    assert_ok!(Exgine::set_pack(RuntimeOrigin::signed(1), 2000, 100, 0, 5));
+    assert_ok!(Asset::do_mint(&1, &2, 2000, 100, 4));
+}
+
+fn next_block() {
+    System::set_block_number(System::block_number() + 1);
+    Exgine::on_initialize(System::block_number());
+}
+
+fn open_packs() -> (u128, u128) {
+    before_loot();
+    assert_ok!(Exgine::open_pack(RuntimeOrigin::signed(2), 2000, 100, 2));
+    next_block();
+    assert_ok!(Exgine::reveal_loot(RuntimeOrigin::signed(2), 0));
+    (
+        Asset::balance_of(&2, 2000, 10),
+        Asset::balance_of(&2, 2000, 11),
+    )
+}
+
+#[test]
+fn create_loot_table_fails() {
+    new_test_ext().execute_with(|| {
+        before_crafting();
+
+        assert_noop!(
+            Exgine::create_loot_table(RuntimeOrigin::signed(1), bounded_vec![]),
+            Error::<Test>::InvalidLootTable
+        );
+        assert_noop!(
+            Exgine::create_loot_table(
+                RuntimeOrigin::signed(1),
+                bounded_vec![LootEntry {
+                    class_id: 2000,
+                    asset_id: 10,
+                    amount: 1,
+                    weight: 0,
+                }],
+            ),
+            Error::<Test>::InvalidWeight
+        );
+        assert_noop!(
+            Exgine::create_loot_table(
+                RuntimeOrigin::signed(2),
+                bounded_vec![LootEntry {
+                    class_id: 2000,
+                    asset_id: 10,
+                    amount: 1,
+                    weight: 1,
+                }],
+            ),
+            Error::<Test>::NoPermission
+        );
+    })
+}
 
 #[test]
-fn it_works_for_default_value() {
+fn open_pack_burns_pack_and_rolls_loot() {
     new_test_ext().execute_with(|| {
-        // Dispatch a signed extrinsic.
-        assert_ok!(Exgine::do_something(RuntimeOrigin::signed(1), 42));
-        // Read pallet storage and assert an expected result.
-        assert_eq!(Exgine::something(), Some(42));
-        // Assert that the correct event was deposited
-    });
+        let (common, rare) = open_packs();
+
+        assert_eq!(Asset::balance_of(&2, 2000, 100), 2);
+        assert_eq!(common + rare, 10);
+        assert_eq!(Exgine::pending_rolls(2, 0), None);
+        assert_eq!(Exgine::roll_seeds(2), None);
+    })
+}
+
+#[test]
+fn loot_revealed_from_a_later_block() {
+    new_test_ext().execute_with(|| {
+        before_loot();
+
+        assert_ok!(Exgine::open_pack(RuntimeOrigin::signed(2), 2000, 100, 1));
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Exgine(crate::Event::PackOpened {
+                who: 2,
+                class_id: 2000,
+                asset_id: 100,
+                amount: 1,
+            }),
+        );
+        System::assert_has_event(RuntimeEvent::Exgine(crate::Event::LootCommitted {
+            roll_id: 0,
+            table_id: 0,
+            who: 2,
+            rolls: 5,
+            reveal_at: 2,
+        }));
+        assert_eq!(Asset::balance_of(&2, 2000, 100), 3);
+
+        // The pack is burned but nothing is rolled in the commit block
+        assert_noop!(
+            Exgine::reveal_loot(RuntimeOrigin::signed(2), 0),
+            Error::<Test>::RollNotReady
+        );
+        assert_eq!(
+            Asset::balance_of(&2, 2000, 10) + Asset::balance_of(&2, 2000, 11),
+            0
+        );
+
+        next_block();
+        assert_noop!(
+            Exgine::reveal_loot(RuntimeOrigin::signed(3), 0),
+            Error::<Test>::InvalidRoll
+        );
+
+        // Revealing later still rolls with the seed of the block after the commit
+        next_block();
+        assert!(Exgine::roll_seeds(2).is_some());
+        assert_ok!(Exgine::reveal_loot(RuntimeOrigin::signed(2), 0));
+        assert_eq!(
+            Asset::balance_of(&2, 2000, 10) + Asset::balance_of(&2, 2000, 11),
+            5
+        );
+        assert_eq!(Exgine::roll_seeds(2), None);
+        assert_noop!(
+            Exgine::reveal_loot(RuntimeOrigin::signed(2), 0),
+            Error::<Test>::InvalidRoll
+        );
+    })
 }
 
 #[test]
-fn correct_error_for_none_value() {
+fn open_pack_is_reproducible() {
+    let first = new_test_ext().execute_with(open_packs);
+    let second = new_test_ext().execute_with(open_packs);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn open_pack_fails() {
     new_test_ext().execute_with(|| {
-        // Ensure the expected error is thrown when no value is present.
+        before_loot();
+
         assert_noop!(
-            Exgine::cause_error(RuntimeOrigin::signed(1)),
-            Error::<Test>::NoneValue
+            Exgine::open_pack(RuntimeOrigin::signed(2), 2000, 101, 1),
+            Error::<Test>::InvalidPack
+        );
+        assert_noop!(
+            Exgine::open_pack(RuntimeOrigin::signed(2), 2000, 100, 0),
+            Error::<Test>::InvalidAmount
+        );
+        assert_noop!(
+            Exgine::open_pack(RuntimeOrigin::signed(2), 2000, 100, 21),
+            Error::<Test>::TooManyRolls
+        );
+        assert_noop!(
+            Exgine::open_pack(RuntimeOrigin::signed(2), 2000, 100, 5),
+            sugarfunge_asset::Error::<Test>::NumOverflow
+        );
+        assert_noop!(
+            Exgine::set_pack(RuntimeOrigin::signed(2), 2000, 100, 0, 1),
+            Error::<Test>::NoPermission
+        );
+    })
+}
+
+#[test]
+fn craft_rolls_recipe_loot() {
+    new_test_ext().execute_with(|| {
+        before_loot();
+
+        assert_ok!(Exgine::create_recipe(
+            RuntimeOrigin::signed(1),
+            bounded_vec![RecipeAsset {
+                class_id: 1000,
+                asset_id: 1,
+                amount: 1,
+            }],
+            bounded_vec![],
+            bounded_vec![],
+            0,
+            None,
+            Some(0),
+        ));
+        assert_ok!(Exgine::craft(RuntimeOrigin::signed(2), 1, 3));
+        next_block();
+        assert_ok!(Exgine::reveal_loot(RuntimeOrigin::signed(2), 0));
+
+        assert_eq!(Asset::balance_of(&2, 1000, 1), 7);
+        assert_eq!(
+            Asset::balance_of(&2, 2000, 10) + Asset::balance_of(&2, 2000, 11),
+            3
         );
-    });
+    })
 }
@@ -0,0 +1,503 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+    dispatch::{DispatchInfo, DispatchResult, PostDispatchInfo},
+    ensure,
+    traits::Currency,
+};
+use pallet_transaction_payment::{ChargeTransactionPayment, OnChargeTransaction};
+use scale_info::TypeInfo;
+use sp_runtime::{
+    helpers_128bit::multiply_by_rational_with_rounding,
+    traits::{
+        DispatchInfoOf, Dispatchable, PostDispatchInfoOf, SignedExtension, UniqueSaturatedInto,
+        Zero,
+    },
+    transaction_validity::{
+        InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+    },
+    DispatchError, FixedPointNumber, FixedU128, Rounding, RuntimeDebug,
+};
+use sugarfunge_asset::HoldReason;
+use sugarfunge_primitives::Balance;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Hold reason of asset fees withdrawn before dispatch
+pub const FEE_HOLD: HoldReason = *b"sug/fees";
+
+type NativeBalanceOf<T> = <<T as pallet_transaction_payment::Config>::OnChargeTransaction as OnChargeTransaction<T>>::Balance;
+
+type LiquidityInfoOf<T> = <<T as pallet_transaction_payment::Config>::OnChargeTransaction as OnChargeTransaction<T>>::LiquidityInfo;
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum FeeRate<MarketId> {
+    /// Fee asset units charged per native unit
+    Fixed(FixedU128),
+    /// Price quoted from the native and fee asset reserves of a market vault
+    Market(MarketId),
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct FeeAsset<AccountId, MarketId> {
+    /// How native fees convert to the fee asset
+    pub rate: FeeRate<MarketId>,
+    /// Account receiving the fees paid in the asset and paying the native fees for them
+    pub sink: AccountId,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+    use frame_system::pallet_prelude::*;
+
+    #[pallet::config]
+    pub trait Config:
+        frame_system::Config + pallet_transaction_payment::Config + sugarfunge_market::Config
+    {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Lowest fixed rate of fee asset units per native unit
+        #[pallet::constant]
+        type MinFeeRate: Get<FixedU128>;
+    }
+
+    pub type FeeAssetOf<T> = FeeAsset<
+        <T as frame_system::Config>::AccountId,
+        <T as sugarfunge_market::Config>::MarketId,
+    >;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// Assets accepted for transaction fees
+    #[pallet::storage]
+    #[pallet::getter(fn fee_assets)]
+    pub(super) type FeeAssets<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::ClassId,
+        Blake2_128Concat,
+        T::AssetId,
+        FeeAssetOf<T>,
+    >;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        FeeAssetSet {
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            rate: FeeRate<T::MarketId>,
+            sink: T::AccountId,
+        },
+        FeeAssetRemoved {
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        },
+        AssetTxFeePaid {
+            who: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            actual_fee: Balance,
+            tip: Balance,
+        },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        NoPermission,
+        InvalidRate,
+        InvalidMarket,
+        InvalidFeeAsset,
+        InsufficientLiquidity,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Accept the asset for transaction fees, paid to `sink`
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn set_fee_asset(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            rate: FeeRate<T::MarketId>,
+            sink: T::AccountId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_set_fee_asset(&who, class_id, asset_id, rate, sink)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn remove_fee_asset(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_remove_fee_asset(&who, class_id, asset_id)?;
+
+            Ok(().into())
+        }
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    pub fn do_set_fee_asset(
+        who: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+        rate: FeeRate<T::MarketId>,
+        sink: T::AccountId,
+    ) -> DispatchResult {
+        ensure!(
+            sugarfunge_asset::Pallet::<T>::account_is_owner(who, class_id),
+            Error::<T>::NoPermission
+        );
+
+        match rate {
+            FeeRate::Fixed(rate) => ensure!(
+                rate.into_inner() > 0 && rate >= T::MinFeeRate::get(),
+                Error::<T>::InvalidRate
+            ),
+            FeeRate::Market(market_id) => ensure!(
+                sugarfunge_market::Pallet::<T>::get_vault(market_id).is_some(),
+                Error::<T>::InvalidMarket
+            ),
+        }
+
+        FeeAssets::<T>::insert(
+            class_id,
+            asset_id,
+            FeeAsset {
+                rate,
+                sink: sink.clone(),
+            },
+        );
+
+        Self::deposit_event(Event::FeeAssetSet {
+            class_id,
+            asset_id,
+            rate,
+            sink,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_remove_fee_asset(
+        who: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+    ) -> DispatchResult {
+        ensure!(
+            sugarfunge_asset::Pallet::<T>::account_is_owner(who, class_id),
+            Error::<T>::NoPermission
+        );
+        ensure!(
+            FeeAssets::<T>::contains_key(class_id, asset_id),
+            Error::<T>::InvalidFeeAsset
+        );
+
+        FeeAssets::<T>::remove(class_id, asset_id);
+
+        Self::deposit_event(Event::FeeAssetRemoved { class_id, asset_id });
+
+        Ok(())
+    }
+
+    /// Amount of fee asset charged for a native `fee`
+    pub fn quote_fee(
+        fee_asset: &FeeAssetOf<T>,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+        fee: Balance,
+    ) -> Result<Balance, DispatchError> {
+        if fee.is_zero() {
+            return Ok(Zero::zero());
+        }
+
+        match fee_asset.rate {
+            FeeRate::Fixed(rate) => Ok(rate.saturating_mul_int(fee)),
+            FeeRate::Market(market_id) => {
+                let vault = sugarfunge_market::Pallet::<T>::get_vault(market_id)
+                    .ok_or(Error::<T>::InvalidMarket)?;
+                let reserve_in =
+                    sugarfunge_asset::Pallet::<T>::balance_of(&vault, class_id, asset_id);
+                let reserve_out: Balance =
+                    <T as sugarfunge_asset::Config>::Currency::free_balance(&vault)
+                        .unique_saturated_into();
+                ensure!(reserve_out > fee, Error::<T>::InsufficientLiquidity);
+
+                sugarfunge_market::Pallet::<T>::get_buy_price(fee, reserve_in, reserve_out)
+            }
+        }
+    }
+
+    /// Fee asset `who` pays for a native `fee` and the sink paying the native fee for it
+    fn quote_asset_fee(
+        who: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+        fee: Balance,
+    ) -> Result<(Balance, T::AccountId), TransactionValidityError> {
+        let fee_asset =
+            FeeAssets::<T>::get(class_id, asset_id).ok_or(InvalidTransaction::Payment)?;
+        // Paying the sink is not a sale, so assets whose royalty forbids gifts cannot pay fees
+        sugarfunge_asset::Pallet::<T>::ensure_gift_allowed_by(who, class_id, asset_id)
+            .map_err(|_| InvalidTransaction::Payment)?;
+        let asset_fee = Self::quote_fee(&fee_asset, class_id, asset_id, fee)
+            .map_err(|_| InvalidTransaction::Payment)?;
+
+        // A fee rounding down to nothing would let the sink pay for free transactions
+        ensure!(
+            fee.is_zero() || !asset_fee.is_zero(),
+            InvalidTransaction::Payment
+        );
+        ensure!(
+            !sugarfunge_asset::Pallet::<T>::is_frozen(who, class_id, asset_id)
+                && sugarfunge_asset::Pallet::<T>::spendable_balance_of(who, class_id, asset_id)
+                    >= asset_fee,
+            InvalidTransaction::Payment
+        );
+
+        Ok((asset_fee, fee_asset.sink))
+    }
+
+    /// Pay the part of the held fee matching the actual fee to the sink and release the rest
+    fn correct_and_deposit_fee(
+        who: &T::AccountId,
+        payment: &AssetPayment<T>,
+        actual_fee: Balance,
+        tip: Balance,
+    ) -> Result<(), TransactionValidityError> {
+        let actual_asset_fee = multiply_by_rational_with_rounding(
+            payment.asset_fee,
+            actual_fee.min(payment.fee),
+            payment.fee,
+            Rounding::Up,
+        )
+        .unwrap_or(payment.asset_fee);
+
+        sugarfunge_asset::Pallet::<T>::do_transfer_on_hold(
+            FEE_HOLD,
+            who,
+            &payment.sink,
+            payment.class_id,
+            payment.asset_id,
+            actual_asset_fee,
+        )
+        .map_err(|_| InvalidTransaction::Payment)?;
+
+        let refund = payment.asset_fee.saturating_sub(actual_asset_fee);
+        if !refund.is_zero() {
+            sugarfunge_asset::Pallet::<T>::do_release(
+                FEE_HOLD,
+                who,
+                payment.class_id,
+                payment.asset_id,
+                refund,
+            )
+            .map_err(|_| InvalidTransaction::Payment)?;
+        }
+
+        Self::deposit_event(Event::AssetTxFeePaid {
+            who: who.clone(),
+            class_id: payment.class_id,
+            asset_id: payment.asset_id,
+            actual_fee: actual_asset_fee,
+            tip,
+        });
+
+        Ok(())
+    }
+}
+
+/// Fee asset held before dispatch
+pub struct AssetPayment<T: Config> {
+    class_id: T::ClassId,
+    asset_id: T::AssetId,
+    /// Native fee the held amount was quoted for
+    fee: Balance,
+    asset_fee: Balance,
+    sink: T::AccountId,
+    /// Native fee withdrawn from the sink
+    liquidity: LiquidityInfoOf<T>,
+}
+
+pub enum InitialPayment<T: Config> {
+    Nothing,
+    Native(<ChargeTransactionPayment<T> as SignedExtension>::Pre),
+    Asset(AssetPayment<T>),
+}
+
+/// Charge the transaction fee in native currency, or in a fee asset when one is given
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ChargeAssetTxPayment<T: Config> {
+    #[codec(compact)]
+    tip: NativeBalanceOf<T>,
+    asset: Option<(T::ClassId, T::AssetId)>,
+}
+
+impl<T: Config> ChargeAssetTxPayment<T> {
+    pub fn from(tip: NativeBalanceOf<T>, asset: Option<(T::ClassId, T::AssetId)>) -> Self {
+        Self { tip, asset }
+    }
+}
+
+impl<T: Config> sp_std::fmt::Debug for ChargeAssetTxPayment<T> {
+    #[cfg(feature = "std")]
+    fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+        write!(
+            f,
+            "ChargeAssetTxPayment<{:?}, {:?}>",
+            self.tip,
+            self.asset.encode()
+        )
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+        Ok(())
+    }
+}
+
+impl<T: Config> SignedExtension for ChargeAssetTxPayment<T>
+where
+    <T as frame_system::Config>::RuntimeCall:
+        Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+    NativeBalanceOf<T>: Send + Sync + From<u64>,
+{
+    const IDENTIFIER: &'static str = "ChargeAssetTxPayment";
+    type AccountId = T::AccountId;
+    type Call = <T as frame_system::Config>::RuntimeCall;
+    type AdditionalSigned = ();
+    type Pre = (NativeBalanceOf<T>, T::AccountId, InitialPayment<T>);
+
+    fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+        Ok(())
+    }
+
+    fn validate(
+        &self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        len: usize,
+    ) -> TransactionValidity {
+        let Some((class_id, asset_id)) = self.asset else {
+            return ChargeTransactionPayment::<T>::from(self.tip).validate(who, call, info, len);
+        };
+
+        let fee = pallet_transaction_payment::Pallet::<T>::compute_fee(len as u32, info, self.tip);
+        let (_, sink) =
+            Pallet::<T>::quote_asset_fee(who, class_id, asset_id, fee.unique_saturated_into())?;
+        // The sink must afford the native fee it pays in exchange for the asset
+        <T as pallet_transaction_payment::Config>::OnChargeTransaction::withdraw_fee(
+            &sink, call, info, fee, self.tip,
+        )?;
+
+        Ok(ValidTransaction {
+            priority: ChargeTransactionPayment::<T>::get_priority(info, len, self.tip, fee),
+            ..Default::default()
+        })
+    }
+
+    fn pre_dispatch(
+        self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        let Some((class_id, asset_id)) = self.asset else {
+            let pre =
+                ChargeTransactionPayment::<T>::from(self.tip).pre_dispatch(who, call, info, len)?;
+            return Ok((self.tip, who.clone(), InitialPayment::Native(pre)));
+        };
+
+        let native_fee =
+            pallet_transaction_payment::Pallet::<T>::compute_fee(len as u32, info, self.tip);
+        let fee: Balance = native_fee.unique_saturated_into();
+        let (asset_fee, sink) = Pallet::<T>::quote_asset_fee(who, class_id, asset_id, fee)?;
+        if fee.is_zero() {
+            return Ok((self.tip, who.clone(), InitialPayment::Nothing));
+        }
+
+        // The sink pays the native fee and is paid back in the fee asset
+        let liquidity =
+            <T as pallet_transaction_payment::Config>::OnChargeTransaction::withdraw_fee(
+                &sink, call, info, native_fee, self.tip,
+            )?;
+        sugarfunge_asset::Pallet::<T>::do_hold(FEE_HOLD, who, class_id, asset_id, asset_fee)
+            .map_err(|_| InvalidTransaction::Payment)?;
+
+        let payment = InitialPayment::Asset(AssetPayment {
+            class_id,
+            asset_id,
+            fee,
+            asset_fee,
+            sink,
+            liquidity,
+        });
+
+        Ok((self.tip, who.clone(), payment))
+    }
+
+    fn post_dispatch(
+        maybe_pre: Option<Self::Pre>,
+        info: &DispatchInfoOf<Self::Call>,
+        post_info: &PostDispatchInfoOf<Self::Call>,
+        len: usize,
+        result: &DispatchResult,
+    ) -> Result<(), TransactionValidityError> {
+        let Some((tip, who, initial_payment)) = maybe_pre else {
+            return Ok(());
+        };
+
+        match initial_payment {
+            InitialPayment::Native(pre) => ChargeTransactionPayment::<T>::post_dispatch(
+                Some(pre),
+                info,
+                post_info,
+                len,
+                result,
+            ),
+            InitialPayment::Asset(payment) => {
+                let actual_fee = pallet_transaction_payment::Pallet::<T>::compute_actual_fee(
+                    len as u32, info, post_info, tip,
+                );
+                Pallet::<T>::correct_and_deposit_fee(
+                    &who,
+                    &payment,
+                    actual_fee.unique_saturated_into(),
+                    tip.unique_saturated_into(),
+                )?;
+                <T as pallet_transaction_payment::Config>::OnChargeTransaction::correct_and_deposit_fee(
+                    &payment.sink,
+                    info,
+                    post_info,
+                    actual_fee,
+                    tip,
+                    payment.liquidity,
+                )
+            }
+            InitialPayment::Nothing => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use crate::{mock::*, ChargeAssetTxPayment, Error, FeeRate, FEE_HOLD};
+use frame_support::{
+    assert_noop, assert_ok, bounded_vec,
+    dispatch::{DispatchInfo, Pays, PostDispatchInfo},
+    weights::Weight,
+};
+use sp_runtime::{
+    traits::SignedExtension,
+    transaction_validity::{InvalidTransaction, TransactionValidityError},
+    FixedU128,
+};
+use sugarfunge_asset::{GiftPolicy, Royalty};
+
+fn last_event() -> RuntimeEvent {
+    frame_system::Pallet::<Test>::events()
+        .pop()
+        .expect("Event expected")
+        .event
+}
+
+fn call() -> RuntimeCall {
+    RuntimeCall::System(frame_system::Call::remark { remark: vec![] })
+}
+
+fn info() -> DispatchInfo {
+    DispatchInfo {
+        weight: Weight::from_parts(1_000, 0),
+        ..Default::default()
+    }
+}
+
+fn post_info() -> PostDispatchInfo {
+    PostDispatchInfo {
+        actual_weight: Some(Weight::from_parts(500, 0)),
+        pays_fee: Pays::Yes,
+    }
+}
+
+const LEN: usize = 10;
+
+pub fn before_fees() {
+    System::set_block_number(1);
+
+    // Account 3 holds no native balance, only 1000/1 game currency
+    assert_ok!(Asset::do_create_class(&1, &1, 1000, bounded_vec![0]));
+    assert_ok!(Asset::do_mint(&1, &3, 1000, 1, 1_000_000_000_000));
+
+    assert_ok!(Fee::set_fee_asset(
+        RuntimeOrigin::signed(1),
+        1000,
+        1,
+        FeeRate::Fixed(FixedU128::from_u32(2)),
+        4
+    ));
+    // The sink pays the native fees of the transactions paid to it in 1000/1
+    assert_ok!(Balances::transfer_keep_alive(
+        RuntimeOrigin::signed(1),
+        4,
+        DOLLARS
+    ));
+}
+
+#[test]
+fn set_fee_asset_fails() {
+    new_test_ext().execute_with(|| {
+        before_fees();
+
+        assert_noop!(
+            Fee::set_fee_asset(
+                RuntimeOrigin::signed(3),
+                1000,
+                1,
+                FeeRate::Fixed(FixedU128::from_u32(1)),
+                3
+            ),
+            Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            Fee::set_fee_asset(
+                RuntimeOrigin::signed(1),
+                1000,
+                1,
+                FeeRate::Fixed(FixedU128::from_u32(0)),
+                4
+            ),
+            Error::<Test>::InvalidRate
+        );
+        assert_noop!(
+            Fee::set_fee_asset(
+                RuntimeOrigin::signed(1),
+                1000,
+                1,
+                FeeRate::Fixed(FixedU128::from_rational(1, 1_000_000_000)),
+                4
+            ),
+            Error::<Test>::InvalidRate
+        );
+        assert_noop!(
+            Fee::set_fee_asset(RuntimeOrigin::signed(1), 1000, 1, FeeRate::Market(0), 4),
+            Error::<Test>::InvalidMarket
+        );
+        assert_noop!(
+            Fee::remove_fee_asset(RuntimeOrigin::signed(1), 1000, 2),
+            Error::<Test>::InvalidFeeAsset
+        );
+    })
+}
+
+#[test]
+fn fees_paid_in_asset() {
+    new_test_ext().execute_with(|| {
+        before_fees();
+
+        let fee = TransactionPayment::compute_fee(LEN as u32, &info(), 0);
+        let actual_fee =
+            TransactionPayment::compute_actual_fee(LEN as u32, &info(), &post_info(), 0);
+        let balance = Asset::balance_of(&3, 1000, 1);
+        let native = Balances::free_balance(4);
+        let issuance = Balances::total_issuance();
+
+        let ext = ChargeAssetTxPayment::<Test>::from(0, Some((1000, 1)));
+        assert_ok!(ext.validate(&3, &call(), &info(), LEN));
+        let pre = ext.pre_dispatch(&3, &call(), &info(), LEN).unwrap();
+
+        // The whole fee is held before dispatch and the sink pays it in native currency
+        assert_eq!(Asset::balance_on_hold(FEE_HOLD, &3, 1000, 1), 2 * fee);
+        assert_eq!(Asset::spendable_balance_of(&3, 1000, 1), balance - 2 * fee);
+        assert_eq!(Balances::free_balance(4), native - fee);
+
+        assert_ok!(ChargeAssetTxPayment::<Test>::post_dispatch(
+            Some(pre),
+            &info(),
+            &post_info(),
+            LEN,
+            &Ok(())
+        ));
+
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Fee(crate::Event::AssetTxFeePaid {
+                who: 3,
+                class_id: 1000,
+                asset_id: 1,
+                actual_fee: 2 * actual_fee,
+                tip: 0,
+            })
+        );
+        assert_eq!(Asset::balance_on_hold(FEE_HOLD, &3, 1000, 1), 0);
+        assert_eq!(Asset::balance_of(&3, 1000, 1), balance - 2 * actual_fee);
+        assert_eq!(Asset::balance_of(&4, 1000, 1), 2 * actual_fee);
+        assert_eq!(Balances::free_balance(4), native - actual_fee);
+        assert_eq!(Balances::total_issuance(), issuance - actual_fee);
+    })
+}
+
+#[test]
+fn asset_fees_rejected_unless_native_fee_paid() {
+    new_test_ext().execute_with(|| {
+        before_fees();
+
+        // A sink without native currency cannot pay for the transaction
+        assert_ok!(Fee::set_fee_asset(
+            RuntimeOrigin::signed(1),
+            1000,
+            1,
+            FeeRate::Fixed(FixedU128::from_u32(2)),
+            5
+        ));
+        let ext = ChargeAssetTxPayment::<Test>::from(0, Some((1000, 1)));
+        assert_eq!(
+            ext.validate(&3, &call(), &info(), LEN),
+            Err(TransactionValidityError::Invalid(
+                InvalidTransaction::Payment
+            ))
+        );
+        assert!(ext.pre_dispatch(&3, &call(), &info(), LEN).is_err());
+        assert_eq!(Asset::balance_on_hold(FEE_HOLD, &3, 1000, 1), 0);
+
+        // Fees rounding down to no asset at all are not paid for by the sink
+        assert_ok!(Fee::set_fee_asset(
+            RuntimeOrigin::signed(1),
+            1000,
+            1,
+            FeeRate::Fixed(FixedU128::from_rational(1, 1_000_000)),
+            4
+        ));
+        let native = Balances::free_balance(4);
+        let ext = ChargeAssetTxPayment::<Test>::from(0, Some((1000, 1)));
+        assert_eq!(
+            ext.validate(&3, &call(), &info(), LEN),
+            Err(TransactionValidityError::Invalid(
+                InvalidTransaction::Payment
+            ))
+        );
+        assert!(ext.pre_dispatch(&3, &call(), &info(), LEN).is_err());
+        assert_eq!(Balances::free_balance(4), native);
+    })
+}
+
+#[test]
+fn fees_quoted_from_market_reserves() {
+    new_test_ext().execute_with(|| {
+        before_fees();
+
+        assert_ok!(Market::do_create_market(&1, 0));
+        let vault = Market::get_vault(0).unwrap();
+        assert_ok!(Balances::transfer_keep_alive(
+            RuntimeOrigin::signed(1),
+            vault,
+            100 * DOLLARS
+        ));
+        assert_ok!(Asset::do_mint(&1, &vault, 1000, 1, 1_000_000 * DOLLARS));
+
+        assert_ok!(Fee::set_fee_asset(
+            RuntimeOrigin::signed(1),
+            1000,
+            1,
+            FeeRate::Market(0),
+            4
+        ));
+
+        let fee = TransactionPayment::compute_fee(LEN as u32, &info(), 0);
+        let asset_fee = Market::get_buy_price(fee, 1_000_000 * DOLLARS, 100 * DOLLARS).unwrap();
+
+        let ext = ChargeAssetTxPayment::<Test>::from(0, Some((1000, 1)));
+        assert_ok!(ext.pre_dispatch(&3, &call(), &info(), LEN));
+        assert_eq!(Asset::balance_on_hold(FEE_HOLD, &3, 1000, 1), asset_fee);
+    })
+}
+
+#[test]
+fn fees_rejected_without_fee_asset() {
+    new_test_ext().execute_with(|| {
+        before_fees();
+
+        assert_ok!(Asset::do_mint(&1, &3, 1000, 2, 1_000_000_000_000));
+        let ext = ChargeAssetTxPayment::<Test>::from(0, Some((1000, 2)));
+        assert_eq!(
+            ext.validate(&3, &call(), &info(), LEN),
+            Err(TransactionValidityError::Invalid(
+                InvalidTransaction::Payment
+            ))
+        );
+
+        // Held assets cannot pay fees
+        let balance = Asset::balance_of(&3, 1000, 1);
+        assert_ok!(Asset::do_hold(*b"market/0", &3, 1000, 1, balance));
+        let ext = ChargeAssetTxPayment::<Test>::from(0, Some((1000, 1)));
+        assert_eq!(
+            ext.validate(&3, &call(), &info(), LEN),
+            Err(TransactionValidityError::Invalid(
+                InvalidTransaction::Payment
+            ))
+        );
+
+        // Native fees are still charged without a fee asset
+        let ext = ChargeAssetTxPayment::<Test>::from(0, None);
+        assert_eq!(
+            ext.validate(&3, &call(), &info(), LEN),
+            Err(TransactionValidityError::Invalid(
+                InvalidTransaction::Payment
+            ))
+        );
+        let native = Balances::free_balance(2);
+        let ext = ChargeAssetTxPayment::<Test>::from(0, None);
+        assert_ok!(ext.pre_dispatch(&2, &call(), &info(), LEN));
+        assert!(Balances::free_balance(2) < native);
+    })
+}
+
+#[test]
+fn gift_forbidden_assets_cannot_pay_fees() {
+    new_test_ext().execute_with(|| {
+        before_fees();
+
+        // Fees held before the royalty forbids gifts are still paid to the sink
+        let ext = ChargeAssetTxPayment::<Test>::from(0, Some((1000, 1)));
+        let pre = ext.pre_dispatch(&3, &call(), &info(), LEN).unwrap();
+        assert_ok!(Asset::set_royalty(
+            RuntimeOrigin::signed(1),
+            1000,
+            None,
+            Some(Royalty {
+                beneficiary: 1,
+                bps: 250,
+                gifts: GiftPolicy::Forbidden,
+            })
+        ));
+        assert_ok!(ChargeAssetTxPayment::<Test>::post_dispatch(
+            Some(pre),
+            &info(),
+            &post_info(),
+            LEN,
+            &Ok(())
+        ));
+        assert_eq!(Asset::balance_on_hold(FEE_HOLD, &3, 1000, 1), 0);
+        assert!(Asset::balance_of(&4, 1000, 1) > 0);
+
+        // But new fees cannot be paid in the asset
+        let ext = ChargeAssetTxPayment::<Test>::from(0, Some((1000, 1)));
+        assert_eq!(
+            ext.validate(&3, &call(), &info(), LEN),
+            Err(TransactionValidityError::Invalid(
+                InvalidTransaction::Payment
+            ))
+        );
+    })
+}
@@ -9,7 +9,11 @@ use sp_runtime::{
     traits::{AccountIdConversion, AtLeast32BitUnsigned, Zero},
     RuntimeDebug,
 };
-use sp_std::{collections::btree_map::BTreeMap, prelude::*};
+use sp_std::{
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+    prelude::*,
+};
+use sugarfunge_asset::MAX_ROYALTY_BPS;
 use sugarfunge_primitives::{Amount, Balance};
 
 pub use pallet::*;
@@ -166,6 +170,24 @@ pub struct RateBalance<AccountId, ClassId, AssetId> {
     balance: Amount,
 }
 
+/// Royalty paid to the creator of a sold asset out of a payment leg of an exchange
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct RoyaltyPayment<AccountId, ClassId, AssetId> {
+    class_id: ClassId,
+    asset_id: AssetId,
+    rate: AssetRate<AccountId, ClassId, AssetId>,
+    beneficiary: AccountId,
+    amount: Balance,
+}
+
+pub type RoyaltyPayments<T> = Vec<
+    RoyaltyPayment<
+        <T as frame_system::Config>::AccountId,
+        <T as sugarfunge_asset::Config>::ClassId,
+        <T as sugarfunge_asset::Config>::AssetId,
+    >,
+>;
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
@@ -284,6 +306,7 @@ pub mod pallet {
             market_rate_id: T::MarketRateId,
             amount: Balance,
             balances: Vec<RateBalance<T::AccountId, T::ClassId, T::AssetId>>,
+            royalties: Vec<RoyaltyPayment<T::AccountId, T::ClassId, T::AssetId>>,
             success: bool,
         },
     }
@@ -309,6 +332,7 @@ pub mod pallet {
         InvalidTransferBalance,
         InvalidBuyer,
         InvalidArrayLength,
+        InvalidRoyalty,
     }
 
     // Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -739,6 +763,28 @@ impl<T: Config> Pallet<T> {
         Markets::<T>::get(market_id).and_then(|market| Some(market.vault))
     }
 
+    /// Classes of the assets a market rate moves or checks
+    pub fn rate_classes(
+        market_id: T::MarketId,
+        market_rate_id: T::MarketRateId,
+    ) -> Vec<T::ClassId> {
+        MarketRates::<T>::get((market_id, market_rate_id))
+            .map(|rates| Self::classes_of_rates(&rates))
+            .unwrap_or_default()
+    }
+
+    /// Classes of the assets in `rates`, including the reserves priced by market transfers
+    pub fn classes_of_rates(rates: &Rates<T>) -> Vec<T::ClassId> {
+        let mut classes = BTreeSet::new();
+        for rate in rates.iter() {
+            classes.insert(rate.class_id);
+            if let RateAction::MarketTransfer(_, class_id, _) = rate.action {
+                classes.insert(class_id);
+            }
+        }
+        classes.into_iter().collect()
+    }
+
     pub fn balance(
         market: &Market<T::AccountId>,
         class_id: T::ClassId,
@@ -783,7 +829,7 @@ impl<T: Config> Pallet<T> {
         market_id: T::MarketId,
         market_rate_id: T::MarketRateId,
         amount: Balance,
-    ) -> Result<(bool, RateBalances<T>), DispatchError> {
+    ) -> Result<(bool, RateBalances<T>, RoyaltyPayments<T>), DispatchError> {
         ensure!(amount > 0, Error::<T>::InsufficientAmount);
 
         let market = Markets::<T>::get(market_id).ok_or(Error::<T>::InvalidMarket)?;
@@ -794,7 +840,8 @@ impl<T: Config> Pallet<T> {
 
         let mut can_do_exchange = true;
 
-        // RateAction::Has - Prove parties possess non-transferable assets
+        // RateAction::Has - Prove parties possess non-transferable assets, either owned or
+        // held through user rights (e.g. rentals)
 
         for asset_rate in rates.iter() {
             if let RateAction::Has(op, amount) = asset_rate.action {
@@ -803,7 +850,7 @@ impl<T: Config> Pallet<T> {
                     RateAccount::Buyer => buyer,
                     RateAccount::Market => &market.vault,
                 };
-                let balance: i128 = sugarfunge_asset::Pallet::<T>::balance_of(
+                let balance: i128 = sugarfunge_asset::Pallet::<T>::usable_balance_of(
                     target_account,
                     asset_rate.class_id,
                     asset_rate.asset_id,
@@ -987,7 +1034,70 @@ impl<T: Config> Pallet<T> {
             }
         }
 
-        Ok((can_do_exchange, exchange_balances))
+        let royalties = Self::quote_royalties(&rates, total_amount)?;
+
+        Ok((can_do_exchange, exchange_balances, royalties))
+    }
+
+    /// Royalties of the assets transferred to the buyer, taken out of the transfers the buyer
+    /// pays with. Minted assets are primary sales and owe no royalty.
+    fn quote_royalties(
+        rates: &Rates<T>,
+        total_amount: Amount,
+    ) -> Result<RoyaltyPayments<T>, DispatchError> {
+        let mut sold = BTreeSet::new();
+        let mut royalties = Vec::new();
+        for asset_rate in rates.iter() {
+            if matches!(asset_rate.action, RateAction::Transfer(_))
+                && asset_rate.to == RateAccount::Buyer
+                && asset_rate.from != RateAccount::Buyer
+                && sold.insert((asset_rate.class_id, asset_rate.asset_id))
+            {
+                if let Some(royalty) = sugarfunge_asset::Pallet::<T>::royalty_of(
+                    asset_rate.class_id,
+                    asset_rate.asset_id,
+                ) {
+                    royalties.push((asset_rate.class_id, asset_rate.asset_id, royalty));
+                }
+            }
+        }
+
+        let total_bps: u32 = royalties
+            .iter()
+            .map(|(_, _, royalty)| u32::from(royalty.bps))
+            .sum();
+        ensure!(
+            total_bps <= u32::from(MAX_ROYALTY_BPS),
+            Error::<T>::InvalidRoyalty
+        );
+
+        let mut payments = Vec::new();
+        for asset_rate in rates.iter() {
+            if let RateAction::Transfer(amount) = asset_rate.action {
+                if asset_rate.from != RateAccount::Buyer || asset_rate.to == RateAccount::Buyer {
+                    continue;
+                }
+                let price: Balance = amount
+                    .checked_mul(total_amount)
+                    .ok_or(Error::<T>::Overflow)?
+                    .try_into()
+                    .map_err(|_| Error::<T>::Overflow)?;
+                for (class_id, asset_id, royalty) in royalties.iter() {
+                    let amount = royalty.amount_of(price);
+                    if !amount.is_zero() {
+                        payments.push(RoyaltyPayment {
+                            class_id: *class_id,
+                            asset_id: *asset_id,
+                            rate: asset_rate.clone(),
+                            beneficiary: royalty.beneficiary.clone(),
+                            amount,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(payments)
     }
 
     pub fn do_exchange_assets(
@@ -1003,12 +1113,24 @@ impl<T: Config> Pallet<T> {
         ensure!(*buyer != market.owner, Error::<T>::InvalidBuyer);
         ensure!(*buyer != market.vault, Error::<T>::InvalidBuyer);
 
-        let (can_do_exchange, exchange_balances) =
+        let (can_do_exchange, exchange_balances, royalties) =
             Self::do_quote_exchange(buyer, market_id, market_rate_id, amount)?;
 
         if can_do_exchange {
+            // Only assets paying a royalty are sold, other transfers are gifts
+            let sold: BTreeSet<_> = royalties
+                .iter()
+                .map(|payment| (payment.class_id, payment.asset_id))
+                .collect();
             for (asset_rate, amount) in &exchange_balances {
                 let amount: u128 = (*amount).try_into().map_err(|_| Error::<T>::Overflow)?;
+                // The seller receives the payment minus the royalties taken out of it
+                let royalty: Balance = royalties
+                    .iter()
+                    .filter(|payment| payment.rate == *asset_rate)
+                    .map(|payment| payment.amount)
+                    .sum();
+                let amount = amount.checked_sub(royalty).ok_or(Error::<T>::Overflow)?;
                 let from = match &asset_rate.from {
                     RateAccount::Account(account) => account,
                     RateAccount::Buyer => buyer,
@@ -1020,6 +1142,18 @@ impl<T: Config> Pallet<T> {
                     RateAccount::Market => &market.vault,
                 };
                 match asset_rate.action {
+                    RateAction::Transfer(_)
+                        if sold.contains(&(asset_rate.class_id, asset_rate.asset_id)) =>
+                    {
+                        sugarfunge_asset::Pallet::<T>::do_sale_transfer_from(
+                            &market.owner,
+                            from,
+                            to,
+                            asset_rate.class_id,
+                            asset_rate.asset_id,
+                            amount,
+                        )?
+                    }
                     RateAction::Transfer(_) => sugarfunge_asset::Pallet::<T>::do_transfer_from(
                         &market.owner,
                         from,
@@ -1045,6 +1179,17 @@ impl<T: Config> Pallet<T> {
                     _ => (),
                 }
             }
+
+            for payment in royalties.iter() {
+                sugarfunge_asset::Pallet::<T>::do_sale_transfer_from(
+                    &market.owner,
+                    buyer,
+                    &payment.beneficiary,
+                    payment.rate.class_id,
+                    payment.rate.asset_id,
+                    payment.amount,
+                )?;
+            }
         }
 
         let balances = exchange_balances
@@ -1061,6 +1206,7 @@ impl<T: Config> Pallet<T> {
             market_rate_id,
             amount,
             balances,
+            royalties,
             success: can_do_exchange,
         });
 
@@ -1,9 +1,11 @@
 use crate::{
-    mock::*, AmountOp, AssetRate, Error, RateAccount, RateAction, RateBalance, Rates, AMM,
+    mock::*, AmountOp, AssetRate, Error, RateAccount, RateAction, RateBalance, Rates,
+    RoyaltyPayment, AMM,
 };
 use frame_support::{assert_noop, assert_ok};
 use sp_core::bounded_vec;
 use sp_std::prelude::*;
+use sugarfunge_asset::{GiftPolicy, Royalty};
 
 fn last_event() -> RuntimeEvent {
     frame_system::Pallet::<Test>::events()
@@ -228,6 +230,26 @@ fn create_market_rate_works() {
     })
 }
 
+#[test]
+fn rate_classes_list_every_class() {
+    new_test_ext().execute_with(|| {
+        before_market();
+
+        assert_ok!(Market::do_create_market_rate(
+            &1,
+            1000,
+            2,
+            &simple_market_rates()
+        ));
+        assert_eq!(Market::rate_classes(1000, 2), vec![2000, 3000, 4000]);
+        assert_eq!(Market::rate_classes(1000, 3), vec![]);
+
+        let mut rates = swap_market_rates();
+        rates[1].action = RateAction::MarketTransfer(AMM::Constant, 3000, 1);
+        assert_eq!(Market::classes_of_rates(&rates), vec![2000, 3000]);
+    })
+}
+
 #[test]
 fn invalid_market_fails() {
     new_test_ext().execute_with(|| {
@@ -408,7 +430,7 @@ fn quote_exchange_insufficient() {
         assert_ok!(Market::do_deposit(&2, 2000, 100, 4));
 
         let result = Market::do_quote_exchange(&3, 2000, 100, 3);
-        if let Ok((can_do_exchange, balances)) = result {
+        if let Ok((can_do_exchange, balances, _)) = result {
             assert_eq!(can_do_exchange, false);
             assert_eq!(balances.get(&rates[0]), Some(&3));
             assert_eq!(balances.get(&rates[1]), Some(&3));
@@ -457,7 +479,7 @@ fn quote_exchange_sufficient() {
         ));
 
         let result = Market::do_quote_exchange(&3, 2000, 100, 3);
-        if let Ok((can_do_exchange, balances)) = result {
+        if let Ok((can_do_exchange, balances, _)) = result {
             assert_eq!(can_do_exchange, true);
             assert_eq!(balances.get(&rates[0]), Some(&3));
             assert_eq!(balances.get(&rates[1]), Some(&3));
@@ -472,6 +494,37 @@ fn quote_exchange_sufficient() {
     })
 }
 
+#[test]
+fn quote_exchange_has_counts_user_rights() {
+    new_test_ext().execute_with(|| {
+        before_market();
+        assert_ok!(Market::do_create_market(&2, 2000));
+        let rates = simple_market_rates();
+        assert_ok!(Market::do_create_market_rate(&2, 2000, 100, &rates));
+        assert_ok!(Market::do_deposit(&2, 2000, 100, 4));
+
+        // Account 4 owns the required assets and lends them to the buyer
+        assert_ok!(Asset::do_mint(&1, &4, 3000, 1, 5000));
+        assert_ok!(Asset::do_lend(&4, &3, 3000, 1, 5000));
+
+        let result = Market::do_quote_exchange(&3, 2000, 100, 3);
+        if let Ok((_, balances, _)) = result {
+            assert_eq!(balances.get(&rates[2]), Some(&5000));
+        } else {
+            result.unwrap();
+        };
+
+        // The lender no longer satisfies the requirement while the assets are lent out
+        let result = Market::do_quote_exchange(&4, 2000, 100, 3);
+        if let Ok((can_do_exchange, balances, _)) = result {
+            assert_eq!(can_do_exchange, false);
+            assert_eq!(balances.get(&rates[2]), Some(&-5000));
+        } else {
+            result.unwrap();
+        };
+    })
+}
+
 #[test]
 fn exchange_assets_works() {
     new_test_ext().execute_with(|| {
@@ -514,6 +567,7 @@ fn exchange_assets_works() {
             amount,
             balances,
             success,
+            ..
         }) = last_event()
         {
             let get_balance = |rate_idx: usize| {
@@ -578,6 +632,7 @@ fn exchange_assets_fails() {
             amount,
             balances,
             success,
+            ..
         }) = last_event()
         {
             let get_balance = |rate_idx: usize| {
@@ -715,3 +770,169 @@ fn dex_price_works() {
         // };
     })
 }
+
+fn sale_market_rates() -> Rates<Test> {
+    vec![
+        // Market sells 1 asset of class_id: 2000 asset_id: 1
+        AssetRate {
+            class_id: 2000,
+            asset_id: 1,
+            action: RateAction::Transfer(1),
+            from: RateAccount::Market,
+            to: RateAccount::Buyer,
+        },
+        // Buyer pays 100 assets of class_id: 3000 asset_id: 1
+        AssetRate {
+            class_id: 3000,
+            asset_id: 1,
+            action: RateAction::Transfer(100),
+            from: RateAccount::Buyer,
+            to: RateAccount::Market,
+        },
+    ]
+    .try_into()
+    .unwrap()
+}
+
+#[test]
+fn exchange_assets_pays_royalties() {
+    new_test_ext().execute_with(|| {
+        before_market();
+        assert_ok!(Market::do_create_market(&2, 2000));
+        let rates = sale_market_rates();
+        assert_ok!(Market::do_create_market_rate(&2, 2000, 100, &rates));
+        let vault = Market::get_vault(2000).unwrap();
+        assert_ok!(Asset::do_transfer_from(&2, &2, &vault, 2000, 1, 10));
+        assert_ok!(Asset::do_mint(&1, &3, 3000, 1, 1000));
+
+        // Creator account 5 earns 2.5% of secondary sales of class 2000
+        assert_ok!(Asset::set_royalty(
+            RuntimeOrigin::signed(1),
+            2000,
+            None,
+            Some(Royalty {
+                beneficiary: 5,
+                bps: 250,
+                gifts: GiftPolicy::Exempt,
+            })
+        ));
+
+        let royalty = RoyaltyPayment {
+            class_id: 2000,
+            asset_id: 1,
+            rate: rates[1].clone(),
+            beneficiary: 5,
+            amount: 5,
+        };
+        let (can_do_exchange, _, royalties) = Market::do_quote_exchange(&3, 2000, 100, 2).unwrap();
+        assert_eq!(can_do_exchange, true);
+        assert_eq!(royalties, vec![royalty.clone()]);
+
+        assert_ok!(Market::do_exchange_assets(&3, 2000, 100, 2));
+
+        if let RuntimeEvent::Market(crate::Event::Exchanged {
+            royalties, success, ..
+        }) = last_event()
+        {
+            assert_eq!(success, true);
+            assert_eq!(royalties, vec![royalty]);
+        } else {
+            unreachable!()
+        }
+        assert_eq!(Asset::balance_of(&3, 2000, 1), 2);
+        assert_eq!(Asset::balance_of(&3, 3000, 1), 800);
+        assert_eq!(Asset::balance_of(&vault, 3000, 1), 195);
+        assert_eq!(Asset::balance_of(&5, 3000, 1), 5);
+    })
+}
+
+#[test]
+fn gift_forbidden_assets_only_move_in_sales() {
+    new_test_ext().execute_with(|| {
+        before_market();
+        assert_ok!(Market::do_create_market(&2, 2000));
+        let rates = sale_market_rates();
+        assert_ok!(Market::do_create_market_rate(&2, 2000, 100, &rates));
+        let vault = Market::get_vault(2000).unwrap();
+        assert_ok!(Asset::do_transfer_from(&2, &2, &vault, 2000, 1, 10));
+        assert_ok!(Asset::do_mint(&1, &3, 3000, 1, 1000));
+
+        let royalty = Royalty {
+            beneficiary: 5,
+            bps: 0,
+            gifts: GiftPolicy::Forbidden,
+        };
+        assert_ok!(Asset::set_royalty(
+            RuntimeOrigin::signed(1),
+            2000,
+            None,
+            Some(royalty.clone())
+        ));
+
+        // Liquidity is not sold
+        assert_noop!(
+            Market::add_liquidity(&2, 2000, 100, vec![2000], vec![vec![1]], vec![vec![1]]),
+            sugarfunge_asset::Error::<Test>::GiftsForbidden
+        );
+        assert_noop!(
+            Market::remove_liquidity(&2, 2000, 100, vec![2000], vec![vec![1]], vec![vec![1]]),
+            sugarfunge_asset::Error::<Test>::GiftsForbidden
+        );
+
+        // An exchange paying no royalty for the asset is not a sale either
+        assert_noop!(
+            Market::do_exchange_assets(&3, 2000, 100, 1),
+            sugarfunge_asset::Error::<Test>::GiftsForbidden
+        );
+
+        assert_ok!(Asset::set_royalty(
+            RuntimeOrigin::signed(1),
+            2000,
+            None,
+            Some(Royalty {
+                bps: 250,
+                ..royalty
+            })
+        ));
+        assert_ok!(Market::do_exchange_assets(&3, 2000, 100, 1));
+        assert_eq!(Asset::balance_of(&3, 2000, 1), 1);
+        assert_eq!(Asset::balance_of(&5, 3000, 1), 2);
+    })
+}
+
+#[test]
+fn quote_exchange_rejects_excessive_royalties() {
+    new_test_ext().execute_with(|| {
+        before_market();
+        assert_ok!(Market::do_create_market(&2, 2000));
+        let mut rates = sale_market_rates();
+        rates
+            .try_push(AssetRate {
+                class_id: 2000,
+                asset_id: 2,
+                action: RateAction::Transfer(1),
+                from: RateAccount::Market,
+                to: RateAccount::Buyer,
+            })
+            .unwrap();
+        assert_ok!(Market::do_create_market_rate(&2, 2000, 100, &rates));
+
+        for asset_id in [1, 2] {
+            assert_ok!(Asset::set_royalty(
+                RuntimeOrigin::signed(1),
+                2000,
+                Some(asset_id),
+                Some(Royalty {
+                    beneficiary: 5,
+                    bps: 6_000,
+                    gifts: GiftPolicy::Exempt,
+                })
+            ));
+        }
+
+        assert_noop!(
+            Market::do_quote_exchange(&3, 2000, 100, 1),
+            Error::<Test>::InvalidRoyalty
+        );
+    })
+}
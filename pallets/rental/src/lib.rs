@@ -0,0 +1,426 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+    dispatch::DispatchResult,
+    ensure,
+    traits::{Currency, ExistenceRequirement::KeepAlive, Get, ReservableCurrency},
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{SaturatedConversion, Saturating, Zero},
+    RuntimeDebug,
+};
+use sp_std::prelude::*;
+use sugarfunge_primitives::Balance;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+type BalanceOf<T> =
+    <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
+
+    /// Configure the pallet by specifying the parameters and types on which it depends.
+    #[pallet::config]
+    pub trait Config: frame_system::Config + sugarfunge_asset::Config {
+        /// Because this pallet emits events, it depends on the runtime's definition of an event.
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+
+        /// Max number of rentals expiring in the same block
+        #[pallet::constant]
+        type MaxExpiriesPerBlock: Get<u32>;
+    }
+
+    pub type ListingOf<T> = Listing<BalanceOf<T>, BlockNumberFor<T>>;
+    pub type RentalOf<T> = Rental<<T as frame_system::Config>::AccountId, BlockNumberFor<T>>;
+    pub type RentalKeyOf<T> = (
+        <T as frame_system::Config>::AccountId,
+        <T as sugarfunge_asset::Config>::ClassId,
+        <T as sugarfunge_asset::Config>::AssetId,
+    );
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::storage]
+    #[pallet::getter(fn listings)]
+    pub(super) type Listings<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+        ),
+        ListingOf<T>,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn rentals)]
+    pub(super) type Rentals<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::ClassId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+        ),
+        RentalOf<T>,
+    >;
+
+    #[pallet::storage]
+    #[pallet::getter(fn expiries)]
+    pub(super) type Expiries<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        BlockNumberFor<T>,
+        BoundedVec<RentalKeyOf<T>, T::MaxExpiriesPerBlock>,
+        ValueQuery,
+    >;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        Listed {
+            owner: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+            price_per_block: BalanceOf<T>,
+            max_duration: BlockNumberFor<T>,
+        },
+        Unlisted {
+            owner: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        },
+        Rented {
+            owner: T::AccountId,
+            renter: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+            price: BalanceOf<T>,
+            expires: BlockNumberFor<T>,
+        },
+        Expired {
+            owner: T::AccountId,
+            renter: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+        },
+    }
+
+    // Errors inform users that something went wrong.
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Listing does not exist
+        InvalidListing,
+        /// Asset is currently rented out
+        RentalActive,
+        /// Rental does not exist
+        InvalidRental,
+        /// Rental has not expired yet
+        RentalNotExpired,
+        /// Amount to rent must be greater than zero
+        InvalidAmount,
+        /// Duration is zero or above the listing maximum
+        InvalidDuration,
+        /// Owner cannot rent its own assets
+        InvalidRenter,
+        /// Owner does not have enough unlocked assets
+        InsufficientBalance,
+        /// Too many rentals expire in the same block
+        TooManyExpiries,
+        /// Number Overflow
+        NumOverflow,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+            let expiring = Expiries::<T>::take(now);
+            let count = expiring.len() as u64;
+            for (owner, class_id, asset_id) in expiring.into_iter() {
+                // Rentals ended lazily are already gone
+                let _ = Self::do_end_rental(&owner, class_id, asset_id);
+            }
+            T::DbWeight::get().reads_writes(1 + count * 2, 1 + count * 4)
+        }
+    }
+
+    // Dispatchable functions allows users to interact with the pallet and invoke state changes.
+    // These functions materialize as "extrinsics", which are often compared to transactions.
+    // Dispatchable functions must be annotated with a weight and must return a DispatchResult.
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn list(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            amount: Balance,
+            price_per_block: BalanceOf<T>,
+            max_duration: BlockNumberFor<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_list(
+                &who,
+                class_id,
+                asset_id,
+                amount,
+                price_per_block,
+                max_duration,
+            )?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn unlist(
+            origin: OriginFor<T>,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_unlist(&who, class_id, asset_id)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn rent(
+            origin: OriginFor<T>,
+            owner: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+            duration: BlockNumberFor<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_rent(&who, &owner, class_id, asset_id, duration)?;
+
+            Ok(().into())
+        }
+
+        /// Ends an expired rental that has not been processed yet. Callable by anyone.
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn end_rental(
+            origin: OriginFor<T>,
+            owner: T::AccountId,
+            class_id: T::ClassId,
+            asset_id: T::AssetId,
+        ) -> DispatchResultWithPostInfo {
+            let _who = ensure_signed(origin)?;
+
+            Self::do_end_rental(&owner, class_id, asset_id)?;
+
+            Ok(().into())
+        }
+    }
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Listing<CurrencyBalance, BlockNumber> {
+    /// Amount of assets rented out together
+    pub amount: Balance,
+    /// Native currency price per block of rental
+    pub price_per_block: CurrencyBalance,
+    /// Maximum number of blocks per rental
+    pub max_duration: BlockNumber,
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Rental<AccountId, BlockNumber> {
+    /// The account holding user rights
+    pub renter: AccountId,
+    /// Amount of assets rented
+    pub amount: Balance,
+    /// Block at which user rights end
+    pub expires: BlockNumber,
+}
+
+impl<T: Config> Pallet<T> {
+    pub fn do_list(
+        who: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+        amount: Balance,
+        price_per_block: BalanceOf<T>,
+        max_duration: BlockNumberFor<T>,
+    ) -> DispatchResult {
+        ensure!(amount > 0, Error::<T>::InvalidAmount);
+        ensure!(!max_duration.is_zero(), Error::<T>::InvalidDuration);
+        ensure!(
+            !Rentals::<T>::contains_key((who, class_id, asset_id)),
+            Error::<T>::RentalActive
+        );
+
+        let balance = sugarfunge_asset::Pallet::<T>::balance_of(who, class_id, asset_id);
+        let locked = sugarfunge_asset::Pallet::<T>::locked_balance_of(who, class_id, asset_id);
+        ensure!(
+            balance.saturating_sub(locked) >= amount,
+            Error::<T>::InsufficientBalance
+        );
+
+        Listings::<T>::insert(
+            (who, class_id, asset_id),
+            Listing {
+                amount,
+                price_per_block,
+                max_duration,
+            },
+        );
+
+        Self::deposit_event(Event::Listed {
+            owner: who.clone(),
+            class_id,
+            asset_id,
+            amount,
+            price_per_block,
+            max_duration,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_unlist(
+        who: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+    ) -> DispatchResult {
+        ensure!(
+            Listings::<T>::contains_key((who, class_id, asset_id)),
+            Error::<T>::InvalidListing
+        );
+        ensure!(
+            !Rentals::<T>::contains_key((who, class_id, asset_id)),
+            Error::<T>::RentalActive
+        );
+
+        Listings::<T>::remove((who, class_id, asset_id));
+
+        Self::deposit_event(Event::Unlisted {
+            owner: who.clone(),
+            class_id,
+            asset_id,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_rent(
+        who: &T::AccountId,
+        owner: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+        duration: BlockNumberFor<T>,
+    ) -> DispatchResult {
+        ensure!(who != owner, Error::<T>::InvalidRenter);
+
+        let listing =
+            Listings::<T>::get((owner, class_id, asset_id)).ok_or(Error::<T>::InvalidListing)?;
+        ensure!(
+            !Rentals::<T>::contains_key((owner, class_id, asset_id)),
+            Error::<T>::RentalActive
+        );
+        ensure!(
+            !duration.is_zero() && duration <= listing.max_duration,
+            Error::<T>::InvalidDuration
+        );
+
+        let blocks: u128 = duration.saturated_into();
+        let price = listing
+            .price_per_block
+            .saturated_into::<u128>()
+            .checked_mul(blocks)
+            .ok_or(Error::<T>::NumOverflow)?
+            .saturated_into::<BalanceOf<T>>();
+
+        let expires = frame_system::Pallet::<T>::block_number().saturating_add(duration);
+
+        Expiries::<T>::try_mutate(expires, |expiring| -> DispatchResult {
+            expiring
+                .try_push((owner.clone(), class_id, asset_id))
+                .map_err(|_| Error::<T>::TooManyExpiries)?;
+            Ok(())
+        })?;
+
+        <T as Config>::Currency::transfer(who, owner, price, KeepAlive)?;
+
+        sugarfunge_asset::Pallet::<T>::do_lend(owner, who, class_id, asset_id, listing.amount)?;
+
+        Rentals::<T>::insert(
+            (owner, class_id, asset_id),
+            Rental {
+                renter: who.clone(),
+                amount: listing.amount,
+                expires,
+            },
+        );
+
+        Self::deposit_event(Event::Rented {
+            owner: owner.clone(),
+            renter: who.clone(),
+            class_id,
+            asset_id,
+            amount: listing.amount,
+            price,
+            expires,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_end_rental(
+        owner: &T::AccountId,
+        class_id: T::ClassId,
+        asset_id: T::AssetId,
+    ) -> DispatchResult {
+        let rental =
+            Rentals::<T>::get((owner, class_id, asset_id)).ok_or(Error::<T>::InvalidRental)?;
+        ensure!(
+            frame_system::Pallet::<T>::block_number() >= rental.expires,
+            Error::<T>::RentalNotExpired
+        );
+
+        sugarfunge_asset::Pallet::<T>::do_reclaim(
+            owner,
+            &rental.renter,
+            class_id,
+            asset_id,
+            rental.amount,
+        )?;
+
+        Rentals::<T>::remove((owner, class_id, asset_id));
+
+        Self::deposit_event(Event::Expired {
+            owner: owner.clone(),
+            renter: rental.renter,
+            class_id,
+            asset_id,
+            amount: rental.amount,
+        });
+
+        Ok(())
+    }
+}
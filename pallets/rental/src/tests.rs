@@ -0,0 +1,122 @@
+use crate::{mock::*, Error};
+use frame_support::{assert_noop, assert_ok};
+
+fn last_event() -> RuntimeEvent {
+    frame_system::Pallet::<Test>::events()
+        .pop()
+        .expect("Event expected")
+        .event
+}
+
+pub fn before_rental() {
+    run_to_block(10);
+
+    assert_ok!(Asset::do_mint(&1, &1, 1000, 1, 100));
+    assert_ok!(Rental::list(
+        RuntimeOrigin::signed(1),
+        1000,
+        1,
+        10,
+        DOLLARS,
+        20
+    ));
+}
+
+#[test]
+fn before_rental_works() {
+    new_test_ext().execute_with(|| {
+        before_rental();
+    })
+}
+
+#[test]
+fn rent_locks_assets_and_grants_user_rights() {
+    new_test_ext().execute_with(|| {
+        before_rental();
+
+        assert_ok!(Rental::rent(RuntimeOrigin::signed(2), 1, 1000, 1, 5));
+
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Rental(crate::Event::Rented {
+                owner: 1,
+                renter: 2,
+                class_id: 1000,
+                asset_id: 1,
+                amount: 10,
+                price: 5 * DOLLARS,
+                expires: 15,
+            }),
+        );
+
+        assert_eq!(Balances::free_balance(1), 1000005 * DOLLARS);
+        assert_eq!(Balances::free_balance(2), 999995 * DOLLARS);
+
+        // Owner keeps ownership, renter gets user rights
+        assert_eq!(Asset::balance_of(&1, 1000, 1), 100);
+        assert_eq!(Asset::balance_of(&2, 1000, 1), 0);
+        assert_eq!(Asset::usable_balance_of(&1, 1000, 1), 90);
+        assert_eq!(Asset::usable_balance_of(&2, 1000, 1), 10);
+
+        assert_noop!(
+            Asset::do_transfer_from(&1, &1, &3, 1000, 1, 91),
+            sugarfunge_asset::Error::<Test>::BalanceLocked
+        );
+        assert_ok!(Asset::do_transfer_from(&1, &1, &3, 1000, 1, 90));
+    })
+}
+
+#[test]
+fn rental_expires_on_initialize() {
+    new_test_ext().execute_with(|| {
+        before_rental();
+
+        assert_ok!(Rental::rent(RuntimeOrigin::signed(2), 1, 1000, 1, 5));
+
+        run_to_block(14);
+        assert_eq!(Asset::usable_balance_of(&2, 1000, 1), 10);
+        assert_noop!(
+            Rental::end_rental(RuntimeOrigin::signed(2), 1, 1000, 1),
+            Error::<Test>::RentalNotExpired
+        );
+
+        run_to_block(15);
+        assert_eq!(Rental::rentals((1, 1000, 1)), None);
+        assert_eq!(Asset::usable_balance_of(&1, 1000, 1), 100);
+        assert_eq!(Asset::usable_balance_of(&2, 1000, 1), 0);
+        assert!(Rental::listings((1, 1000, 1)).is_some());
+
+        assert_ok!(Asset::do_transfer_from(&1, &1, &3, 1000, 1, 100));
+    })
+}
+
+#[test]
+fn rent_fails() {
+    new_test_ext().execute_with(|| {
+        before_rental();
+
+        assert_noop!(
+            Rental::rent(RuntimeOrigin::signed(1), 1, 1000, 1, 5),
+            Error::<Test>::InvalidRenter
+        );
+        assert_noop!(
+            Rental::rent(RuntimeOrigin::signed(2), 1, 1000, 2, 5),
+            Error::<Test>::InvalidListing
+        );
+        assert_noop!(
+            Rental::rent(RuntimeOrigin::signed(2), 1, 1000, 1, 21),
+            Error::<Test>::InvalidDuration
+        );
+
+        assert_ok!(Rental::rent(RuntimeOrigin::signed(2), 1, 1000, 1, 5));
+
+        assert_noop!(
+            Rental::rent(RuntimeOrigin::signed(2), 1, 1000, 1, 5),
+            Error::<Test>::RentalActive
+        );
+        assert_noop!(
+            Rental::unlist(RuntimeOrigin::signed(1), 1000, 1),
+            Error::<Test>::RentalActive
+        );
+    })
+}
@@ -0,0 +1,601 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+    dispatch::{DispatchInfo, DispatchResult, PostDispatchInfo},
+    ensure,
+    traits::{Currency, ExistenceRequirement, Get},
+    BoundedVec, PalletId,
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use pallet_transaction_payment::{ChargeTransactionPayment, OnChargeTransaction};
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{
+        AccountIdConversion, DispatchInfoOf, Dispatchable, PostDispatchInfoOf, Saturating,
+        SignedExtension, UniqueSaturatedInto, Zero,
+    },
+    transaction_validity::{InvalidTransaction, TransactionValidity, TransactionValidityError},
+    RuntimeDebug,
+};
+use sp_std::{marker::PhantomData, prelude::*};
+use sugarfunge_primitives::Balance;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub type SponsorshipId = u32;
+
+/// Invalid transaction code of calls a sponsorship does not cover
+pub const NOT_COVERED: u8 = 1;
+/// Invalid transaction code of users over their sponsored rate limit
+pub const RATE_LIMITED: u8 = 2;
+
+type BalanceOf<T> =
+    <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+type NativeBalanceOf<T> = <<T as pallet_transaction_payment::Config>::OnChargeTransaction as OnChargeTransaction<T>>::Balance;
+
+/// Classes of the assets a call operates on
+pub trait ClassOfCall<Call, ClassId> {
+    fn classes_of(call: &Call) -> Vec<ClassId>;
+}
+
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct CallFilter {
+    /// Index of the pallet in the runtime
+    pub pallet_index: u8,
+    /// Index of the call in the pallet, any call if `None`
+    pub call_index: Option<u8>,
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct SponsorshipTerms<BlockNumber, Calls, Classes> {
+    /// Calls covered by the sponsorship
+    pub calls: Calls,
+    /// Classes the covered calls must only operate on, any class if empty
+    pub classes: Classes,
+    /// Max number of sponsored transactions per user and period
+    pub max_per_period: u32,
+    /// Length of a rate limit period in blocks
+    pub period: BlockNumber,
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Sponsorship<AccountId, Terms> {
+    pub sponsor: AccountId,
+    pub terms: Terms,
+}
+
+#[derive(
+    Encode, Decode, Clone, Copy, Default, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen,
+)]
+pub struct Usage<BlockNumber> {
+    /// Block the current rate limit period started
+    pub since: BlockNumber,
+    /// Sponsored transactions in the current period
+    pub count: u32,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+    use frame_system::pallet_prelude::*;
+
+    #[pallet::config]
+    pub trait Config:
+        frame_system::Config + pallet_transaction_payment::Config + sugarfunge_asset::Config
+    {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+
+        type Currency: Currency<Self::AccountId>;
+
+        /// Reads the classes of the assets a call operates on
+        type CallClass: ClassOfCall<<Self as frame_system::Config>::RuntimeCall, Self::ClassId>;
+
+        /// Max number of call filters per sponsorship
+        #[pallet::constant]
+        type MaxSponsoredCalls: Get<u32>;
+
+        /// Max number of classes per sponsorship
+        #[pallet::constant]
+        type MaxSponsoredClasses: Get<u32>;
+    }
+
+    pub type SponsorshipTermsOf<T> = SponsorshipTerms<
+        BlockNumberFor<T>,
+        BoundedVec<CallFilter, <T as Config>::MaxSponsoredCalls>,
+        BoundedVec<<T as sugarfunge_asset::Config>::ClassId, <T as Config>::MaxSponsoredClasses>,
+    >;
+
+    pub type SponsorshipOf<T> =
+        Sponsorship<<T as frame_system::Config>::AccountId, SponsorshipTermsOf<T>>;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::storage]
+    #[pallet::getter(fn next_sponsorship_id)]
+    pub(super) type NextSponsorshipId<T: Config> = StorageValue<_, SponsorshipId, ValueQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn sponsorships)]
+    pub(super) type Sponsorships<T: Config> =
+        StorageMap<_, Blake2_128Concat, SponsorshipId, SponsorshipOf<T>>;
+
+    /// Sponsored transactions of a user in the current rate limit period
+    #[pallet::storage]
+    #[pallet::getter(fn usage)]
+    pub(super) type Usages<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        SponsorshipId,
+        Blake2_128Concat,
+        T::AccountId,
+        Usage<BlockNumberFor<T>>,
+        ValueQuery,
+    >;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        Created {
+            sponsorship_id: SponsorshipId,
+            sponsor: T::AccountId,
+        },
+        Updated {
+            sponsorship_id: SponsorshipId,
+        },
+        Funded {
+            sponsorship_id: SponsorshipId,
+            who: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        Withdrawn {
+            sponsorship_id: SponsorshipId,
+            amount: BalanceOf<T>,
+        },
+        Removed {
+            sponsorship_id: SponsorshipId,
+        },
+        Sponsored {
+            sponsorship_id: SponsorshipId,
+            who: T::AccountId,
+            actual_fee: Balance,
+        },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        InvalidSponsorship,
+        InvalidTerms,
+        NoPermission,
+        NumOverflow,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Sponsor the calls matching `terms`, paid from a budget funded separately
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn create(
+            origin: OriginFor<T>,
+            terms: SponsorshipTermsOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_create(&who, terms)?;
+
+            Ok(().into())
+        }
+
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn update(
+            origin: OriginFor<T>,
+            sponsorship_id: SponsorshipId,
+            terms: SponsorshipTermsOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_update(&who, sponsorship_id, terms)?;
+
+            Ok(().into())
+        }
+
+        /// Add `amount` to the budget of a sponsorship
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn fund(
+            origin: OriginFor<T>,
+            sponsorship_id: SponsorshipId,
+            amount: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_fund(&who, sponsorship_id, amount)?;
+
+            Ok(().into())
+        }
+
+        /// Return `amount` of the budget to the sponsor
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn withdraw(
+            origin: OriginFor<T>,
+            sponsorship_id: SponsorshipId,
+            amount: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_withdraw(&who, sponsorship_id, amount)?;
+
+            Ok(().into())
+        }
+
+        /// End a sponsorship and return its remaining budget to the sponsor
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000 as u64, 0))]
+        pub fn remove(
+            origin: OriginFor<T>,
+            sponsorship_id: SponsorshipId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            Self::do_remove(&who, sponsorship_id)?;
+
+            Ok(().into())
+        }
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Account holding the budget of a sponsorship
+    pub fn budget_account(sponsorship_id: SponsorshipId) -> T::AccountId {
+        <T as Config>::PalletId::get().into_sub_account_truncating(sponsorship_id)
+    }
+
+    pub fn do_create(who: &T::AccountId, terms: SponsorshipTermsOf<T>) -> DispatchResult {
+        Self::validate_terms(&terms)?;
+
+        let sponsorship_id =
+            NextSponsorshipId::<T>::try_mutate(|id| -> Result<SponsorshipId, DispatchError> {
+                let current_id = *id;
+                *id = id.checked_add(1).ok_or(Error::<T>::NumOverflow)?;
+                Ok(current_id)
+            })?;
+
+        Sponsorships::<T>::insert(
+            sponsorship_id,
+            Sponsorship {
+                sponsor: who.clone(),
+                terms,
+            },
+        );
+
+        Self::deposit_event(Event::Created {
+            sponsorship_id,
+            sponsor: who.clone(),
+        });
+
+        Ok(())
+    }
+
+    pub fn do_update(
+        who: &T::AccountId,
+        sponsorship_id: SponsorshipId,
+        terms: SponsorshipTermsOf<T>,
+    ) -> DispatchResult {
+        Self::validate_terms(&terms)?;
+
+        Sponsorships::<T>::try_mutate(sponsorship_id, |sponsorship| -> DispatchResult {
+            let sponsorship = sponsorship.as_mut().ok_or(Error::<T>::InvalidSponsorship)?;
+            ensure!(sponsorship.sponsor == *who, Error::<T>::NoPermission);
+            sponsorship.terms = terms;
+            Ok(())
+        })?;
+
+        Self::deposit_event(Event::Updated { sponsorship_id });
+
+        Ok(())
+    }
+
+    pub fn do_fund(
+        who: &T::AccountId,
+        sponsorship_id: SponsorshipId,
+        amount: BalanceOf<T>,
+    ) -> DispatchResult {
+        ensure!(
+            Sponsorships::<T>::contains_key(sponsorship_id),
+            Error::<T>::InvalidSponsorship
+        );
+
+        <T as Config>::Currency::transfer(
+            who,
+            &Self::budget_account(sponsorship_id),
+            amount,
+            ExistenceRequirement::KeepAlive,
+        )?;
+
+        Self::deposit_event(Event::Funded {
+            sponsorship_id,
+            who: who.clone(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_withdraw(
+        who: &T::AccountId,
+        sponsorship_id: SponsorshipId,
+        amount: BalanceOf<T>,
+    ) -> DispatchResult {
+        let sponsorship =
+            Sponsorships::<T>::get(sponsorship_id).ok_or(Error::<T>::InvalidSponsorship)?;
+        ensure!(sponsorship.sponsor == *who, Error::<T>::NoPermission);
+
+        <T as Config>::Currency::transfer(
+            &Self::budget_account(sponsorship_id),
+            who,
+            amount,
+            ExistenceRequirement::AllowDeath,
+        )?;
+
+        Self::deposit_event(Event::Withdrawn {
+            sponsorship_id,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn do_remove(who: &T::AccountId, sponsorship_id: SponsorshipId) -> DispatchResult {
+        let sponsorship =
+            Sponsorships::<T>::get(sponsorship_id).ok_or(Error::<T>::InvalidSponsorship)?;
+        ensure!(sponsorship.sponsor == *who, Error::<T>::NoPermission);
+
+        let budget = Self::budget_account(sponsorship_id);
+        <T as Config>::Currency::transfer(
+            &budget,
+            who,
+            <T as Config>::Currency::free_balance(&budget),
+            ExistenceRequirement::AllowDeath,
+        )?;
+
+        Sponsorships::<T>::remove(sponsorship_id);
+        let _ = Usages::<T>::clear_prefix(sponsorship_id, u32::MAX, None);
+
+        Self::deposit_event(Event::Removed { sponsorship_id });
+
+        Ok(())
+    }
+
+    fn validate_terms(terms: &SponsorshipTermsOf<T>) -> DispatchResult {
+        ensure!(
+            !terms.calls.is_empty() && terms.max_per_period > 0 && !terms.period.is_zero(),
+            Error::<T>::InvalidTerms
+        );
+        Ok(())
+    }
+
+    /// Whether the terms of a sponsorship cover `call`
+    pub fn covers(
+        terms: &SponsorshipTermsOf<T>,
+        call: &<T as frame_system::Config>::RuntimeCall,
+    ) -> bool {
+        let (pallet_index, call_index) = call.using_encoded(|encoded| {
+            (
+                encoded.first().copied().unwrap_or_default(),
+                encoded.get(1).copied().unwrap_or_default(),
+            )
+        });
+
+        let call_covered = terms.calls.iter().any(|filter| {
+            filter.pallet_index == pallet_index
+                && filter.call_index.map_or(true, |index| index == call_index)
+        });
+        let class_covered = terms.classes.is_empty() || {
+            let classes = T::CallClass::classes_of(call);
+            !classes.is_empty()
+                && classes
+                    .iter()
+                    .all(|class_id| terms.classes.contains(class_id))
+        };
+
+        call_covered && class_covered
+    }
+
+    /// Check that `who` may have `call` sponsored and return the budget account and usage after it
+    fn check_sponsored(
+        sponsorship_id: SponsorshipId,
+        who: &T::AccountId,
+        call: &<T as frame_system::Config>::RuntimeCall,
+    ) -> Result<(T::AccountId, Usage<BlockNumberFor<T>>), TransactionValidityError> {
+        let sponsorship = Sponsorships::<T>::get(sponsorship_id)
+            .ok_or(InvalidTransaction::Custom(NOT_COVERED))?;
+        ensure!(
+            Self::covers(&sponsorship.terms, call),
+            InvalidTransaction::Custom(NOT_COVERED)
+        );
+
+        let now = frame_system::Pallet::<T>::block_number();
+        let mut usage = Usages::<T>::get(sponsorship_id, who);
+        if now >= usage.since.saturating_add(sponsorship.terms.period) {
+            usage = Usage {
+                since: now,
+                count: 0,
+            };
+        }
+        ensure!(
+            usage.count < sponsorship.terms.max_per_period,
+            InvalidTransaction::Custom(RATE_LIMITED)
+        );
+        usage.count = usage.count.saturating_add(1);
+
+        Ok((Self::budget_account(sponsorship_id), usage))
+    }
+}
+
+pub enum SponsoredPre<T: Config, Pre> {
+    /// Fees charged by the wrapped extension
+    Unsponsored(Pre),
+    /// Fees charged to the budget of a sponsorship
+    Sponsored {
+        sponsorship_id: SponsorshipId,
+        who: T::AccountId,
+        pre: <ChargeTransactionPayment<T> as SignedExtension>::Pre,
+    },
+}
+
+/// Charge fees to a sponsorship budget when the call is covered, or through `S` otherwise
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ChargeSponsored<T: Config, S> {
+    sponsorship_id: Option<SponsorshipId>,
+    inner: S,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Config, S> ChargeSponsored<T, S> {
+    pub fn from(sponsorship_id: Option<SponsorshipId>, inner: S) -> Self {
+        Self {
+            sponsorship_id,
+            inner,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Config, S: sp_std::fmt::Debug> sp_std::fmt::Debug for ChargeSponsored<T, S> {
+    #[cfg(feature = "std")]
+    fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+        write!(
+            f,
+            "ChargeSponsored<{:?}, {:?}>",
+            self.sponsorship_id, self.inner
+        )
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+        Ok(())
+    }
+}
+
+impl<T: Config, S> SignedExtension for ChargeSponsored<T, S>
+where
+    S: SignedExtension<AccountId = T::AccountId, Call = <T as frame_system::Config>::RuntimeCall>,
+    <T as frame_system::Config>::RuntimeCall:
+        Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+    NativeBalanceOf<T>: Send + Sync + From<u64>,
+{
+    const IDENTIFIER: &'static str = "ChargeSponsored";
+    type AccountId = T::AccountId;
+    type Call = <T as frame_system::Config>::RuntimeCall;
+    type AdditionalSigned = S::AdditionalSigned;
+    type Pre = SponsoredPre<T, S::Pre>;
+
+    fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
+        self.inner.additional_signed()
+    }
+
+    fn validate(
+        &self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        len: usize,
+    ) -> TransactionValidity {
+        let Some(sponsorship_id) = self.sponsorship_id else {
+            return self.inner.validate(who, call, info, len);
+        };
+
+        let (budget, _) = Pallet::<T>::check_sponsored(sponsorship_id, who, call)?;
+        ChargeTransactionPayment::<T>::from(Zero::zero()).validate(&budget, call, info, len)
+    }
+
+    fn pre_dispatch(
+        self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        let Some(sponsorship_id) = self.sponsorship_id else {
+            return Ok(SponsoredPre::Unsponsored(
+                self.inner.pre_dispatch(who, call, info, len)?,
+            ));
+        };
+
+        let (budget, usage) = Pallet::<T>::check_sponsored(sponsorship_id, who, call)?;
+        let pre = ChargeTransactionPayment::<T>::from(Zero::zero())
+            .pre_dispatch(&budget, call, info, len)?;
+        Usages::<T>::insert(sponsorship_id, who, usage);
+
+        Ok(SponsoredPre::Sponsored {
+            sponsorship_id,
+            who: who.clone(),
+            pre,
+        })
+    }
+
+    fn validate_unsigned(
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        len: usize,
+    ) -> TransactionValidity {
+        S::validate_unsigned(call, info, len)
+    }
+
+    fn post_dispatch(
+        pre: Option<Self::Pre>,
+        info: &DispatchInfoOf<Self::Call>,
+        post_info: &PostDispatchInfoOf<Self::Call>,
+        len: usize,
+        result: &DispatchResult,
+    ) -> Result<(), TransactionValidityError> {
+        match pre {
+            None => S::post_dispatch(None, info, post_info, len, result),
+            Some(SponsoredPre::Unsponsored(pre)) => {
+                S::post_dispatch(Some(pre), info, post_info, len, result)
+            }
+            Some(SponsoredPre::Sponsored {
+                sponsorship_id,
+                who,
+                pre,
+            }) => {
+                ChargeTransactionPayment::<T>::post_dispatch(
+                    Some(pre),
+                    info,
+                    post_info,
+                    len,
+                    result,
+                )?;
+
+                let actual_fee = pallet_transaction_payment::Pallet::<T>::compute_actual_fee(
+                    len as u32,
+                    info,
+                    post_info,
+                    Zero::zero(),
+                );
+                Pallet::<T>::deposit_event(Event::Sponsored {
+                    sponsorship_id,
+                    who,
+                    actual_fee: actual_fee.unique_saturated_into(),
+                });
+
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    mock::*, CallFilter, ChargeSponsored, Error, SponsorshipTerms, SponsorshipTermsOf, NOT_COVERED,
+    RATE_LIMITED,
+};
+use codec::Encode;
+use frame_support::{
+    assert_noop, assert_ok, bounded_vec,
+    dispatch::{DispatchInfo, Pays, PostDispatchInfo},
+    weights::Weight,
+};
+use pallet_transaction_payment::ChargeTransactionPayment;
+use sp_runtime::{
+    traits::SignedExtension,
+    transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+
+type Extension = ChargeSponsored<Test, ChargeTransactionPayment<Test>>;
+
+fn last_event() -> RuntimeEvent {
+    frame_system::Pallet::<Test>::events()
+        .pop()
+        .expect("Event expected")
+        .event
+}
+
+fn transfer_call(class_id: u64) -> RuntimeCall {
+    RuntimeCall::Asset(sugarfunge_asset::Call::transfer_from {
+        from: 3,
+        to: 4,
+        class_id,
+        asset_id: 1,
+        amount: 1,
+    })
+}
+
+fn terms() -> SponsorshipTermsOf<Test> {
+    let encoded = transfer_call(1000).encode();
+    SponsorshipTerms {
+        calls: bounded_vec![CallFilter {
+            pallet_index: encoded[0],
+            call_index: Some(encoded[1]),
+        }],
+        classes: bounded_vec![1000],
+        max_per_period: 2,
+        period: 10,
+    }
+}
+
+fn info() -> DispatchInfo {
+    DispatchInfo {
+        weight: Weight::from_parts(1_000, 0),
+        ..Default::default()
+    }
+}
+
+fn post_info() -> PostDispatchInfo {
+    PostDispatchInfo {
+        actual_weight: Some(Weight::from_parts(500, 0)),
+        pays_fee: Pays::Yes,
+    }
+}
+
+fn invalid(code: u8) -> Result<(), TransactionValidityError> {
+    Err(TransactionValidityError::Invalid(
+        InvalidTransaction::Custom(code),
+    ))
+}
+
+const LEN: usize = 10;
+
+pub fn before_sponsorship() {
+    System::set_block_number(1);
+
+    // Account 1 sponsors transfers of class 1000 for players without native balance
+    assert_ok!(Sponsor::create(RuntimeOrigin::signed(1), terms()));
+    assert_ok!(Sponsor::fund(RuntimeOrigin::signed(1), 0, 10 * DOLLARS));
+}
+
+#[test]
+fn before_sponsorship_works() {
+    new_test_ext().execute_with(|| {
+        before_sponsorship();
+
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Sponsor(crate::Event::Funded {
+                sponsorship_id: 0,
+                who: 1,
+                amount: 10 * DOLLARS,
+            })
+        );
+        assert_eq!(
+            Balances::free_balance(Sponsor::budget_account(0)),
+            10 * DOLLARS
+        );
+    })
+}
+
+#[test]
+fn update_sponsorship_fails() {
+    new_test_ext().execute_with(|| {
+        before_sponsorship();
+
+        assert_noop!(
+            Sponsor::update(RuntimeOrigin::signed(2), 0, terms()),
+            Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            Sponsor::update(
+                RuntimeOrigin::signed(1),
+                0,
+                SponsorshipTerms {
+                    max_per_period: 0,
+                    ..terms()
+                }
+            ),
+            Error::<Test>::InvalidTerms
+        );
+        assert_noop!(
+            Sponsor::withdraw(RuntimeOrigin::signed(2), 0, DOLLARS),
+            Error::<Test>::NoPermission
+        );
+        assert_noop!(
+            Sponsor::fund(RuntimeOrigin::signed(1), 1, DOLLARS),
+            Error::<Test>::InvalidSponsorship
+        );
+    })
+}
+
+#[test]
+fn sponsor_pays_covered_calls() {
+    new_test_ext().execute_with(|| {
+        before_sponsorship();
+
+        let budget = Balances::free_balance(Sponsor::budget_account(0));
+        let call = transfer_call(1000);
+        let actual_fee =
+            TransactionPayment::compute_actual_fee(LEN as u32, &info(), &post_info(), 0);
+
+        let ext = Extension::from(Some(0), ChargeTransactionPayment::from(0));
+        assert_ok!(ext.validate(&3, &call, &info(), LEN));
+        let pre = ext.pre_dispatch(&3, &call, &info(), LEN).unwrap();
+        assert_ok!(Extension::post_dispatch(
+            Some(pre),
+            &info(),
+            &post_info(),
+            LEN,
+            &Ok(())
+        ));
+
+        assert_eq!(
+            last_event(),
+            RuntimeEvent::Sponsor(crate::Event::Sponsored {
+                sponsorship_id: 0,
+                who: 3,
+                actual_fee,
+            })
+        );
+        assert_eq!(
+            Balances::free_balance(Sponsor::budget_account(0)),
+            budget - actual_fee
+        );
+        assert_eq!(Sponsor::usage(0, 3).count, 1);
+    })
+}
+
+#[test]
+fn uncovered_calls_are_rejected() {
+    new_test_ext().execute_with(|| {
+        before_sponsorship();
+
+        let ext = Extension::from(Some(0), ChargeTransactionPayment::from(0));
+        assert_eq!(
+            ext.validate(&3, &transfer_call(2000), &info(), LEN),
+            invalid(NOT_COVERED)
+        );
+        let remark = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+        assert_eq!(
+            ext.validate(&3, &remark, &info(), LEN),
+            invalid(NOT_COVERED)
+        );
+        let ext = Extension::from(Some(1), ChargeTransactionPayment::from(0));
+        assert_eq!(
+            ext.validate(&3, &transfer_call(1000), &info(), LEN),
+            invalid(NOT_COVERED)
+        );
+
+        // Without a sponsorship the signer pays
+        let ext = Extension::from(None, ChargeTransactionPayment::from(0));
+        assert_eq!(
+            ext.validate(&3, &transfer_call(1000), &info(), LEN),
+            Err(TransactionValidityError::Invalid(
+                InvalidTransaction::Payment
+            ))
+        );
+        let balance = Balances::free_balance(2);
+        let ext = Extension::from(None, ChargeTransactionPayment::from(0));
+        assert_ok!(ext.pre_dispatch(&2, &transfer_call(1000), &info(), LEN));
+        assert!(Balances::free_balance(2) < balance);
+    })
+}
+
+#[test]
+fn sponsored_calls_are_rate_limited() {
+    new_test_ext().execute_with(|| {
+        before_sponsorship();
+
+        let call = transfer_call(1000);
+        for _ in 0..2 {
+            let ext = Extension::from(Some(0), ChargeTransactionPayment::from(0));
+            assert_ok!(ext.pre_dispatch(&3, &call, &info(), LEN));
+        }
+        let ext = Extension::from(Some(0), ChargeTransactionPayment::from(0));
+        assert_eq!(ext.validate(&3, &call, &info(), LEN), invalid(RATE_LIMITED));
+        // Other users have their own limit
+        assert_ok!(ext.validate(&4, &call, &info(), LEN));
+
+        System::set_block_number(11);
+        assert_ok!(ext.pre_dispatch(&3, &call, &info(), LEN));
+        assert_eq!(Sponsor::usage(0, 3).count, 1);
+    })
+}
+
+#[test]
+fn remove_returns_budget() {
+    new_test_ext().execute_with(|| {
+        before_sponsorship();
+
+        let balance = Balances::free_balance(1);
+        assert_ok!(Sponsor::withdraw(RuntimeOrigin::signed(1), 0, DOLLARS));
+        assert_eq!(Balances::free_balance(1), balance + DOLLARS);
+
+        assert_ok!(Sponsor::remove(RuntimeOrigin::signed(1), 0));
+        assert_eq!(Balances::free_balance(1), balance + 10 * DOLLARS);
+        assert_eq!(Balances::free_balance(Sponsor::budget_account(0)), 0);
+
+        let ext = Extension::from(Some(0), ChargeTransactionPayment::from(0));
+        assert_eq!(
+            ext.validate(&3, &transfer_call(1000), &info(), LEN),
+            invalid(NOT_COVERED)
+        );
+    })
+}
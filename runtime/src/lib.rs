@@ -15,9 +15,9 @@ use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::traits::OpaqueKeys;
 use sp_runtime::{
     create_runtime_str, generic, impl_opaque_keys,
-    traits::{BlakeTwo256, Block as BlockT, NumberFor, One},
+    traits::{BlakeTwo256, Block as BlockT, NumberFor, One, Verify},
     transaction_validity::{TransactionSource, TransactionValidity},
-    ApplyExtrinsicResult,
+    ApplyExtrinsicResult, FixedU128,
 };
 use sp_std::prelude::*;
 #[cfg(feature = "std")]
@@ -229,6 +229,7 @@ parameter_types! {
     pub FeeMultiplier: Multiplier = Multiplier::one();
     pub const BundleModuleId: PalletId = PalletId(*b"sug/bndl");
     pub const MaxAssets: u32 = 20;
+    pub const MaxBundleDepth: u32 = 3;
 }
 
 impl pallet_transaction_payment::Config for Runtime {
@@ -326,6 +327,8 @@ parameter_types! {
 parameter_types! {
     pub const MaxClassMetadata: u32 = METADATA_SIZE;
     pub const MaxAssetMetadata: u32 = METADATA_SIZE;
+    pub const MaxVestingSchedules: u32 = 28;
+    pub const MaxMultiItems: u32 = 128;
 }
 
 impl sugarfunge_asset::Config for Runtime {
@@ -336,11 +339,16 @@ impl sugarfunge_asset::Config for Runtime {
     type ClassId = u64;
     type MaxClassMetadata = MaxClassMetadata;
     type MaxAssetMetadata = MaxAssetMetadata;
+    type MaxVestingSchedules = MaxVestingSchedules;
+    type MaxMultiItems = MaxMultiItems;
+    type OffchainSignature = Signature;
+    type OffchainPublic = <Signature as Verify>::Signer;
 }
 
 parameter_types! {
     pub const BagModuleId: PalletId = PalletId(*b"sug/crow");
     pub const MarketModuleId: PalletId = PalletId(*b"sug/mrkt");
+    pub const DaoModuleId: PalletId = PalletId(*b"sug/daos");
 }
 
 parameter_types! {
@@ -353,10 +361,29 @@ impl sugarfunge_bundle::Config for Runtime {
     type PalletId = BundleModuleId;
     type Currency = Balances;
     type MaxAssets = MaxAssets;
+    type MaxBundleDepth = MaxBundleDepth;
 }
 
 parameter_types! {
     pub const MaxOwners: u32 = 20;
+    pub const MaxBagProposalLength: u32 = 4096;
+    pub const BagDefaultQuorum: Perbill = Perbill::from_percent(50);
+    pub const BagDefaultThreshold: Perbill = Perbill::from_percent(50);
+    pub const BagDefaultVotingPeriod: BlockNumber = 3 * DAYS;
+}
+
+/// Calls a bag's share holders can propose to dispatch as the bag account
+pub struct BagProposalFilter;
+impl Contains<RuntimeCall> for BagProposalFilter {
+    fn contains(call: &RuntimeCall) -> bool {
+        matches!(
+            call,
+            RuntimeCall::Bag(sugarfunge_bag::Call::transfer { .. })
+                | RuntimeCall::Bag(sugarfunge_bag::Call::deposit { .. })
+                | RuntimeCall::Bag(sugarfunge_bag::Call::set_settings { .. })
+                | RuntimeCall::Market(sugarfunge_market::Call::exchange_assets { .. })
+        )
+    }
 }
 
 impl sugarfunge_bag::Config for Runtime {
@@ -365,6 +392,12 @@ impl sugarfunge_bag::Config for Runtime {
     type CreateBagDeposit = CreateBagDeposit;
     type Currency = Balances;
     type MaxOwners = MaxOwners;
+    type Proposal = RuntimeCall;
+    type ProposalFilter = BagProposalFilter;
+    type MaxProposalLength = MaxBagProposalLength;
+    type DefaultQuorum = BagDefaultQuorum;
+    type DefaultThreshold = BagDefaultThreshold;
+    type DefaultVotingPeriod = BagDefaultVotingPeriod;
 }
 
 impl sugarfunge_market::Config for Runtime {
@@ -376,6 +409,126 @@ impl sugarfunge_market::Config for Runtime {
     type MaxMetadata = MaxMetadata;
 }
 
+parameter_types! {
+    pub const MaxDaoProposalLength: u32 = 4096;
+}
+
+/// Calls a dao's holders can propose to dispatch as the dao account
+pub struct DaoProposalFilter;
+impl Contains<RuntimeCall> for DaoProposalFilter {
+    fn contains(call: &RuntimeCall) -> bool {
+        matches!(
+            call,
+            RuntimeCall::Balances(pallet_balances::Call::transfer_keep_alive { .. })
+                | RuntimeCall::Balances(pallet_balances::Call::transfer_allow_death { .. })
+                | RuntimeCall::Dao(sugarfunge_dao::Call::set_settings { .. })
+                | RuntimeCall::Bag(sugarfunge_bag::Call::deposit { .. })
+                | RuntimeCall::Bag(sugarfunge_bag::Call::vote { .. })
+                | RuntimeCall::Market(sugarfunge_market::Call::exchange_assets { .. })
+        )
+    }
+}
+
+impl sugarfunge_dao::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type PalletId = DaoModuleId;
+    type Proposal = RuntimeCall;
+    type ProposalFilter = DaoProposalFilter;
+    type MaxProposalLength = MaxDaoProposalLength;
+    type MaxMetadata = MaxMetadata;
+}
+
+parameter_types! {
+    pub const MaxRentalExpiriesPerBlock: u32 = 50;
+}
+
+impl sugarfunge_rental::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
+    type MaxExpiriesPerBlock = MaxRentalExpiriesPerBlock;
+}
+
+parameter_types! {
+    pub MinFeeRate: FixedU128 = FixedU128::from_rational(1, 1_000_000);
+}
+
+impl sugarfunge_fee::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type MinFeeRate = MinFeeRate;
+}
+
+parameter_types! {
+    pub const SponsorModuleId: PalletId = PalletId(*b"sug/spon");
+    pub const MaxSponsoredCalls: u32 = 16;
+    pub const MaxSponsoredClasses: u32 = 16;
+}
+
+pub struct SponsoredCallClass;
+impl sugarfunge_sponsor::ClassOfCall<RuntimeCall, ClassId> for SponsoredCallClass {
+    fn classes_of(call: &RuntimeCall) -> Vec<ClassId> {
+        match call {
+            RuntimeCall::Asset(sugarfunge_asset::Call::transfer_from { class_id, .. })
+            | RuntimeCall::Asset(sugarfunge_asset::Call::batch_transfer_from {
+                class_id, ..
+            })
+            | RuntimeCall::Asset(sugarfunge_asset::Call::burn { class_id, .. })
+            | RuntimeCall::Asset(sugarfunge_asset::Call::batch_burn { class_id, .. })
+            | RuntimeCall::Asset(sugarfunge_asset::Call::vest { class_id, .. }) => vec![*class_id],
+            // Market calls operate on every class of their rate
+            RuntimeCall::Market(sugarfunge_market::Call::create_market_rate { rates, .. }) => {
+                Market::classes_of_rates(rates)
+            }
+            RuntimeCall::Market(sugarfunge_market::Call::deposit {
+                market_id,
+                market_rate_id,
+                ..
+            })
+            | RuntimeCall::Market(sugarfunge_market::Call::exchange_assets {
+                market_id,
+                market_rate_id,
+                ..
+            }) => Market::rate_classes(*market_id, *market_rate_id),
+            _ => vec![],
+        }
+    }
+}
+
+impl sugarfunge_sponsor::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type PalletId = SponsorModuleId;
+    type Currency = Balances;
+    type CallClass = SponsoredCallClass;
+    type MaxSponsoredCalls = MaxSponsoredCalls;
+    type MaxSponsoredClasses = MaxSponsoredClasses;
+}
+
+parameter_types! {
+    pub const AirdropModuleId: PalletId = PalletId(*b"sug/drop");
+    pub const MaxAirdropProofLength: u32 = 32;
+}
+
+impl sugarfunge_airdrop::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type PalletId = AirdropModuleId;
+    type MaxProofLength = MaxAirdropProofLength;
+}
+
+parameter_types! {
+    pub const MaxRecipeAssets: u32 = 16;
+    pub const MaxLootEntries: u32 = 64;
+    pub const MaxLootRolls: u32 = 100;
+}
+
+impl pallet_insecure_randomness_collective_flip::Config for Runtime {}
+
+impl sugarfunge_exgine::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type MaxRecipeAssets = MaxRecipeAssets;
+    type Randomness = RandomnessCollectiveFlip;
+    type MaxLootEntries = MaxLootEntries;
+    type MaxLootRolls = MaxLootRolls;
+}
+
 // Create the runtime by composing the FRAME pallets that were previously configured.
 construct_runtime!(
     pub struct Runtime {
@@ -387,15 +540,20 @@ construct_runtime!(
         TransactionPayment: pallet_transaction_payment,
         Sudo: pallet_sudo,
         Scheduler: pallet_scheduler,
+        RandomnessCollectiveFlip: pallet_insecure_randomness_collective_flip,
         Council: pallet_collective::<Instance1>,
         ValidatorSet: validator_set,
         Session: pallet_session,
         Asset: sugarfunge_asset,
-        // Dao: sugarfunge_dao,
+        Dao: sugarfunge_dao,
         Bundle: sugarfunge_bundle,
         Bag: sugarfunge_bag,
-        // Exgine: sugarfunge_exgine,
+        Exgine: sugarfunge_exgine,
         Market: sugarfunge_market,
+        Rental: sugarfunge_rental,
+        Fee: sugarfunge_fee,
+        Sponsor: sugarfunge_sponsor,
+        Airdrop: sugarfunge_airdrop,
     }
 );
 
@@ -414,7 +572,7 @@ pub type SignedExtra = (
     frame_system::CheckEra<Runtime>,
     frame_system::CheckNonce<Runtime>,
     frame_system::CheckWeight<Runtime>,
-    pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+    sugarfunge_sponsor::ChargeSponsored<Runtime, sugarfunge_fee::ChargeAssetTxPayment<Runtime>>,
 );
 
 /// Unchecked extrinsic type as expected by this runtime.
@@ -429,8 +587,22 @@ pub type Executive = frame_executive::Executive<
     frame_system::ChainContext<Runtime>,
     Runtime,
     AllPalletsWithSystem,
+    Migrations,
 >;
 
+parameter_types! {
+    /// Entries a single block migration may touch, larger ones skip the upgrade
+    pub const MaxMigratedEntries: u32 = 5_000;
+}
+
+/// Runtime migrations, run in order on the next runtime upgrade.
+pub type Migrations = (
+    sugarfunge_asset::migrations::v1::MigrateToV1<Runtime>,
+    sugarfunge_asset::migrations::v2::MigrateToV2<Runtime, MaxMigratedEntries>,
+    sugarfunge_bag::migrations::v1::MigrateToV1<Runtime, MaxMigratedEntries>,
+    sugarfunge_bundle::migrations::v1::MigrateToV1<Runtime, MaxMigratedEntries>,
+);
+
 #[cfg(feature = "runtime-benchmarks")]
 #[macro_use]
 extern crate frame_benchmarking;
@@ -615,6 +787,50 @@ impl_runtime_apis! {
         }
     }
 
+    impl sugarfunge_asset_runtime_api::AssetApi<Block, u64, u64> for Runtime {
+        fn verify_metadata(class_id: u64, asset_id: Option<u64>, content: Vec<u8>) -> bool {
+            Asset::verify_metadata(class_id, asset_id, &content)
+        }
+    }
+
+    impl sugarfunge_bundle_runtime_api::BundleApi<Block, AccountId, u64, u64, Balance> for Runtime {
+        fn bundle_info(
+            bundle_id: sugarfunge_bundle::BundleId,
+        ) -> Option<sugarfunge_bundle_runtime_api::BundleInfo<AccountId, u64, u64, Balance>> {
+            Bundle::bundles(bundle_id).map(|bundle| sugarfunge_bundle_runtime_api::BundleInfo {
+                creator: bundle.creator,
+                class_id: bundle.class_id,
+                asset_id: bundle.asset_id,
+                metadata: bundle.metadata.into_inner(),
+                vault: bundle.vault,
+                class_ids: bundle.schema.class_ids.into_inner(),
+                asset_ids: bundle
+                    .schema
+                    .asset_ids
+                    .into_iter()
+                    .map(|ids| ids.into_inner())
+                    .collect(),
+                amounts: bundle
+                    .schema
+                    .amounts
+                    .into_iter()
+                    .map(|amounts| amounts.into_inner())
+                    .collect(),
+                optional: bundle.schema.optional.into_inner(),
+                native: bundle.schema.native,
+                depth: bundle.depth,
+            })
+        }
+
+        fn bundles_containing(class_id: u64, asset_id: u64) -> Vec<sugarfunge_bundle::BundleId> {
+            Bundle::bundles_containing(class_id, asset_id)
+        }
+
+        fn max_mintable(account: AccountId, bundle_id: sugarfunge_bundle::BundleId) -> Balance {
+            Bundle::max_mintable(&account, bundle_id).unwrap_or_default()
+        }
+    }
+
     #[cfg(feature = "runtime-benchmarks")]
     impl frame_benchmarking::Benchmark<Block> for Runtime {
         fn benchmark_metadata(extra: bool) -> (